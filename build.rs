@@ -0,0 +1,120 @@
+//! Turns every PNG in `assets/` into 2BPP sprite data for `blit`, written to
+//! `$OUT_DIR/sprites.rs` and pulled in by `src/sprites.rs`.
+//!
+//! Pixels must use the cart's palette. Each color becomes the sprite slot
+//! with the same number, so `card.png` drawn in white, red and black blits
+//! slots 1, 2 and 3 of `DRAW_COLORS`:
+//!
+//! | slot | color   | RGB      |
+//! |------|---------|----------|
+//! | 1    | white   | `ffffff` |
+//! | 2    | red     | `c60e0e` |
+//! | 3    | black   | `000000` |
+//! | 4    | yellow  | `ffef00` |
+//!
+//! `assets/king.png` comes out as `KING`, a `Sprite` holding its size and
+//! its data, run-length encoded whenever that comes out smaller. Each
+//! encoded chunk starts with a header byte:
+//!
+//! - `0..=127`: the next `header + 1` bytes are copied as they are.
+//! - `128..=255`: the next byte is repeated `(header & 0x7f) + 3` times.
+//!
+//! `LARGEST` is the most bytes any sprite decodes to, for sizing buffers.
+//! Tests get `ALL` as well, every sprite by its asset's file stem.
+
+use std::{env, fmt::Write as _, fs, path::Path};
+
+const PALETTE: [[u8; 3]; 4] = [
+    [0xff, 0xff, 0xff],
+    [0xc6, 0x0e, 0x0e],
+    [0x00, 0x00, 0x00],
+    [0xff, 0xef, 0x00],
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=assets");
+    let mut paths: Vec<_> = fs::read_dir("assets")
+        .expect("assets folder is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+        .collect();
+    paths.sort();
+
+    let mut out = String::from("// Generated by build.rs from assets/*.png, do not edit.\n");
+    let mut largest = 0;
+    let mut all = String::new();
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let (width, height, data) = convert(&path);
+        largest = largest.max(data.len());
+        let stem = path.file_stem().unwrap().to_str().unwrap();
+        let name = stem.to_uppercase().replace(['-', ' '], "_");
+        write!(all, "({stem:?}, {name}), ").unwrap();
+        let rle = encode(&data);
+        // tiny sprites rarely have runs to pack, so they stay as they are
+        let (packed, data) = if rle.len() < data.len() { (true, rle) } else { (false, data) };
+        writeln!(out, "pub const {name}: Sprite = Sprite {{ width: {width}, height: {height}, packed: {packed}, data: &{data:?} }};").unwrap();
+    }
+    writeln!(out, "const LARGEST: usize = {largest};").unwrap();
+    writeln!(out, "#[cfg(test)]\npub const ALL: &[(&str, Sprite)] = &[{all}];").unwrap();
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("sprites.rs");
+    fs::write(out_path, out).unwrap();
+}
+
+/// Decodes a PNG and packs it four pixels to a byte, first pixel in the high
+/// bits, with rows running on without padding.
+fn convert(path: &Path) -> (u32, u32, Vec<u8>) {
+    let mut decoder = png::Decoder::new(fs::File::open(path).unwrap());
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let channels = info.color_type.samples();
+    let pixels = buf[..info.buffer_size()].chunks(channels).enumerate().map(|(index, pixel)| {
+        let rgb = match pixel.len() {
+            1 | 2 => [pixel[0]; 3],
+            _ => [pixel[0], pixel[1], pixel[2]],
+        };
+        PALETTE.iter().position(|color| *color == rgb).unwrap_or_else(|| {
+            let (x, y) = (index as u32 % info.width, index as u32 / info.width);
+            panic!("{}: pixel ({}, {}) is #{:02x}{:02x}{:02x}, not a palette color", path.display(), x, y, rgb[0], rgb[1], rgb[2])
+        }) as u8
+    });
+    let mut data = vec![0; (info.width * info.height).div_ceil(4) as usize];
+    for (index, slot) in pixels.enumerate() {
+        data[index / 4] |= slot << ((3 - index % 4) * 2);
+    }
+    (info.width, info.height, data)
+}
+
+/// Packs runs of three or more equal bytes into two bytes and gathers
+/// everything else into literal chunks, as described at the top.
+fn encode(data: &[u8]) -> Vec<u8> {
+    fn flush(out: &mut Vec<u8>, literal: &mut Vec<u8>) {
+        if !literal.is_empty() {
+            out.push(literal.len() as u8 - 1);
+            out.append(literal);
+        }
+    }
+    let mut out = Vec::new();
+    let mut literal = Vec::new();
+    let mut index = 0;
+    while index < data.len() {
+        let byte = data[index];
+        let run = data[index..].iter().take(130).take_while(|next| **next == byte).count();
+        if run >= 3 {
+            flush(&mut out, &mut literal);
+            out.push(0x80 | (run - 3) as u8);
+            out.push(byte);
+            index += run;
+        } else {
+            literal.push(byte);
+            index += 1;
+            if literal.len() == 128 {
+                flush(&mut out, &mut literal);
+            }
+        }
+    }
+    flush(&mut out, &mut literal);
+    out
+}
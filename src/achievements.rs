@@ -0,0 +1,60 @@
+use crate::profiles::Profile;
+
+/// Milestones unlocked per profile, stored as bits of `Profile::achievements`.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(u8)]
+pub enum Achievement {
+    FirstHand,
+    Regular,
+    BigWin,
+    HighRoller,
+    Millionaire,
+}
+
+impl Achievement {
+    pub const ALL: [Self; 5] = [
+        Self::FirstHand,
+        Self::Regular,
+        Self::BigWin,
+        Self::HighRoller,
+        Self::Millionaire,
+    ];
+
+    pub fn announcement(&self) -> &'static str {
+        match self {
+            Self::FirstHand => "Trophy: First hand",
+            Self::Regular => "Trophy: Regular",
+            Self::BigWin => "Trophy: Big win",
+            Self::HighRoller => "Trophy: High roller",
+            Self::Millionaire => "Trophy: Millionaire",
+        }
+    }
+
+    pub fn bit(&self) -> u16 {
+        1 << *self as u8
+    }
+
+    fn earned(&self, profile: &Profile, net_winnings: i32) -> bool {
+        let rounds_played: u32 = profile.stats.iter().flatten().map(|stats| stats.rounds_played).sum();
+        match self {
+            Self::FirstHand => rounds_played >= 1,
+            Self::Regular => rounds_played >= 100,
+            Self::BigWin => net_winnings >= 250,
+            Self::HighRoller => profile.bank >= 1_000,
+            Self::Millionaire => profile.bank >= 1_000_000,
+        }
+    }
+
+    /// Unlocks everything `profile` has newly earned after a game that won
+    /// `net_winnings`, returning the first new unlock to announce.
+    pub fn unlock(profile: &mut Profile, net_winnings: i32) -> Option<Self> {
+        let mut unlocked = None;
+        for achievement in Self::ALL {
+            if profile.achievements & achievement.bit() == 0 && achievement.earned(profile, net_winnings) {
+                profile.achievements |= achievement.bit();
+                unlocked = unlocked.or(Some(achievement));
+            }
+        }
+        unlocked
+    }
+}
@@ -0,0 +1,60 @@
+//! Room tone for the tables: a low murmur with the odd shuffle or chip
+//! blip over it, all made on the noise channel. Card sounds share that
+//! channel and simply cut in, and the murmur comes back on its next swell.
+
+use crate::{sfx::{Tone, Volume}, wasm4::*};
+
+pub struct Ambience {
+    /// State of a small LCG, since none of this needs good randomness.
+    seed: u32,
+    /// Frames until the next swell of murmur and the next blip.
+    murmur_left: u32,
+    blip_left: u32,
+}
+
+impl Ambience {
+    pub const fn new() -> Self {
+        Self { seed: 0x2545_f491, murmur_left: 0, blip_left: 90 }
+    }
+
+    /// A number from `0..range`.
+    fn roll(&mut self, range: u32) -> u32 {
+        self.seed = self.seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (self.seed >> 16) % range
+    }
+
+    /// Steps the loop once a frame, only making sound while `on`, at
+    /// `volume` from settings.
+    pub fn update(&mut self, on: bool, volume: Volume) {
+        if !on {
+            self.murmur_left = 0;
+            return
+        }
+        if self.murmur_left == 0 {
+            let (attack, sustain, release) = (20 + self.roll(20) as u8, 10 + self.roll(20) as u8, 20);
+            let frequency = 120 + self.roll(120) as u16;
+            Tone {
+                frequency,
+                slide: frequency + 40,
+                attack,
+                sustain,
+                release,
+                volume: 4 + self.roll(3) as u8,
+                peak: 7,
+                flags: TONE_NOISE,
+                ..Tone::SILENT
+            }
+            .at(volume)
+            .play();
+            // the next swell starts as this one fades
+            self.murmur_left = attack as u32 + sustain as u32 + release as u32 / 2;
+        }
+        self.murmur_left -= 1;
+        if self.blip_left == 0 {
+            let frequency = 1800 + self.roll(1200) as u16;
+            Tone { frequency, release: 2 + self.roll(3) as u8, volume: 6, flags: TONE_NOISE, ..Tone::SILENT }.at(volume).play();
+            self.blip_left = 90 + self.roll(150);
+        }
+        self.blip_left -= 1;
+    }
+}
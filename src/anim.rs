@@ -0,0 +1,87 @@
+//! Frame-based animation. Everything is keyed off a frame counter the game
+//! already keeps, so animations pause, rewind and restore with the game's
+//! own state instead of tracking time on their own.
+
+use crate::timers;
+
+#[derive(Copy, Clone)]
+pub enum Easing {
+    Linear,
+    /// Slow at both ends, quick in the middle.
+    EaseInOut,
+}
+
+impl Easing {
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Self::EaseInOut => 1.0 - 2.0 * (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
+/// A move from one value to another over `duration` frames starting at
+/// frame `start`.
+#[derive(Copy, Clone)]
+pub struct Tween {
+    start: u32,
+    duration: u32,
+    easing: Easing,
+}
+
+impl Tween {
+    pub const fn new(start: u32, duration: u32, easing: Easing) -> Self {
+        Self { start, duration, easing }
+    }
+
+    /// How far along the tween is at `frame`, from 0 before it starts to 1
+    /// once it's done.
+    pub fn progress(&self, frame: u32) -> f32 {
+        if self.duration == 0 {
+            return 1.0
+        }
+        let t = frame.saturating_sub(self.start).min(self.duration) as f32 / self.duration as f32;
+        self.easing.apply(t)
+    }
+
+    pub fn done(&self, frame: u32) -> bool {
+        frame >= self.start + self.duration
+    }
+
+    pub fn lerp(&self, frame: u32, from: i32, to: i32) -> i32 {
+        from + libm::roundf((to - from) as f32 * self.progress(frame)) as i32
+    }
+
+    pub fn lerp_point(&self, frame: u32, from: (i32, i32), to: (i32, i32)) -> (i32, i32) {
+        (self.lerp(frame, from.0, to.0), self.lerp(frame, from.1, to.1))
+    }
+}
+
+/// A fixed list of frames where something happens, in ascending order.
+pub struct Timeline<const N: usize>(pub [u32; N]);
+
+impl<const N: usize> Timeline<N> {
+    /// Cues `seconds` in, at animation speed `speed`.
+    pub fn seconds(seconds: [f32; N], speed: f32) -> Self {
+        Self(seconds.map(|seconds| timers::anim(seconds, speed)))
+    }
+
+    /// The cue landing exactly on `frame`, if any.
+    pub fn cue_at(&self, frame: u32) -> Option<usize> {
+        self.0.iter().position(|cue| *cue == frame)
+    }
+
+    /// The last cue at or before `frame`.
+    pub fn latest(&self, frame: u32) -> Option<usize> {
+        self.0.iter().rposition(|cue| *cue <= frame)
+    }
+
+    pub fn frame(&self, cue: usize) -> u32 {
+        self.0[cue]
+    }
+
+    pub fn end(&self) -> u32 {
+        self.0[N - 1]
+    }
+}
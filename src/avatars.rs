@@ -0,0 +1,56 @@
+use crate::wasm4::*;
+
+/// 8x8 1bpp faces a seat can pick on the lobby screen.
+const AVATARS: [[u8; 8]; 6] = [
+    // smiley
+    [0x3c, 0x42, 0xa5, 0x81, 0xa5, 0x99, 0x42, 0x3c],
+    // cat
+    [0x81, 0xc3, 0xbd, 0x81, 0xa5, 0x81, 0x5a, 0x3c],
+    // star
+    [0x18, 0x18, 0xff, 0x7e, 0x3c, 0x7e, 0x66, 0x42],
+    // heart
+    [0x00, 0x66, 0xff, 0xff, 0xff, 0x7e, 0x3c, 0x18],
+    // crown
+    [0x00, 0x99, 0xdb, 0xff, 0xff, 0xff, 0xff, 0x00],
+    // ghost
+    [0x3c, 0x7e, 0xdb, 0xdb, 0xff, 0xff, 0xff, 0xdb],
+];
+
+/// Draw colors for an avatar: red, black, yellow on black and red on yellow,
+/// so two seats never look alike even with the four-colour palette.
+const COLORS: [u16; 4] = [0x20, 0x30, 0x43, 0x24];
+
+/// The avatar and color a seat shows next to its hands, bets and cursor.
+#[derive(Copy, Clone, Default, PartialEq)]
+pub struct Look {
+    pub avatar: u8,
+    pub color: u8,
+}
+
+impl Look {
+    /// Every seat starts out different from the others.
+    pub const DEFAULTS: [Look; 4] = [
+        Look { avatar: 0, color: 0 },
+        Look { avatar: 1, color: 1 },
+        Look { avatar: 2, color: 2 },
+        Look { avatar: 3, color: 3 },
+    ];
+
+    pub fn next_avatar(&mut self, step: i32) {
+        self.avatar = (self.avatar as i32 + step).rem_euclid(AVATARS.len() as i32) as u8;
+    }
+
+    pub fn next_color(&mut self, step: i32) {
+        self.color = (self.color as i32 + step).rem_euclid(COLORS.len() as i32) as u8;
+    }
+
+    /// Draws the 8x8 avatar, leaving `DRAW_COLORS` as it found it.
+    pub fn draw(&self, x: i32, y: i32) {
+        unsafe {
+            let draw_colors = *DRAW_COLORS;
+            *DRAW_COLORS = COLORS[self.color as usize];
+            blit(&AVATARS[self.avatar as usize], x, y, 8, 8, BLIT_1BPP);
+            *DRAW_COLORS = draw_colors;
+        }
+    }
+}
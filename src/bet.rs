@@ -0,0 +1,71 @@
+use crate::{bigfont, model::Inputs, sfx, wasm4::*};
+
+/// Banks from this size up can pick which digit of the bet to change, since
+/// stepping by tens would take hundreds of presses.
+const DIGIT_ENTRY_BANK: u32 = 1_000;
+/// Digit columns count from the ones. Bets start out moving by tens.
+const DEFAULT_COLUMN: u32 = 1;
+
+/// Picking a bet with the d-pad. Up and down step it by the selected digit
+/// column, and with a large bank left and right move between columns, so a
+/// bet like 4,870 is a few presses away.
+#[derive(Copy, Clone)]
+pub struct BetSelector {
+    column: u32,
+}
+
+impl BetSelector {
+    pub const fn new() -> Self {
+        Self { column: DEFAULT_COLUMN }
+    }
+
+    pub fn digit_entry(bank: u32) -> bool {
+        bank >= DIGIT_ENTRY_BANK
+    }
+
+    /// Moves between the columns the bank has digits for. A bank too small
+    /// for digit entry goes back to stepping by tens.
+    pub fn navigate(&mut self, inputs: &Inputs, bank: u32) {
+        if !Self::digit_entry(bank) {
+            self.column = DEFAULT_COLUMN;
+            return
+        }
+        let top = bank.ilog10();
+        let column = self.column;
+        if inputs.tap_left && self.column < top {
+            self.column += 1;
+        }
+        if inputs.tap_right && self.column > 0 {
+            self.column -= 1;
+        }
+        self.column = self.column.min(top);
+        if self.column != column {
+            sfx::cursor_move();
+        }
+    }
+
+    fn step(&self) -> u32 {
+        10u32.pow(self.column)
+    }
+
+    pub fn raise(&self, bet: u32) -> u32 {
+        bet.saturating_add(self.step())
+    }
+
+    pub fn lower(&self, bet: u32) -> u32 {
+        bet.saturating_sub(self.step())
+    }
+
+    /// Marks the selected column above a bet drawn in the big font with its
+    /// right edge at `right`, in the color the bet was drawn in. Nothing is
+    /// marked on a bet shortened with a K or M.
+    pub fn draw_column(&self, amount: &str, right: i32, y: i32, bank: u32) {
+        if !Self::digit_entry(bank) || amount.ends_with(['K', 'M']) {
+            return
+        }
+        // every glyph is the same width, and a comma comes every third digit
+        let advance = bigfont::width("0");
+        let from_right = (self.column + self.column / 3 + 1) as i32;
+        hline(right - advance * from_right, y - 2, (advance - 2) as u32);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::wasm4::*;
+
+/// Each pixel of a glyph is drawn as a square this many pixels wide.
+const SCALE: i32 = 2;
+/// Space taken by one glyph, gap included.
+const ADVANCE: i32 = 4 * SCALE;
+pub const HEIGHT: i32 = 5 * SCALE;
+
+/// 3x5 glyphs, one row per 3 bits from the top, high bit on the left.
+fn glyph(c: char) -> Option<u16> {
+    let rows: [u16; 5] = match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b011, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '$' => [0b011, 0b110, 0b010, 0b011, 0b110],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        'K' => [0b101, 0b110, 0b100, 0b110, 0b101],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => return None,
+    };
+    Some(rows.iter().fold(0, |bits, row| bits << 3 | row))
+}
+
+pub fn width(text: &str) -> i32 {
+    text.chars().count() as i32 * ADVANCE
+}
+
+/// Draws digits and money punctuation in the large font with the first
+/// `DRAW_COLORS` color. Anything else is skipped as a blank.
+pub fn draw(text: &str, x: i32, y: i32) {
+    unsafe {
+        let draw_colors = *DRAW_COLORS;
+        let color = draw_colors & 0xf;
+        *DRAW_COLORS = color << 4 | color;
+        for (index, c) in text.chars().enumerate() {
+            let Some(bits) = glyph(c) else { continue };
+            for pixel in 0..15 {
+                if bits & (1 << (14 - pixel)) != 0 {
+                    let (column, row) = (pixel % 3, pixel / 3);
+                    rect(x + index as i32 * ADVANCE + column * SCALE, y + row * SCALE, SCALE as u32, SCALE as u32);
+                }
+            }
+        }
+        *DRAW_COLORS = draw_colors;
+    }
+}
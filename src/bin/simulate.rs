@@ -0,0 +1,64 @@
+//! Plays a game automatically, a great many rounds, and reports the house
+//! edge and how results were spread, to check its rules against published
+//! figures. Runs natively:
+//!
+//! ```shell
+//! cargo run --release --target x86_64-unknown-linux-gnu --bin simulate -- --rounds 1000000 --arcade --riffle casino
+//! ```
+
+#[cfg(not(target_family = "wasm"))]
+fn main() {
+    use cart::simulate::{self, Difficulty, Realism, Shuffle, ShuffleStrategy};
+    use std::{env, process};
+
+    let mut rounds = 1_000_000;
+    let mut seed = 1;
+    let mut difficulty = Difficulty::Realistic;
+    let mut shuffle = Shuffle::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = || args.next().and_then(|value| value.parse().ok());
+        match arg.as_str() {
+            "--rounds" => rounds = number().unwrap_or_else(|| usage()),
+            "--seed" => seed = number().unwrap_or_else(|| usage()),
+            "--arcade" => difficulty = Difficulty::Arcade,
+            "--riffle" => {
+                let realism = match args.next().as_deref() {
+                    Some("neat") => Realism::Neat,
+                    Some("casino") => Realism::Casino,
+                    Some("sloppy") => Realism::Sloppy,
+                    _ => usage(),
+                };
+                shuffle = Shuffle { strategy: ShuffleStrategy::Riffle, realism };
+            }
+            _ => usage(),
+        }
+    }
+
+    fn usage() -> ! {
+        eprintln!("usage: simulate [--rounds N] [--seed N] [--arcade] [--riffle neat|casino|sloppy]");
+        process::exit(2)
+    }
+
+    // a bet of 10 keeps 6:5 and 3:2 payouts whole
+    let report = simulate::blackjack(rounds, seed, 10, difficulty, shuffle).unwrap_or_else(|error| {
+        eprintln!("round failed: {:?}", error);
+        process::exit(1)
+    });
+    let shuffled = match shuffle.strategy {
+        ShuffleStrategy::FisherYates => "machine shuffled".to_string(),
+        ShuffleStrategy::Riffle => format!("{} riffles", shuffle.realism.name().to_lowercase()),
+    };
+    println!("blackjack, {} odds, {}, {} rounds", difficulty.name(), shuffled, report.rounds);
+    println!("house edge  {:.3}%", report.house_edge() * 100.0);
+    println!("std dev     {:.3} bets", report.std_dev());
+    println!("wagered     {:.3} bets a round", report.wagered as f64 / (report.rounds * report.bet as u64) as f64);
+    println!();
+    for (net, count) in &report.outcomes {
+        println!("{:>+6.1} bets  {:>6.2}%", *net as f64 / report.bet as f64, *count as f64 * 100.0 / report.rounds as f64);
+    }
+}
+
+// the simulator only makes sense off the console
+#[cfg(target_family = "wasm")]
+fn main() {}
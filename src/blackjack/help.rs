@@ -0,0 +1,67 @@
+//! Rules pages for the pause menu, shared by every mode played at a
+//! blackjack table.
+
+use super::{Card, CardSuit, CardValue};
+use crate::{help::{Diagram, Page}, ui::Display, wasm4::*};
+
+/// Lays out cards left to right with a caption under each.
+fn draw_labelled(cards: &[(CardValue, CardSuit, &str)], y: i32, display: Display) {
+    for (index, (value, suit, label)) in cards.iter().enumerate() {
+        let x = 8 + 50 * index as i32;
+        Card { value: *value, suit: *suit }.draw_large(x + 8, y, true, display);
+        unsafe { *DRAW_COLORS = 0x03; }
+        text(label, x, y + 24);
+    }
+}
+
+fn draw_twenty_one(y: i32, display: Display) {
+    Card { value: CardValue::Ace, suit: CardSuit::Spade }.draw_large(60, y, true, display);
+    Card { value: CardValue::King, suit: CardSuit::Heart }.draw_large(78, y, true, display);
+    unsafe { *DRAW_COLORS = 0x03; }
+    text("= 21", 98, y + 6);
+}
+
+fn draw_values(y: i32, display: Display) {
+    draw_labelled(
+        &[
+            (CardValue::Seven, CardSuit::Club, "7"),
+            (CardValue::Queen, CardSuit::Diamond, "10"),
+            (CardValue::Ace, CardSuit::Heart, "1/11"),
+        ],
+        y,
+        display,
+    );
+}
+
+fn draw_pair(y: i32, display: Display) {
+    Card { value: CardValue::Eight, suit: CardSuit::Club }.draw_large(60, y, true, display);
+    Card { value: CardValue::Eight, suit: CardSuit::Diamond }.draw_large(84, y, true, display);
+}
+
+pub const RULES: [Page; 5] = [
+    Page {
+        title: "Blackjack",
+        diagram: Some(Diagram { height: 21, draw: draw_twenty_one }),
+        text: b"Get closer to 21 than the dealer without going over. Going over is a bust and loses the bet. An ace and a ten-card dealt together are a blackjack.",
+    },
+    Page {
+        title: "Card values",
+        diagram: Some(Diagram { height: 32, draw: draw_values }),
+        text: b"Number cards count as their number. Jacks, queens and kings count 10. An ace counts 1 or 11, whichever is better.",
+    },
+    Page {
+        title: "Your turn",
+        diagram: Some(Diagram { height: 21, draw: draw_pair }),
+        text: b"Hit takes a card and Stand keeps your total. Double doubles the bet for exactly one more card. A pair can be split into two hands, each with its own bet, up to four hands.",
+    },
+    Page {
+        title: "The dealer",
+        diagram: None,
+        text: b"The dealer draws until reaching 17. On Realistic odds the dealer hits soft 17, blackjack pays 6:5 and you can only double on 10 or 11. Arcade odds pay 3:2 and let you double on any two cards.",
+    },
+    Page {
+        title: "Insurance",
+        diagram: None,
+        text: b"When the dealer shows an ace, insurance costs half your bet and pays 2:1 if the dealer has blackjack. \x80 buys it and \x81 declines.",
+    },
+];
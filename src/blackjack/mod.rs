@@ -1,55 +1,42 @@
-use std::fmt;
-
-use crate::{model::Model, wasm4::*, PlayerState};
-use fastrand::Rng;
-
-
-fn buzz() {
-    tone(140, 6, 40, 0);
-}
-
-struct Button {
-    text: &'static str,
-    disabled: bool
-}
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-#[repr(u8)]
-pub enum CardValue {
-    Ace = 1,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King
-}
-
-impl fmt::Display for CardValue {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", match self {
-            Self::Ace => "A",
-            Self::Two => "2",
-            Self::Three => "3",
-            Self::Four => "4",
-            Self::Five => "5",
-            Self::Six => "6",
-            Self::Seven => "7",
-            Self::Eight => "8",
-            Self::Nine => "9",
-            Self::Ten => "T",
-            Self::Jack => "J",
-            Self::Queen => "Q",
-            Self::King => "K",
-        })
-    }
-}
+use alloc::vec::Vec;
+
+mod help;
+#[cfg(not(target_family = "wasm"))]
+pub(crate) mod simulate;
+mod snapshot;
+#[cfg(debug_assertions)]
+mod stack;
+mod tutorial;
+
+use crate::{
+    anim::{Easing, Timeline, Tween},
+    bet::BetSelector,
+    events::Promotion,
+    fixed::FixedVec,
+    bigfont,
+    cards::{Card, CardSuit, CardValue},
+    chips,
+    dealer::{self, Expression},
+    effects::ScreenEffect,
+    fsm::{Machine, State, Transition},
+    model::{point_in, GameError, GameEvent, Inputs, Model},
+    money,
+    music::{self, Track},
+    particles::{Burst, Effect},
+    rng::{GameRng, Rng},
+    rules::TableRules,
+    sfx,
+    shuffle::Shuffle,
+    timers::{self, Interval},
+    tutorial::Tutorial,
+    sprites,
+    layout::{self, Align},
+    ui::{Button, ButtonGrid, Display, Label, Modal, Style},
+    wasm4::*,
+    PlayerState,
+};
+
+pub use help::RULES;
 
 impl CardValue {
     fn equal_to(&self, other: &Self) -> bool {
@@ -65,81 +52,9 @@ impl CardValue {
     }
 }
 
-impl CardValue {
-    fn values() -> [Self; 13] {
-        use CardValue::*;
-        [Ace, Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Jack, Queen, King]
-    }
-}
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum CardSuit {
-    Club,
-    Diamond,
-    Heart,
-    Spade,
-}
-
-impl CardSuit {
-    fn suits() -> [Self; 4] {
-        use CardSuit::*;
-        [Club, Diamond, Heart, Spade]
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct Card {
-    pub value: CardValue,
-    pub suit: CardSuit,
-}
-
 impl Card {
-    fn draw_sprite(&self, x: i32, y: i32, face_up: bool) {
-        let card_sprite = [5, 85, 64, 106, 170, 69, 170, 169, 86, 170, 165, 90, 170, 149, 106, 170, 85, 170, 169, 86, 170, 165, 90, 170, 149, 106, 170, 85, 170, 169, 86, 170, 165, 90, 170, 149, 106, 170, 81, 170, 169, 1, 85, 80];
-        if face_up {
-            unsafe {
-                *DRAW_COLORS = 0x0130;
-            }
-            blit(&card_sprite, x, y, 11, 16, BLIT_2BPP);
-            match self.suit {
-                CardSuit::Club | CardSuit::Spade => {
-                    unsafe {
-                        *DRAW_COLORS |= 0x3000;
-                    }
-                }
-                CardSuit::Diamond | CardSuit::Heart => {
-                    unsafe {
-                        *DRAW_COLORS |= 0x2000;
-                    }
-                }
-            }
-            let value_sprite = match self.value {
-                CardValue::Ace => [60, 195, 195, 255, 195],
-                CardValue::Two => [60, 195, 3, 12, 63],
-                CardValue::Three => [255, 3, 63, 3, 255],
-                CardValue::Four => [3, 15, 51, 255, 3],
-                CardValue::Five => [255, 192, 252, 3, 252],
-                CardValue::Six => [63, 192, 252, 195, 252],
-                CardValue::Seven => [255, 15, 12, 48, 48],
-                CardValue::Eight => [60, 195, 60, 195, 60],
-                CardValue::Nine => [60, 195, 63, 3, 3],
-                CardValue::Ten => [255, 12, 12, 12, 12],
-                CardValue::Jack => [255, 12, 12, 204, 48],
-                CardValue::Queen => [48, 204, 204, 204, 51],
-                CardValue::King => [195, 204, 240, 204, 195]
-            };
-            blit(&value_sprite, x + 3, y + 2, 4, 5, BLIT_2BPP);
-            // draw icon underneath
-        } else {
-            unsafe {
-                *DRAW_COLORS = 0x0430;
-            }
-            blit(&card_sprite, x, y, 11, 16, BLIT_2BPP);
-        }
-    }
-
-    fn new_shuffled_horn(rng: &Rng) -> Vec<Self> {
-        let mut horn = Vec::with_capacity(7 * 52);
+    fn new_shuffled_horn(rng: &mut impl GameRng, shuffle: Shuffle) -> FixedVec<Self, SHOE_CARDS> {
+        let mut horn = FixedVec::new();
         for _ in 0..7 {
             for suit in CardSuit::suits() {
                 for value in CardValue::values() {
@@ -147,22 +62,66 @@ impl Card {
                 }
             }
         }
-        rng.shuffle(&mut horn);
+        // cards come back from the discard tray in no order the table
+        // keeps, so the shoe is shuffled as if fresh
+        shuffle.apply_fresh(rng, &mut horn);
         horn
     }
 }
 
-#[derive(Clone)]
+/// Cards in a full shoe of seven decks.
+const SHOE_CARDS: usize = 7 * 52;
+/// The most cards a hand can reach before it's bust: twenty-one aces, then
+/// one more.
+const MAX_HAND_CARDS: usize = 22;
+/// Splitting stops once the player has this many hands.
+const MAX_HANDS: usize = 4;
+
+#[derive(Copy, Clone)]
 pub struct Hand {
-    pub cards: Vec<Card>
+    /// Only changed through `push` and `pop`, which keep the totals below
+    /// up to date.
+    cards: FixedVec<Card, MAX_HAND_CARDS>,
+    /// Set once the hand has been doubled down, putting twice the bet on it.
+    pub doubled: bool,
+    /// Every ace counted as 1.
+    hard: u8,
+    has_ace: bool,
 }
 
 impl Hand {
     fn new() -> Self {
         Self {
-            cards: Vec::with_capacity(4),
+            cards: FixedVec::new(),
+            doubled: false,
+            hard: 0,
+            has_ace: false,
         }
     }
+
+    fn push(&mut self, card: Card) {
+        self.cards.push(card);
+        self.hard += match card.value {
+            CardValue::Ace => 1,
+            CardValue::Ten | CardValue::Jack | CardValue::Queen | CardValue::King => 10,
+            other => other as u8,
+        };
+        self.has_ace |= card.value == CardValue::Ace;
+    }
+
+    /// Takes back the last card, as a split does. Rare enough that the
+    /// totals are just counted again.
+    fn pop(&mut self) -> Option<Card> {
+        let card = self.cards.pop()?;
+        let cards = self.cards;
+        *self = Self { doubled: self.doubled, ..Self::new() };
+        cards.iter().for_each(|card| self.push(*card));
+        Some(card)
+    }
+
+    fn stake(&self, bet: u32) -> u32 {
+        if self.doubled { bet * 2 } else { bet }
+    }
 }
 
 impl Hand {
@@ -170,60 +129,52 @@ impl Hand {
         self.cards[1].value == CardValue::Ace 
     }
 
-    fn points(&self) -> Vec<u8> {
-        use CardValue::*;
-        let mut pts = vec![];
-        let mut sum = 0;
-        for card in self.cards.iter() {
-            match card.value {
-                Ace => {
-                    sum += 1;
-                }
-                Ten | Jack | Queen | King => {
-                    sum += 10;
-                }
-                other => {
-                    sum += other as u8;
-                }
-            }
-        }
-        pts.push(sum);
-        for card in self.cards.iter() {
-            if Ace == card.value {
-                for prev_pt in pts.clone() {
-                    pts.push(prev_pt + 10)
-                }
-            }
-        }
-        pts
+    /// The total with every ace counted as 1.
+    fn hard_total(&self) -> u8 {
+        self.hard
     }
 
-    fn dealer_must_hit(&self) -> bool {
-        for pt in self.points() {
-            if 17 <= pt && pt <= 21 {
-                return false
-            }
-        }
-        true
+    /// Whether an ace can count as 11 without busting. Only one ever can,
+    /// since two would make at least 22.
+    fn is_soft(&self) -> bool {
+        self.has_ace && self.hard + 10 <= 21
+    }
+
+    /// The soft total if there is one, the hard total otherwise.
+    fn best_total(&self) -> u8 {
+        if self.is_soft() { self.hard + 10 } else { self.hard }
     }
 
     fn is_bust(&self) -> bool {
-        self.points().into_iter().all(|pt| pt > 21)
+        self.hard > 21
+    }
+
+    fn dealer_must_hit(&self, hits_soft_17: bool) -> bool {
+        match self.best_total() {
+            // a soft 17 counts an ace as 11
+            17 => hits_soft_17 && self.is_soft(),
+            total => total < 17,
+        }
     }
 
     fn is_blackjack(&self) -> bool {
-        self.cards.len() == 2 && self.points().into_iter().any(|pt| pt == 21)
+        self.cards.len() == 2 && self.best_total() == 21
     }
 
-    fn showdown_result(&self, dealer_hand: Option<&Self>) -> HandResult {
+    /// How the hand did against the dealer's. A natural only ties with
+    /// another natural, and beats or loses to anything else.
+    fn showdown_result(&self, dealer_hand: &Self) -> HandResult {
         if self.is_blackjack() {
-            HandResult::BlackJack
-        } else if self.is_bust() {
+            if dealer_hand.is_blackjack() {
+                HandResult::Push
+            } else {
+                HandResult::BlackJack
+            }
+        } else if self.is_bust() || dealer_hand.is_blackjack() {
             HandResult::Lose
         } else {
-            let player_points = self.points().into_iter().filter(|pt| *pt <= 21).max();
-            let dealer_hand = dealer_hand.unwrap();
-            let dealer_points = dealer_hand.points().into_iter().filter(|pt| *pt <= 21).max();
+            let player_points = (!self.is_bust()).then(|| self.best_total());
+            let dealer_points = (!dealer_hand.is_bust()).then(|| dealer_hand.best_total());
             match (player_points, dealer_points) {
                 (Some(pp), Some(dp)) => {
                     if pp == dp {
@@ -234,12 +185,8 @@ impl Hand {
                         HandResult::Win
                     }
                 }
-                (Some(pp), None) => {
-                    if pp != 21 {
-                        HandResult::Win
-                    } else {
-                        HandResult::BlackJack
-                    }
+                (Some(_), None) => {
+                    HandResult::Win
                 }
                 (None, Some(_))
                 | (None, None) => {
@@ -253,40 +200,48 @@ impl Hand {
         self.cards.len() == 2 && self.cards[0].value.equal_to(&self.cards[1].value)
     }
 
-    fn can_double_down(&self) -> bool {
-        self.cards.len() == 2 && self.points().into_iter().any(|pt| pt == 10 || pt == 11)
+    fn can_double_down(&self, any_two: bool) -> bool {
+        self.cards.len() == 2 && (any_two || matches!(self.hard_total(), 10 | 11))
     }
 }
 
 struct PlayingState {
-    hit_button: Button,
-    stand_button: Button,
-    split_button: Button,
-    double_down_button: Button,
-    button_index: usize,  // 0: hit, 1: stand, 2: split, 3: double_down
+    buttons: ButtonGrid<4>,  // 0: hit, 1: stand, 2: split, 3: double_down
     dealer_hand: Hand,
-    player_hands: Vec<Hand>,
+    player_hands: FixedVec<Hand, MAX_HANDS>,
     player_hand_index: usize,
+    /// Stand or double down waiting out its undo window: the button index
+    /// and frames left before it goes through.
+    pending: Option<(usize, u8)>,
 }
 
 impl PlayingState {
     fn new(dealer_hand: Hand, player_hand: Hand) -> Self {
         Self {
-            hit_button: Button { text: "Hit", disabled: false },
-            stand_button: Button { text: "Stand", disabled: false },
-            split_button: Button { text: "Split", disabled: true },
-            // surrender_button: Button { text: "Surrender", disabled: true },
-            double_down_button: Button { text: "Double Down", disabled: true },
-            button_index: 0,
+            // laid out two by two in the input bar
+            buttons: ButtonGrid::new(
+                [
+                    Button::new("Hit"),
+                    Button::new("Stand"),
+                    Button { text: "Split", disabled: true },
+                    // Button { text: "Surrender", disabled: true },
+                    Button { text: "Double Down", disabled: true },
+                ],
+                2,
+                (2, 142),
+                (60, 9),
+                58,
+                Style::BAR,
+            ),
             dealer_hand: dealer_hand,
-            player_hands: vec![
-                player_hand
-            ],
+            player_hands: [player_hand].into_iter().collect(),
             player_hand_index: 0,
+            pending: None,
         }
     }
 }
 
+#[derive(Copy, Clone)]
 enum HandResult {
     Lose,
     Win,
@@ -296,10 +251,63 @@ enum HandResult {
 
 struct EndState {
     dealer_hand: Hand,
-    player_hands: Vec<(Hand, HandResult)>,
+    player_hands: FixedVec<(Hand, HandResult), MAX_HANDS>,
     bought_insurance: bool,
 }
 
+impl EndState {
+    /// What the table hands back on a bet of `bet` a hand, stakes included.
+    fn returns(&self, bet: u32, rules: TableRules, promotion: Option<Promotion>) -> u32 {
+        // the insurance stake back and 2:1 on it
+        let insurance = if self.bought_insurance { insurance_stake(bet) * 3 } else { 0 };
+        let hands = self.player_hands.iter().map(|(hand, result)| {
+            let stake = hand.stake(bet);
+            match result {
+                HandResult::BlackJack => Promotion::blackjack_winnings(promotion, rules.blackjack_winnings(stake)) + stake,
+                HandResult::Lose => 0,
+                HandResult::Push => stake,
+                HandResult::Win => stake * 2,
+            }
+        });
+        insurance + hands.sum::<u32>()
+    }
+
+    /// Pleased for any winning hand, let down if every hand lost.
+    fn dealer_expression(&self) -> Expression {
+        let results = || self.player_hands.iter().map(|(_, result)| result);
+        if results().any(|result| matches!(result, HandResult::Win | HandResult::BlackJack)) {
+            Expression::Congratulating
+        } else if results().all(|result| matches!(result, HandResult::Lose)) {
+            Expression::Disappointed
+        } else {
+            Expression::Neutral
+        }
+    }
+}
+
+/// Seconds between each of the opening cards going out, dealer first and
+/// alternating, with a fifth beat handing over to play.
+const DEAL_GAP: f32 = 1.0 / 6.0;
+/// Seconds a dealt card takes to slide from the shoe to its spot.
+const SLIDE_SECONDS: f32 = 0.13;
+/// Where dealt cards slide in from, off the dealer's left hand.
+const SHOE: (i32, i32) = (130, 40);
+/// Seconds between each card the dealer draws to their hand.
+const DEALER_HIT_SECONDS: f32 = 0.5;
+
+/// Frames at which the opening cards go out, at the animation speed.
+fn deal(speed: f32) -> Timeline<5> {
+    Timeline::seconds([1.0, 2.0, 3.0, 4.0, 5.0].map(|beat| beat * DEAL_GAP), speed)
+}
+
+fn slide_frames(speed: f32) -> u32 {
+    timers::anim(SLIDE_SECONDS, speed)
+}
+
+fn dealer_hit(speed: f32) -> Interval {
+    Interval::new(timers::anim(DEALER_HIT_SECONDS, speed))
+}
+
 struct DealingState {
     frame: u8,
     dealer_hand: Hand,
@@ -317,7 +325,7 @@ impl DealingState {
 }
 
 struct DealerResolvingState {
-    player_hands: Vec<Hand>,
+    player_hands: FixedVec<Hand, MAX_HANDS>,
     dealer_hand: Hand,
     frame_count: u64,
 }
@@ -345,104 +353,337 @@ enum BlackJackState {
     End(EndState),
 }
 
-fn draw_card(horn: &mut Vec<Card>, rng: &Rng) -> Card {
-    if let Some(card) = horn.pop() {
-        card
-    } else {
-        *horn = Card::new_shuffled_horn(rng);
-        horn.pop().unwrap()
+/// The cards left to deal. The shuffle is derived from `seed` so a shoe can
+/// be rebuilt from the seed, how it was shuffled and the number of cards
+/// remaining.
+struct Shoe {
+    cards: FixedVec<Card, SHOE_CARDS>,
+    seed: u64,
+    shuffle: Shuffle,
+}
+
+impl Shoe {
+    fn new(rng: &mut impl GameRng, shuffle: Shuffle) -> Self {
+        Self::with_seed(rng.new_seed(), shuffle, SHOE_CARDS)
+    }
+
+    fn with_seed(seed: u64, shuffle: Shuffle, remaining: usize) -> Self {
+        let mut cards = Card::new_shuffled_horn(&mut Rng::with_seed(seed), shuffle);
+        cards.truncate(remaining);
+        Self { cards, seed, shuffle }
+    }
+
+    /// Shuffles the next shoe with `shuffle`, and this one too if nothing
+    /// has been dealt from it yet.
+    fn set_shuffle(&mut self, shuffle: Shuffle) {
+        if shuffle == self.shuffle {
+            return
+        }
+        if self.cards.len() == SHOE_CARDS {
+            *self = Self::with_seed(self.seed, shuffle, SHOE_CARDS);
+        }
+        self.shuffle = shuffle;
+    }
+
+    /// Takes the top card, shuffling a fresh shoe once this one runs out.
+    fn draw(&mut self, rng: &mut impl GameRng) -> Result<Card, GameError> {
+        if self.cards.is_empty() {
+            *self = Self::new(rng, self.shuffle);
+        }
+        self.cards.pop().ok_or(GameError::OutOfCards)
     }
 }
 
-pub struct BlackJack {
-    horn: Vec<Card>,
+/// Insurance costs half the bet, rounded down.
+fn insurance_stake(bet: u32) -> u32 {
+    bet / 2
+}
+
+/// What an open modal is asking about.
+enum Confirm {
+    Quit,
+    AllIn,
+}
+
+/// Everything at the table that outlasts a hand: the chips, the shoe and
+/// whatever is waiting to be picked up by the menu. The hand in play is the
+/// `BlackJackState`.
+struct Table {
+    horn: Shoe,
     player_bet: u32,
+    bet_selector: BetSelector,
     total_bet: u32,
     player_bank: u32,
-    state: BlackJackState,
+    rounds: u32,
+    shared_state: PlayerState,
     rng: Rng,
+    /// Playing the lesson, with practice chips instead of the bank.
+    practice: bool,
+    modal: Option<(Modal, Confirm)>,
+    /// Confetti for a natural, waiting to be picked up by the menu.
+    burst: Option<Burst>,
+    /// A shake for a bust, waiting to be picked up by the menu.
+    screen_effect: Option<ScreenEffect>,
+    /// A fanfare for a natural, waiting to be sent to the menu.
+    sting: Option<&'static Track>,
 }
 
-impl BlackJack {
-    pub fn new(random_seed: u64) -> Box<dyn Model<PlayerState>> {
-        let rng = Rng::with_seed(random_seed);
-        Box::new(Self {
-            horn: Card::new_shuffled_horn(&rng),
+impl Table {
+    fn new(random_seed: u64) -> Self {
+        let mut rng = Rng::with_seed(random_seed);
+        Self {
+            horn: Shoe::new(&mut rng, Shuffle::default()),
             player_bank: 0,
+            rounds: 0,
+            shared_state: PlayerState::default(),
             player_bet: 0,
+            bet_selector: BetSelector::new(),
             total_bet: 0,
-            state: BlackJackState::Betting,
-            rng
-        })
+            rng,
+            practice: false,
+            modal: None,
+            burst: None,
+            screen_effect: None,
+            sting: None,
+        }
+    }
+
+    fn rules(&self) -> TableRules {
+        self.shared_state.settings.difficulty.rules()
+    }
+
+    fn exit_state(&self) -> PlayerState {
+        // practice chips stay at the practice table
+        if self.practice {
+            return self.shared_state
+        }
+        let mut state = PlayerState {
+            rounds: self.rounds,
+            ..self.shared_state
+        };
+        state.set_bank(self.player_bank);
+        state
+    }
+}
+
+pub struct BlackJack {
+    table: Table,
+    state: Machine<BlackJackState>,
+    /// Mouse position from the last update, for hover highlighting.
+    mouse: (i32, i32),
+    /// Why the table was just abandoned, waiting to be picked up by the menu.
+    error: Option<GameError>,
+    /// The lesson being played over the table.
+    tutorial: Option<Tutorial<BlackJack>>,
+    /// The deck-stacking screen, open over the table.
+    #[cfg(debug_assertions)]
+    stacker: Option<stack::StackScreen>,
+}
+
+impl BlackJack {
+    pub fn new(random_seed: u64) -> Self {
+        Self {
+            table: Table::new(random_seed),
+            state: Machine::new(BlackJackState::Betting),
+            mouse: (0, 0),
+            error: None,
+            tutorial: None,
+            #[cfg(debug_assertions)]
+            stacker: None,
+        }
+    }
+
+    /// The table with a lesson scripted over it.
+    pub fn lesson(random_seed: u64) -> Self {
+        let mut game = Self::new(random_seed);
+        game.table.horn = tutorial::stacked_shoe();
+        game.table.practice = true;
+        game.tutorial = Some(tutorial::lesson());
+        game
+    }
+
+    fn exit_state(&self) -> PlayerState {
+        self.table.exit_state()
     }
 }
 
-const BET_INCREMENT: u32 = 10;
 const MINIMUM_BET: u32 = 10;
+/// Frames a stand or double down can still be taken back with Z.
+const UNDO_FRAMES: u8 = timers::secs(0.75) as u8;
+
+// mouse targets: top left corners, plus width for the wider ones
+const BET_DOWN: (i32, i32) = (128, 37);
+const BET_UP: (i32, i32) = (144, 37);
+const MAKE_BET: (i32, i32, i32) = (0, 151, 96);
+
+
 
 
+/// An amount for the big font, shortened once it would crowd the other one.
+fn big_amount(amount: u32) -> money::Amount {
+    let full = money::chips(amount);
+    if bigfont::width(&full) > 64 { money::compact(amount) } else { full }
+}
+
+fn dealer_card_position(index: usize) -> (i32, i32) {
+    ((60 + index * 14) as _, 67)
+}
+
+fn player_hand_x(hand_index: usize, num_hands: usize) -> usize {
+    let space_size = 160 / num_hands;
+    space_size * (hand_index + 1) - space_size * 2 / 3
+}
 
+/// Draws `card` partway along its slide from the shoe to `to`.
+fn draw_sliding_card(card: &Card, to: (i32, i32), face_up: bool, tween: Tween, frame: u32, display: Display) {
+    let (x, y) = tween.lerp_point(frame, SHOE, to);
+    card.draw_sprite(x, y, face_up, display);
+}
 
-fn display_cards(dealer_hand: &Hand, player_hands: &[&Hand], active_player_hand_index: usize, showdown: bool) {
+fn display_cards<'a>(dealer_hand: &Hand, player_hands: impl ExactSizeIterator<Item = &'a Hand>, active_player_hand_index: usize, showdown: bool, display: Display) {
     for (index, card) in dealer_hand.cards.iter().enumerate() {
         let x = (60 + index * 14) as _;
         let y = 67;
         let face_up = !(!showdown && index == 0);
-        card.draw_sprite(x, y, face_up)
+        card.draw_sprite(x, y, face_up, display)
     }
     let num_hands = player_hands.len();
-    for (hand_index, hand) in player_hands.iter().enumerate() {
+    for (hand_index, hand) in player_hands.enumerate() {
         let space_size = 160 / num_hands;
         let x = space_size * (hand_index + 1) - space_size * 2 / 3;
         if hand_index == active_player_hand_index {
             unsafe {
                 *DRAW_COLORS = 0x0040;
             }
-            blit(&[4, 1, 5, 84, 84, 4, 0], x as _, 90, 5, 5, BLIT_2BPP);
+            sprites::ARROW.blit(x as _, 90);
         }
         for (card_index, card) in hand.cards.iter().enumerate() {
             let x = x + card_index * 14; // if this is > 160 - sprite width go to next row
             let y = 97;
-            card.draw_sprite(x as _, y, true);
+            card.draw_sprite(x as _, y, true, display);
         }
     }
 }
 
-impl Model<PlayerState> for BlackJack {
-    fn update(&mut self, inputs: [crate::model::Inputs; 4]) -> Option<PlayerState> {
-        let player_one_inputs = inputs[0];
+impl BlackJack {
+    /// A frame of play, stopping short with an error if the table gets
+    /// into a state it can't carry on from.
+    fn step(&mut self, inputs: [crate::model::Inputs; 4]) -> Result<GameEvent<PlayerState>, GameError> {
+        let mut player_one_inputs = inputs[0];
+        if let Some(mut tutorial) = self.tutorial.take() {
+            let finished = tutorial.finished();
+            if !finished {
+                player_one_inputs = tutorial.filter(self, &player_one_inputs);
+            }
+            self.tutorial = Some(tutorial);
+            if finished {
+                return Ok(GameEvent::SwitchTo { state: self.exit_state(), game: tutorial::NEXT_GAME })
+            }
+        }
+        self.mouse = player_one_inputs.mouse();
+        #[cfg(debug_assertions)]
+        if self.stack_shoe(&player_one_inputs) {
+            return Ok(GameEvent::Continue)
+        }
+        if let Some((modal, confirm)) = &mut self.table.modal {
+            match (modal.update(&player_one_inputs), confirm) {
+                (Some(true), Confirm::Quit) => {
+                    self.table.modal = None;
+                    return Ok(GameEvent::Exit { state: self.exit_state() })
+                }
+                (Some(true), Confirm::AllIn) => {
+                    self.table.modal = None;
+                    self.state.go(&mut self.table, BlackJackState::Dealing(DealingState::new()));
+                }
+                (Some(false), _) => self.table.modal = None,
+                (None, _) => {}
+            }
+            return Ok(GameEvent::Continue)
+        }
+        let settled = matches!(self.state.current(), BlackJackState::End(_));
+        if let Some(()) = self.state.update(&mut self.table, &player_one_inputs)? {
+            return Ok(GameEvent::Exit { state: self.exit_state() })
+        }
+        if let Some(sting) = self.table.sting.take() {
+            return Ok(GameEvent::PlaySfx(sting))
+        }
+        // the bank is kept as it stands once the next round is being bet
+        if settled && matches!(self.state.current(), BlackJackState::Betting) {
+            return Ok(GameEvent::RequestSave)
+        }
+        Ok(GameEvent::Continue)
+    }
+
+    /// Gives back everything bet on the table and leaves for the menu.
+    fn abandon(&mut self, error: GameError) -> PlayerState {
+        if self.bet_live() {
+            self.table.player_bank += self.table.total_bet;
+        }
+        self.table.player_bet = 0;
+        self.table.total_bet = 0;
+        // back to betting without leaving the hand in play, so no hooks
+        self.state = Machine::new(BlackJackState::Betting);
+        self.error = Some(error);
+        self.exit_state()
+    }
+}
+
+impl State for BlackJackState {
+    type Context = Table;
+    type Input = Inputs;
+    /// Leaving the table.
+    type Output = ();
+    type Error = GameError;
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Betting => "Betting",
+            Self::Dealing(_) => "Dealing",
+            Self::Insurance(_) => "Insurance",
+            Self::Playing(_) => "Playing",
+            Self::DealerResolving(_) => "DealerResolving",
+            Self::End(_) => "End",
+        }
+    }
+
+    fn update(&mut self, table: &mut Table, player_one_inputs: &Inputs) -> Result<Transition<Self>, GameError> {
+        let rules = table.rules();
         match self {
-            Self { state: BlackJackState::Betting, .. } => {
+            Self::Betting => {
                 if player_one_inputs.tap_z {
-                    return Some(PlayerState { bank: self.player_bank })
+                    table.modal = Some((Modal::new("Leave the table?"), Confirm::Quit));
+                    return Ok(Transition::Stay)
                 }
-                if self.player_bank < MINIMUM_BET {
+                let minimum_bet = Promotion::minimum_bet(table.shared_state.promotion, MINIMUM_BET);
+                if table.player_bank < minimum_bet || table.exit_state().loss_limit_reached() {
                     if player_one_inputs.tap_x {
-                        buzz();
+                        sfx::error();
                     }
                 } else {
-                    // buttons for changing bet amount
-                    if player_one_inputs.tap_up {
-                        self.player_bet = self.player_bet.saturating_add(BET_INCREMENT)
-                    } else if player_one_inputs.tap_down {
-                        self.player_bet = self.player_bet.saturating_sub(BET_INCREMENT);
+                    // buttons for changing bet amount, by the digit picked
+                    // with left and right once the bank is large
+                    table.bet_selector.navigate(player_one_inputs, table.player_bank);
+                    if player_one_inputs.tap_up || player_one_inputs.clicked(BET_UP.0, BET_UP.1, 8, 8) {
+                        table.player_bet = table.bet_selector.raise(table.player_bet)
+                    } else if player_one_inputs.tap_down || player_one_inputs.clicked(BET_DOWN.0, BET_DOWN.1, 8, 8) {
+                        table.player_bet = table.bet_selector.lower(table.player_bet);
                     }
-                    self.player_bet = self.player_bet.max(MINIMUM_BET);
-                    self.player_bet = self.player_bet.min(self.player_bank);
+                    table.player_bet = table.player_bet.max(minimum_bet);
+                    table.player_bet = table.player_bet.min(table.player_bank);
 
                     // buttons for making bet
-                    if player_one_inputs.tap_x {
-                        if self.player_bet > self.player_bank {
-                            buzz();
+                    if player_one_inputs.tap_x || player_one_inputs.clicked(MAKE_BET.0, MAKE_BET.1, MAKE_BET.2, 9) {
+                        if table.player_bet > table.player_bank {
+                            sfx::error();
+                        } else if table.player_bet == table.player_bank {
+                            table.modal = Some((Modal::new("Bet everything?"), Confirm::AllIn));
                         } else {
-                            self.player_bank -= self.player_bet;
-                            self.total_bet = self.player_bet;
-                            self.state = BlackJackState::Dealing(DealingState::new());
+                            return Ok(Transition::To(Self::Dealing(DealingState::new())))
                         }
                     }
                 }
             }
-            Self { state: BlackJackState::Playing(state), player_bet, .. } => {
+            Self::Playing(state) => {
                 if state.player_hand_index >= state.player_hands.len() {
                     let mut showdown_needed = false;
                     for hand in state.player_hands.iter() {
@@ -453,324 +694,802 @@ impl Model<PlayerState> for BlackJack {
                         break
                     }
                     if showdown_needed {
-                        self.state = BlackJackState::DealerResolving(DealerResolvingState {
-                            player_hands: state.player_hands.clone(),
-                            dealer_hand: state.dealer_hand.clone(),
+                        return Ok(Transition::To(Self::DealerResolving(DealerResolvingState {
+                            player_hands: state.player_hands,
+                            dealer_hand: state.dealer_hand,
                             frame_count: 0
-                        });
-                    } else {
-                        let mut player_hands = Vec::new();
-                        for hand in state.player_hands.iter() {
-                            player_hands.push((
-                                hand.clone(),
-                                hand.showdown_result(None)
-                            ))
-                        }
-                        self.state = BlackJackState::End(EndState {
-                            dealer_hand: state.dealer_hand.clone(),
-                            player_hands,
-                            bought_insurance: false
-                        })
+                        })))
                     }
+                    // the hole card is turned over, but the dealer doesn't
+                    // draw
+                    let mut player_hands = FixedVec::new();
+                    for hand in state.player_hands.iter() {
+                        player_hands.push((
+                            *hand,
+                            hand.showdown_result(&state.dealer_hand)
+                        ))
+                    }
+                    return Ok(Transition::To(Self::End(EndState {
+                        dealer_hand: state.dealer_hand,
+                        player_hands,
+                        bought_insurance: false
+                    })))
                 } else {
+                    let hands_full = state.player_hands.is_full();
                     let hand = &mut state.player_hands[state.player_hand_index];
                     if hand.is_bust() || hand.is_blackjack() {
                         state.player_hand_index += 1;
                     }
-                    if hand.can_split() && self.player_bank >= *player_bet {
-                        state.split_button.disabled = false;
-                    } else {
-                        state.split_button.disabled = true;
+                    state.buttons.buttons[2].disabled = !(hand.can_split() && !hands_full && table.player_bank >= table.player_bet);
+                    state.buttons.buttons[3].disabled =
+                        !(hand.can_double_down(rules.double_any_two) && table.player_bank >= table.player_bet);
+                    if let Some((button, frames_left)) = &mut state.pending {
+                        if player_one_inputs.tap_z {
+                            state.pending = None;
+                        } else if *frames_left > 1 {
+                            *frames_left -= 1;
+                        } else {
+                            if *button == 3 {
+                                sfx::card_deal();
+                                sfx::chips(table.player_bet);
+                                hand.push(table.horn.draw(&mut table.rng)?);
+                                hand.doubled = true;
+                                table.player_bank -= table.player_bet;
+                                table.total_bet += table.player_bet;
+                                if hand.is_bust() {
+                                    table.screen_effect = Some(ScreenEffect::Shake);
+                                }
+                            }
+                            state.player_hand_index += 1;
+                            state.pending = None;
+                        }
+                        return Ok(Transition::Stay)
                     }
-                    if hand.can_double_down() && self.player_bank >= *player_bet {
-                        state.double_down_button.disabled = false;
+                    let clicked_button = state.buttons.clicked(player_one_inputs);
+                    // the direct layout fires hit, stand, split and double
+                    // down from left, right, down and up
+                    let direct_button = if !player_one_inputs.direct_actions {
+                        None
+                    } else if player_one_inputs.tap_left {
+                        Some(0)
+                    } else if player_one_inputs.tap_right {
+                        Some(1)
+                    } else if player_one_inputs.tap_down {
+                        Some(2)
+                    } else if player_one_inputs.tap_up {
+                        Some(3)
                     } else {
-                        state.double_down_button.disabled = true;
+                        None
+                    };
+                    let chosen_button = clicked_button.or(direct_button);
+                    if let Some(index) = chosen_button {
+                        state.buttons.focus = index;
                     }
-                    if player_one_inputs.tap_x {
-                        match state.button_index {
-                            0 if !state.hit_button.disabled => {  // Hit
-                                hand.cards.push(draw_card(&mut self.horn, &self.rng));
+                    if player_one_inputs.tap_x || chosen_button.is_some() {
+                        let focus = state.buttons.focus;
+                        match focus {
+                            _ if state.buttons.buttons[focus].disabled => {
+                                sfx::error();
+                            }
+                            0 => {  // Hit
+                                sfx::card_deal();
+                                hand.push(table.horn.draw(&mut table.rng)?);
+                                if hand.is_bust() {
+                                    table.screen_effect = Some(ScreenEffect::Shake);
+                                }
                             }
-                            1 if !state.stand_button.disabled => {  // Stand
-                                state.player_hand_index += 1
+                            1 => {  // Stand
+                                sfx::confirm();
+                                state.pending = Some((1, UNDO_FRAMES));
                             }
-                            2 if !state.split_button.disabled => {  // Split
+                            2 => {  // Split
+                                sfx::card_deal();
+                                sfx::chips(table.player_bet);
                                 // take from hand 1
-                                let new_hand_card = hand.cards.pop().unwrap();
-                                hand.cards.push(draw_card(&mut self.horn, &self.rng));
+                                let new_hand_card = hand.pop().ok_or(GameError::BadState)?;
+                                hand.push(table.horn.draw(&mut table.rng)?);
 
                                 // give to hand 2
                                 let mut new_hand = Hand::new();
-                                new_hand.cards.push(new_hand_card);
-                                new_hand.cards.push(draw_card(&mut self.horn, &self.rng));
+                                new_hand.push(new_hand_card);
+                                new_hand.push(table.horn.draw(&mut table.rng)?);
                                 state.player_hands.push(new_hand);
 
-                                self.total_bet += *player_bet;
+                                table.player_bank -= table.player_bet;
+                                table.total_bet += table.player_bet;
                             }
-                            3 if !state.double_down_button.disabled => {  // Double Down
-                                hand.cards.push(draw_card(&mut self.horn, &self.rng));
-                                self.total_bet += self.player_bet;
-                                state.player_hand_index += 1;
+                            _ => {  // Double Down
+                                sfx::confirm();
+                                state.pending = Some((3, UNDO_FRAMES));
                             },
-                            _ => {
-                                buzz();
-                            }
                         }
                     } else {
-                        // left right buttons
-                        if player_one_inputs.tap_right && state.button_index % 2 == 0 {
-                            state.button_index += 1;
-                        }
-                        if player_one_inputs.tap_left && state.button_index % 2 == 1 {
-                            state.button_index -= 1;
-                        }
-                        if player_one_inputs.tap_down && state.button_index / 2 == 0 {
-                            state.button_index += 2;
-                        }
-                        if player_one_inputs.tap_up && state.button_index / 2 == 1 {
-                            state.button_index -= 2;
-                        }
+                        state.buttons.navigate(player_one_inputs);
                     }
                 }
             }
-            Self { state: BlackJackState::End(state), .. } => {
-                if self.player_bet != 0 {
-                    if state.dealer_hand.is_blackjack() {
-                        if state.bought_insurance {
-                            self.player_bank += self.player_bet * 3 / 2
-                        }
-                    } else {
-                        for (_, res) in state.player_hands.iter() {
-                            self.player_bank += match res {
-                                HandResult::BlackJack => self.player_bet * 6 / 5 + self.player_bet,
-                                HandResult::Lose => 0,
-                                HandResult::Push => self.player_bet,
-                                HandResult::Win => self.player_bet * 2,
-                            }
-                        }
+            Self::End(state) => {
+                if table.player_bet != 0 {
+                    if state.player_hands.iter().any(|(_, res)| matches!(res, HandResult::BlackJack)) {
+                        table.burst = Some(Burst { effect: Effect::Confetti, x: 80, y: 100 });
+                        table.sting = Some(&music::FANFARE);
                     }
-                    self.total_bet = 0;
-                    self.player_bet = 0;
-                }
-                if player_one_inputs.tap_x {
-                    self.state = BlackJackState::Betting
+                    let paid = state.returns(table.player_bet, rules, table.shared_state.promotion);
+                    table.player_bank += paid;
+                    log!(Info, "blackjack", "paid {} on {} over {} hands", paid, table.total_bet, state.player_hands.len());
+                    if paid > table.total_bet {
+                        sfx::win(paid);
+                    } else if paid < table.total_bet {
+                        sfx::lose();
+                    }
+                    if paid > 0 {
+                        sfx::payout(paid);
+                    }
+                    table.total_bet = 0;
+                    table.player_bet = 0;
+                    table.rounds += 1;
+                    table.shared_state.promotion = table.shared_state.promotion.and_then(Promotion::hand_played);
                 }
-                if player_one_inputs.tap_z {
-                    return Some(PlayerState { bank: self.player_bank })
+                if table.shared_state.single_round {
+                    if player_one_inputs.tap_x || player_one_inputs.tap_z {
+                        return Ok(Transition::Finish(()))
+                    }
+                } else {
+                    if player_one_inputs.tap_x {
+                        return Ok(Transition::To(Self::Betting))
+                    }
+                    if player_one_inputs.tap_z {
+                        return Ok(Transition::Finish(()))
+                    }
                 }
             }
-            Self { state: BlackJackState::Dealing(state), rng, horn, .. } => {
-                state.frame += 1;
-                if state.frame == 10 {
-                    state.dealer_hand.cards.push(draw_card(horn, rng));
-                } else if state.frame == 20 {
-                    state.player_hand.cards.push(draw_card(horn, rng));
-                } else if state.frame == 30 {
-                    state.dealer_hand.cards.push(draw_card(horn, rng));
-                } else if state.frame == 40 {
-                    state.player_hand.cards.push(draw_card(horn, rng));
-                } else if state.frame == 50 {
-                    if state.dealer_hand.dealer_showing_ace() {
-                        self.state = BlackJackState::Insurance(InsuranceState::new(
-                            state.dealer_hand.clone(),
-                            state.player_hand.clone(),
-                        ));
-                    } else {
-                        self.state = BlackJackState::Playing(PlayingState::new(
-                            state.dealer_hand.clone(),
-                            state.player_hand.clone(),
-                        ));
+            Self::Dealing(state) => {
+                state.frame = state.frame.checked_add(1).ok_or(GameError::BadState)?;
+                let frame = state.frame as u32;
+                let deal = deal(table.shared_state.animation_speed);
+                match deal.cue_at(frame) {
+                    Some(cue @ 0..=3) if cue % 2 == 0 => {
+                        sfx::card_deal();
+                        state.dealer_hand.push(table.horn.draw(&mut table.rng)?)
                     }
-                } else if state.frame > 50 {
-                    unreachable!()
+                    Some(0..=3) => {
+                        sfx::card_deal();
+                        state.player_hand.push(table.horn.draw(&mut table.rng)?)
+                    }
+                    _ => {}
+                }
+                if frame == deal.end() {
+                    let next = if state.dealer_hand.dealer_showing_ace() {
+                        Self::Insurance(InsuranceState::new(state.dealer_hand, state.player_hand))
+                    } else {
+                        Self::Playing(PlayingState::new(state.dealer_hand, state.player_hand))
+                    };
+                    return Ok(Transition::To(next))
+                } else if frame > deal.end() {
+                    return Err(GameError::BadState)
                 }
             }
-            Self { state: BlackJackState::DealerResolving(state), .. } => {
+            Self::DealerResolving(state) => {
                 state.frame_count += 1;
-                if state.dealer_hand.dealer_must_hit() && !state.dealer_hand.is_bust() {
-                    if state.frame_count % 30 == 0 {
-                        state.dealer_hand.cards.push(draw_card(&mut self.horn, &self.rng));
+                if state.dealer_hand.dealer_must_hit(rules.dealer_hits_soft_17) && !state.dealer_hand.is_bust() {
+                    if dealer_hit(table.shared_state.animation_speed).due(state.frame_count) {
+                        sfx::card_deal();
+                        state.dealer_hand.push(table.horn.draw(&mut table.rng)?);
                     }
                 } else {
-                    let mut player_hands = vec![];
+                    let mut player_hands = FixedVec::new();
                     for hand in state.player_hands.iter() {
-                        let res = hand.showdown_result(Some(&state.dealer_hand));
+                        let res = hand.showdown_result(&state.dealer_hand);
                         player_hands.push((
-                            hand.clone(),
+                            *hand,
                             res
                         ));
                     }
-                    self.state = BlackJackState::End(EndState {
-                        dealer_hand: state.dealer_hand.clone(),
+                    return Ok(Transition::To(Self::End(EndState {
+                        dealer_hand: state.dealer_hand,
                         player_hands,
                         bought_insurance: false
-                    });
+                    })))
                 }
             }
-            Self { state: BlackJackState::Insurance(state), .. } => {
+            Self::Insurance(state) => {
                 // buttons for changing bet amount
                 if player_one_inputs.tap_x || player_one_inputs.tap_z {
                     let bought_insurance = if player_one_inputs.tap_x {
-                        self.player_bank -= self.player_bet / 2;
+                        let stake = insurance_stake(table.player_bet);
+                        if stake > table.player_bank {
+                            sfx::error();
+                            return Ok(Transition::Stay)
+                        }
+                        sfx::chips(stake);
+                        table.player_bank -= stake;
+                        table.total_bet += stake;
                         true
                     } else {
                         false
                     };
-                    if state.dealer_hand.is_blackjack() {
-                        self.state = BlackJackState::End(EndState {
-                            dealer_hand: state.dealer_hand.clone(),
-                            player_hands: vec![(
-                                state.player_hand.clone(),
-                                if state.player_hand.is_blackjack() {
-                                    HandResult::BlackJack
-                                } else {
-                                    HandResult::Lose
-                                }
-                            )],
+                    let next = if state.dealer_hand.is_blackjack() {
+                        Self::End(EndState {
+                            dealer_hand: state.dealer_hand,
+                            player_hands: [(
+                                state.player_hand,
+                                state.player_hand.showdown_result(&state.dealer_hand)
+                            )].into_iter().collect(),
                             bought_insurance
-                        });
+                        })
                     } else {
-                        self.state = BlackJackState::Playing(PlayingState::new(
-                            state.dealer_hand.clone(),
-                            state.player_hand.clone()
-                        ));
-                    } 
+                        Self::Playing(PlayingState::new(state.dealer_hand, state.player_hand))
+                    };
+                    return Ok(Transition::To(next))
                 }
             }
         }
-        None
+        Ok(Transition::Stay)
+    }
+
+    fn on_enter(&mut self, table: &mut Table) {
+        // the bet goes down as the cards start coming out
+        if let Self::Dealing(_) = self {
+            sfx::chips(table.player_bet);
+            table.player_bank -= table.player_bet;
+            table.total_bet = table.player_bet;
+        }
+    }
+
+    fn on_exit(&mut self, _table: &mut Table) {
+        // the hole card is turned over however play ends
+        if let Self::Playing(_) = self {
+            sfx::card_flip();
+        }
+    }
+}
+
+impl Model<PlayerState> for BlackJack {
+    fn update(&mut self, inputs: [crate::model::Inputs; 4]) -> GameEvent<PlayerState> {
+        let before = self.state_name();
+        let result = match self.step(inputs) {
+            Ok(event) => event,
+            Err(error) => {
+                log!(Error, "blackjack", "{:?} in {}, bet {} returned", error, before, self.table.player_bet);
+                GameEvent::Exit { state: self.abandon(error) }
+            }
+        };
+        let after = self.state_name();
+        if after != before {
+            log!(Debug, "blackjack", "{} -> {}, {} cards left", before, after, self.table.horn.cards.len());
+        }
+        result
     }
 
     fn draw(&self) {
+        let display = self.table.shared_state.settings.display();
         let table_height = 60;
         // draw table
-        unsafe { *DRAW_COLORS = 0x32; }
-        oval(0, 0, 160, (table_height * 2) as _);
+        self.table.shared_state.settings.felt.draw(0, 0, 160, (table_height * 2) as _);
         unsafe { *DRAW_COLORS = 0x11; }
         rect(0, 0, 160, table_height as _);
         unsafe { *DRAW_COLORS = 0x44; }
         line(0, table_height, 160, table_height);
+        let expression = match self.state.current() {
+            BlackJackState::Betting => Expression::Neutral,
+            BlackJackState::End(state) => state.dealer_expression(),
+            _ => Expression::Dealing,
+        };
+        dealer::draw(expression, 72, table_height - 16);
         // draw input bar
         unsafe { *DRAW_COLORS = 0x32; }
         rect(0, 140, 160, 20);
 
-        // draw
-        text(format!("Chips: ${}", self.player_bank), 10, 5);
-        
-        // draw bank
-        text(format!("Bet Amount: ${}", self.player_bet), 10, 13);
+        // draw bank and bet in the big font, labelled underneath
+        let bank = big_amount(self.table.player_bank);
+        bigfont::draw(&bank, 10, 2);
+        let bet = big_amount(self.table.player_bet);
+        bigfont::draw(&bet, 150 - bigfont::width(&bet), 2);
+        if let BlackJackState::Betting = self.state.current() {
+            self.table.bet_selector.draw_column(&bet, 150, 2, self.table.player_bank);
+        }
+        layout::draw("Chips", 10, 3 + bigfont::HEIGHT, Align::Left);
+        layout::draw("Bet", 150, 3 + bigfont::HEIGHT, Align::Right);
+        if self.table.shared_state.num_players() > 1 {
+            // whose seat the table is dealing for
+            self.table.shared_state.looks[self.table.shared_state.seat].draw(76, 4);
+        }
         
         // draw cards in horn
-        text(format!("Cards in Shoe: {}", self.horn.len()), 10, 21);
+        text(fixed_format!("Cards in Shoe: {}", self.table.horn.cards.len()), 10, 21);
 
         // draw total bet
-        text(format!("Total Bet: ${}", self.total_bet), 10, 29);
+        text(fixed_format!("Total Bet: {}", money::chips(self.table.total_bet)), 10, 29);
+        // chips on the felt: the bet being picked, then everything riding on the hand
+        let on_felt = if let BlackJackState::Betting = self.state.current() { self.table.player_bet } else { self.table.total_bet };
+        chips::draw_stacks(on_felt, 14, 84);
 
         // draw bet amount
-        match self {
-            Self { state: BlackJackState::Betting, .. } => {
+        match self.state.current() {
+            BlackJackState::Betting => {
                 unsafe { *DRAW_COLORS = 0x31; }
-                let t = b"\x86\x87: change bet";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 142);
+                if self.exit_state().loss_limit_reached() {
+                    layout::draw("Loss limit reached", 80, 45, Align::Center);
                 }
-
-                let t = b"\x80: make bet \x81: exit";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                for ((x, y), label) in [(BET_DOWN, "-"), (BET_UP, "+")] {
+                    let hovered = point_in(self.mouse, x, y, 8, 8);
+                    unsafe { *DRAW_COLORS = if hovered { 0x42 } else { 0x32 }; }
+                    text(label, x, y);
                 }
+                unsafe { *DRAW_COLORS = 0x31; }
+                if BetSelector::digit_entry(self.table.player_bank) {
+                    Label::new(b"\x84\x85\x86\x87: change bet", 0, 142).draw();
+                } else {
+                    Label::new(b"\x86\x87: change bet", 0, 142).draw();
+                }
+
+                let (x, y, width) = MAKE_BET;
+                unsafe { *DRAW_COLORS = if point_in(self.mouse, x, y, width, 9) { 0x13 } else { 0x31 }; }
+                Label::new(b"\x80: make bet", x, y).draw();
+                unsafe { *DRAW_COLORS = 0x31; }
+                Label::new(b"\x81: exit", 158, y).aligned(Align::Right).draw();
             }
-            Self { state: BlackJackState::Dealing(DealingState { dealer_hand, player_hand, ..}), .. } => {
+            BlackJackState::Dealing(DealingState { frame, dealer_hand, player_hand }) => {
+                let frame = *frame as u32;
+                let speed = self.table.shared_state.animation_speed;
+                let (deal, slide_frames) = (deal(speed), slide_frames(speed));
+                // the latest card stays off its spot until it finishes sliding
+                let sliding = deal.latest(frame).filter(|cue| *cue < 4 && frame < deal.frame(*cue) + slide_frames);
+                let (mut dealer_hand, mut player_hand) = (*dealer_hand, *player_hand);
+                let sliding = sliding.and_then(|cue| {
+                    let (card, to, face_up) = if cue % 2 == 0 {
+                        (dealer_hand.pop()?, dealer_card_position(cue / 2), cue != 0)
+                    } else {
+                        (player_hand.pop()?, ((player_hand_x(0, 1) + cue / 2 * 14) as _, 97), true)
+                    };
+                    Some((card, to, face_up, Tween::new(deal.frame(cue), slide_frames, Easing::EaseInOut)))
+                });
                 display_cards(
-                    dealer_hand,
-                    &[player_hand],
+                    &dealer_hand,
+                    [&player_hand].into_iter(),
                     0,
                     false,
+                    display,
                 );
+                if let Some((card, to, face_up, tween)) = sliding {
+                    draw_sliding_card(&card, to, face_up, tween, frame, display);
+                }
             }
-            Self { state: BlackJackState::Insurance(InsuranceState { player_hand, dealer_hand }), .. } => {
+            BlackJackState::Insurance(InsuranceState { player_hand, dealer_hand }) => {
                 display_cards(
                     dealer_hand,
-                    &[player_hand],
+                    [player_hand].into_iter(),
                     0,
-                    false
+                    false,
+                    display
                 );
                 unsafe { *DRAW_COLORS = 0x31; }
-                text(format!("Insurance Bet: ${}", self.player_bet / 2), 10, 37);
-                let t = b"Insurance bet?";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 142);
-                }
+                text(fixed_format!("Insurance Bet: {}", money::chips(self.table.player_bet / 2)), 10, 37);
+                Label::new(b"Insurance bet?", 80, 142).aligned(Align::Center).draw();
 
-                let t = b" \x80: yes  \x81: no";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 151);
-                }
+                Label::new(b"\x80: yes  \x81: no", 80, 151).aligned(Align::Center).draw();
             }
-            Self { state: BlackJackState::Playing(state), .. } => {
+            BlackJackState::Playing(state) => {
                 display_cards(
                     &state.dealer_hand,
-                    &state.player_hands.iter().collect::<Vec<_>>(),
+                    state.player_hands.iter(),
                     state.player_hand_index,
-                    false
+                    false,
+                    display
                 );
-                // 0: hit, 1: stand, 2: split, 3: double_down
-                for (index, button) in [&state.hit_button, &state.stand_button, &state.split_button, &state.double_down_button].iter().enumerate() {
-                    if index == state.button_index {
-                        unsafe {
-                            *DRAW_COLORS = 0x0043
-                        }
-                    } else {
-                        unsafe {
-                            *DRAW_COLORS = 0x0003
-                        }
-                    }
-                    // extern_text(t.as_ptr(), t.len(), 0, 142);
-                    // extern_text(t.as_ptr(), t.len(), 0, 151);
-                    text(button.text, (2 + (index % 2) * 60) as _, (142 + 9 * (index / 2)) as _);
+                state.buttons.draw(self.mouse, display);
+                if let Some((button, _)) = state.pending {
+                    unsafe { *DRAW_COLORS = 0x31; }
+                    rect(0, 140, 160, 20);
+                    text(if button == 3 { "Double down" } else { "Stand" }, 2, 142);
+                    Label::new(b"Undo? \x81", 2, 151).draw();
                 }
             }
-            Self {
-                state: BlackJackState::DealerResolving(DealerResolvingState {
+            BlackJackState::DealerResolving(DealerResolvingState {
                     dealer_hand,
                     player_hands,
-                    ..
-                }),
-                ..
-            } => {
+                    frame_count,
+                }) => {
+                // a hit lands every so often, so the newest card is still
+                // sliding for the first few frames after one
+                let speed = self.table.shared_state.animation_speed;
+                let (dealer_hit, slide_frames) = (dealer_hit(speed), slide_frames(speed));
+                let since_hit = dealer_hit.since(*frame_count);
+                let mut dealer_hand = *dealer_hand;
+                let sliding = if *frame_count >= dealer_hit.frames() as u64 && since_hit < slide_frames {
+                    dealer_hand.pop()
+                } else {
+                    None
+                };
                 display_cards(
-                    dealer_hand,
-                    &player_hands.iter().collect::<Vec<_>>(),
+                    &dealer_hand,
+                    player_hands.iter(),
                     0,
-                    true
+                    true,
+                    display
                 );
+                if let Some(card) = sliding {
+                    let to = dealer_card_position(dealer_hand.cards.len());
+                    draw_sliding_card(&card, to, true, Tween::new(0, slide_frames, Easing::Linear), since_hit, display);
+                }
             }
-            Self { state: BlackJackState::End(EndState { dealer_hand, player_hands , .. }), .. } => {
+            BlackJackState::End(EndState { dealer_hand, player_hands , .. }) => {
                 display_cards(
                     dealer_hand,
-                    &player_hands.iter().map(|(x, _)| x).collect::<Vec<_>>(),
+                    player_hands.iter().map(|(x, _)| x),
                     0,
-                    true
+                    true,
+                    display
                 );
 
                 unsafe { *DRAW_COLORS = 0x31; }
-                let t = b"Use \x80 to play again.";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 142);
-                }
-                let t = b"Use \x81 to exit.";
-                unsafe {
-                    extern_text(t.as_ptr(), t.len(), 0, 151);
-                }
+                layout::draw_wrapped(b"Use \x80 to play again. Use \x81 to exit.", 80, 142, 160, Align::Center);
             }
         }
+        if let Some((modal, _)) = &self.table.modal {
+            modal.draw(display);
+        }
+        if let Some(tutorial) = &self.tutorial {
+            tutorial.draw(self);
+        }
+        #[cfg(debug_assertions)]
+        self.draw_stacker(display);
         unsafe {
             *DRAW_COLORS = 0x0430
         }
     }
 
     fn share_state(&mut self, state: PlayerState) {
-        self.player_bank = state.bank;
+        self.table.player_bank = if self.tutorial.is_some() { tutorial::PRACTICE_BANK } else { state.bank() };
+        self.table.rounds = state.rounds;
+        self.table.shared_state = state;
+        self.table.horn.set_shuffle(state.settings.shuffle(state.settings.shoe_shuffle));
+        // a finished hand makes way for whoever sits down next
+        if let BlackJackState::End(_) = self.state.current() {
+            self.state.go(&mut self.table, BlackJackState::Betting);
+        }
+    }
+
+    fn peek_state(&self) -> Option<PlayerState> {
+        Some(self.exit_state())
+    }
+
+    fn bet_live(&self) -> bool {
+        !matches!(self.state.current(), BlackJackState::Betting | BlackJackState::End(_))
+    }
+
+    fn needs_redraw(&self) -> bool {
+        // dealing, the dealer's turn and paying out move along without input
+        match self.state.current() {
+            BlackJackState::Betting => false,
+            BlackJackState::End(_) => self.table.player_bet != 0,
+            _ => true,
+        }
+    }
+
+    fn take_burst(&mut self) -> Option<Burst> {
+        self.table.burst.take()
+    }
+
+    fn take_screen_effect(&mut self) -> Option<ScreenEffect> {
+        self.table.screen_effect.take()
+    }
+
+    fn take_error(&mut self) -> Option<GameError> {
+        self.error.take()
+    }
+
+    fn state_name(&self) -> &'static str {
+        self.state.current().name()
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        Some(self.table.horn.cards.len())
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        // the lesson starts over rather than resuming
+        if self.tutorial.is_some() {
+            return None
+        }
+        Some(self.write_snapshot())
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> bool {
+        self.read_snapshot(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{golden, headless, model::Inputs, rng::Scripted, rules::Difficulty};
+
+    fn table(seed: u64, bank: u32, difficulty: Difficulty) -> BlackJack {
+        let mut game = BlackJack::new(seed);
+        let mut state = PlayerState::default();
+        state.set_bank(bank);
+        state.settings.difficulty = difficulty;
+        game.share_state(state);
+        game
+    }
+
+    fn player_one(inputs: Inputs) -> [Inputs; 4] {
+        [inputs, Inputs::default(), Inputs::default(), Inputs::default()]
+    }
+
+    #[test]
+    fn the_largest_snapshot_fits_a_full_slot() {
+        use crate::{profiles::{Profile, MAX_PROFILES}, save::{Resume, SaveData}};
+        let _console = headless::session();
+        let full = || {
+            let mut hand = Hand::new();
+            while hand.cards.len() < MAX_HAND_CARDS {
+                hand.push(Card { value: CardValue::Ace, suit: CardSuit::Spade });
+            }
+            hand
+        };
+        let mut state = PlayingState::new(full(), full());
+        while state.player_hands.len() < MAX_HANDS {
+            state.player_hands.push(full());
+        }
+        let mut game = table(1, u32::MAX, Difficulty::default());
+        game.state = Machine::new(BlackJackState::Playing(state));
+        let snapshot = game.snapshot().unwrap();
+        // every profile with every game played at both odds
+        let mut profile = Profile::new(*b"BEN");
+        for stats in profile.stats.iter_mut().flatten() {
+            stats.rounds_played = 1;
+        }
+        let mut settings = PlayerState::default().settings;
+        for nonce in 1..=settings.redeemed.len() as u16 {
+            settings.redeem(nonce);
+        }
+        let resume = Some(Resume { game: 0, profile: 0, snapshot: snapshot.clone() });
+        let save_data = SaveData { profiles: alloc::vec![profile; MAX_PROFILES], resume, settings, ..SaveData::default() };
+        assert_eq!(save_data.store(0), Ok(()));
+        assert_eq!(SaveData::load(0).unwrap().resume.map(|resume| resume.snapshot), Some(snapshot));
+    }
+
+    /// A press picked at random, short of leaving the table.
+    fn random_press(rng: &mut Rng, game: &BlackJack) -> Inputs {
+        let mut inputs = Inputs { direct_actions: true, ..Inputs::default() };
+        match rng.u8(..7) {
+            0 => inputs.tap_x = true,
+            1 => inputs.tap_left = true,
+            2 => inputs.tap_right = true,
+            3 => inputs.tap_up = true,
+            4 => inputs.tap_down = true,
+            5 if !matches!(game.state.current(), BlackJackState::Betting | BlackJackState::End(_)) => inputs.tap_z = true,
+            _ => {}
+        }
+        inputs
+    }
+
+    /// What a settled hand should hand back, worked out from the cards
+    /// rather than the table's own results.
+    fn expected_return(hand: &Hand, dealer_hand: &Hand, bet: u32, rules: TableRules) -> u32 {
+        let best = |hand: &Hand| (!hand.is_bust()).then(|| hand.best_total());
+        let natural = |hand: &Hand| hand.cards.len() == 2 && best(hand) == Some(21);
+        let stake = if hand.doubled { bet * 2 } else { bet };
+        match (best(hand), best(dealer_hand)) {
+            _ if natural(hand) && natural(dealer_hand) => stake,
+            _ if natural(hand) => stake + rules.blackjack_winnings(stake),
+            _ if natural(dealer_hand) => 0,
+            (None, _) => 0,
+            (Some(_), None) => stake * 2,
+            (Some(player), Some(dealer)) if player > dealer => stake * 2,
+            (Some(player), Some(dealer)) if player == dealer => stake,
+            _ => 0,
+        }
+    }
+
+    /// Plays a table at random for `frames` frames, checking every bet
+    /// taken and every hand paid against the rules.
+    fn check_payouts(seed: u64) {
+        let mut rng = Rng::with_seed(seed);
+        let difficulty = if rng.bool() { Difficulty::Arcade } else { Difficulty::Realistic };
+        let mut game = table(seed, rng.u32(10..5000), difficulty);
+        let rules = game.table.rules();
+        let mut bank_before_bet = game.table.player_bank;
+        let mut insurance = 0;
+        for _ in 0..3000 {
+            let inputs = random_press(&mut rng, &game);
+            let bank = game.table.player_bank;
+            let was_insurance = matches!(game.state.current(), BlackJackState::Insurance(_));
+            let settling = match game.state.current() {
+                BlackJackState::End(state) if game.table.player_bet != 0 => Some(state),
+                _ => None,
+            };
+            let expected = settling.map(|state| {
+                let stakes: u32 = state.player_hands.iter().map(|(hand, _)| hand.stake(game.table.player_bet)).sum();
+                assert_eq!(bank_before_bet - bank, stakes + insurance, "seed {}: debited for splits and doubles", seed);
+                let insured = if state.bought_insurance { insurance * 3 } else { 0 };
+                let hands: u32 = state.player_hands.iter()
+                    .map(|(hand, _)| expected_return(hand, &state.dealer_hand, game.table.player_bet, rules))
+                    .sum();
+                insured + hands
+            });
+            assert!(!matches!(game.update(player_one(inputs)), GameEvent::Exit { .. }), "seed {}: left the table", seed);
+            assert_eq!(game.take_error(), None, "seed {}: in {}", seed, game.state_name());
+            if was_insurance && !matches!(game.state.current(), BlackJackState::Insurance(_)) {
+                insurance = bank - game.table.player_bank;
+                assert!(insurance == 0 || insurance == insurance_stake(game.table.player_bet), "seed {}: insurance cost {}", seed, insurance);
+            }
+            if let Some(expected) = expected {
+                assert_eq!(game.table.player_bank, bank + expected, "seed {}: paid out", seed);
+            }
+            if let BlackJackState::Betting = game.state.current() {
+                bank_before_bet = game.table.player_bank;
+                insurance = 0;
+            }
+        }
+    }
+
+    #[test]
+    fn payouts_follow_the_rules() {
+        let _console = headless::session();
+        for seed in 0..200 {
+            check_payouts(seed);
+        }
+    }
+
+    fn hand(values: &[CardValue]) -> Hand {
+        let mut hand = Hand::new();
+        for &value in values {
+            hand.push(Card { value, suit: CardSuit::Spade });
+        }
+        hand
+    }
+
+    /// The hard total, and the soft total if there is one.
+    fn totals(values: &[CardValue]) -> (u8, Option<u8>) {
+        let hand = hand(values);
+        (hand.hard_total(), hand.is_soft().then(|| hand.best_total()))
+    }
+
+    #[test]
+    fn aces_count_one_soft_total_at_most() {
+        use CardValue::*;
+        assert_eq!(totals(&[Ace, Six]), (7, Some(17)));
+        assert_eq!(totals(&[Ace, Ace]), (2, Some(12)));
+        assert_eq!(totals(&[Ace, Ace, Ace]), (3, Some(13)));
+        assert_eq!(totals(&[Ace, Ace, Ace, Ace]), (4, Some(14)));
+        assert_eq!(totals(&[Ace, Ace, Ace, Ace, Seven]), (11, Some(21)));
+        assert_eq!(totals(&[Ace, Ace, Ace, Ace, Eight]), (12, None));
+        assert_eq!(totals(&[King, Ace]), (11, Some(21)));
+        assert_eq!(totals(&[King, Queen, Ace]), (21, None));
+    }
+
+    #[test]
+    fn a_split_takes_its_card_out_of_the_totals() {
+        use CardValue::*;
+        let mut pair = hand(&[Ace, Ace]);
+        pair.pop();
+        assert_eq!((pair.hard_total(), pair.best_total()), (1, 11));
+        let mut eights = hand(&[Eight, Eight]);
+        eights.pop();
+        assert!(!eights.is_soft());
+        assert_eq!(eights.best_total(), 8);
+    }
+
+    #[test]
+    fn hands_bust_once_the_hard_total_passes_21() {
+        use CardValue::*;
+        assert!(!hand(&[King, Queen, Ace]).is_bust());
+        assert!(hand(&[King, Queen, Two]).is_bust());
+        assert!(hand(&[King, Eight, Ace, Ace, Ace, Ace]).is_bust());
+        assert!(!hand(&[Ace, Ace, Ace, Ace, Five, Six]).is_bust());
+    }
+
+    #[test]
+    fn only_two_card_21s_are_blackjack() {
+        use CardValue::*;
+        assert!(hand(&[Ace, King]).is_blackjack());
+        assert!(hand(&[Ten, Ace]).is_blackjack());
+        assert!(!hand(&[Ace, Ace]).is_blackjack());
+        assert!(!hand(&[Seven, Seven, Seven]).is_blackjack());
+        assert!(!hand(&[Ace, Five, Five]).is_blackjack());
+    }
+
+    #[test]
+    fn soft_17_depends_on_the_rules() {
+        use CardValue::*;
+        assert!(hand(&[Ace, Six]).dealer_must_hit(true));
+        assert!(!hand(&[Ace, Six]).dealer_must_hit(false));
+        assert!(hand(&[Ace, Ace, Five]).dealer_must_hit(true));
+        assert!(!hand(&[Ten, Six, Ace]).dealer_must_hit(true));
+        assert!(hand(&[Ten, Six]).dealer_must_hit(false));
+    }
+
+    #[test]
+    fn an_empty_shoe_is_replaced_from_the_tables_rng() {
+        let mut shoe = Shoe { cards: FixedVec::new(), seed: 0, shuffle: Shuffle::default() };
+        let card = shoe.draw(&mut Scripted::new(&[0, 42])).unwrap();
+        let top = *Shoe::with_seed(42, Shuffle::default(), SHOE_CARDS).cards.last().unwrap();
+        assert_eq!((card.value, card.suit), (top.value, top.suit));
+        assert_eq!((shoe.seed, shoe.cards.len()), (42, SHOE_CARDS - 1));
+    }
+
+    /// Draws the table with a bet down and `state` in play.
+    fn draw_table(state: BlackJackState) {
+        let mut game = table(7, 1000, Difficulty::Realistic);
+        game.table.player_bet = 20;
+        game.table.total_bet = 40;
+        game.table.player_bank = 960;
+        game.state = Machine::new(state);
+        unsafe { *PALETTE = crate::effects::BASE_PALETTE; }
+        game.draw();
+    }
+
+    #[test]
+    fn a_finished_lesson_hands_over_to_a_real_table() {
+        let _console = headless::session();
+        let mut game = BlackJack::lesson(1);
+        let mut state = PlayerState::default();
+        state.set_bank(250);
+        game.share_state(state);
+        // every button in turn, so whichever the lesson prompts comes round
+        let presses = [
+            Inputs { tap_x: true, ..Inputs::default() },
+            Inputs { tap_right: true, ..Inputs::default() },
+            Inputs { tap_up: true, ..Inputs::default() },
+            Inputs { tap_down: true, ..Inputs::default() },
+        ];
+        for frame in 0..10_000 {
+            match game.update(player_one(presses[frame % presses.len()])) {
+                GameEvent::SwitchTo { state, game: next } => {
+                    assert_eq!(next, "Blackjack");
+                    assert_eq!(state.bank(), 250, "practice chips stay at the practice table");
+                    return
+                }
+                GameEvent::Exit { .. } => panic!("left the lesson in {}", game.state_name()),
+                _ => assert_eq!(game.take_error(), None),
+            }
+        }
+        panic!("the lesson never finished, stuck in {}", game.state_name());
+    }
+
+    #[test]
+    fn betting_screen() {
+        let _console = headless::session();
+        draw_table(BlackJackState::Betting);
+        golden::check("blackjack_betting");
+    }
+
+    #[test]
+    fn split_hands_screen() {
+        use CardValue::*;
+        let _console = headless::session();
+        let mut state = PlayingState::new(hand(&[Nine, Six]), hand(&[Eight, Three, Ten]));
+        state.player_hands.push(hand(&[Eight, Ace]));
+        draw_table(BlackJackState::Playing(state));
+        golden::check("blackjack_split");
+    }
+
+    #[test]
+    fn end_screen() {
+        use CardValue::*;
+        let _console = headless::session();
+        draw_table(BlackJackState::End(EndState {
+            dealer_hand: hand(&[Nine, Six, King]),
+            player_hands: [(hand(&[Eight, Three, Ten]), HandResult::Win), (hand(&[Eight, Ace]), HandResult::Win)].into_iter().collect(),
+            bought_insurance: false,
+        }));
+        golden::check("blackjack_end");
+    }
+
+    #[test]
+    fn rounds_play_through_without_errors() {
+        let _console = headless::session();
+        let mut game = table(7, 1000, Difficulty::Realistic);
+        let tap_x = Inputs { tap_x: true, ..Inputs::default() };
+        for frame in 0..20_000 {
+            // tapping every other frame so each tap is its own press
+            let inputs = if frame % 2 == 0 { tap_x } else { Inputs::default() };
+            assert!(!matches!(game.update([inputs, Inputs::default(), Inputs::default(), Inputs::default()]), GameEvent::Exit { .. }));
+            assert_eq!(game.take_error(), None, "in {}", game.state_name());
+        }
+        assert!(game.table.rounds > 0);
     }
 }
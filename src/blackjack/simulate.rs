@@ -0,0 +1,137 @@
+//! Blackjack played by basic strategy, for the native simulator. Hands go
+//! through the same dealing, drawing and settling as at the table, only
+//! without the frames in between.
+//!
+//! The strategy is the usual chart for a multi-deck shoe with doubling
+//! after splits, never taking insurance, so the house edge it finds can be
+//! held up against published figures.
+
+use super::*;
+use crate::{rules::Difficulty, simulate::Report};
+
+#[derive(Copy, Clone, PartialEq)]
+enum Play {
+    Hit,
+    Stand,
+    Double,
+    Split,
+}
+
+/// A card's count for the chart, with aces as 11.
+fn rank(card: Card) -> u8 {
+    match card.value {
+        CardValue::Ace => 11,
+        CardValue::Ten | CardValue::Jack | CardValue::Queen | CardValue::King => 10,
+        other => other as u8,
+    }
+}
+
+fn basic_strategy(hand: &Hand, up: u8, can_split: bool, can_double: bool, hits_soft_17: bool) -> Play {
+    let double_or = |otherwise| if can_double { Play::Double } else { otherwise };
+    if can_split {
+        let split = match rank(hand.cards[0]) {
+            11 | 8 => true,
+            9 => !matches!(up, 7 | 10 | 11),
+            7 | 3 | 2 => up <= 7,
+            6 => up <= 6,
+            4 => matches!(up, 5 | 6),
+            _ => false,
+        };
+        if split {
+            return Play::Split
+        }
+    }
+    if hand.is_soft() {
+        return match hand.best_total() {
+            20.. => Play::Stand,
+            19 if up == 6 && hits_soft_17 => double_or(Play::Stand),
+            19 => Play::Stand,
+            18 if up <= 6 => double_or(Play::Stand),
+            18 if up <= 8 => Play::Stand,
+            17 if (3..=6).contains(&up) => double_or(Play::Hit),
+            15 | 16 if (4..=6).contains(&up) => double_or(Play::Hit),
+            13 | 14 if (5..=6).contains(&up) => double_or(Play::Hit),
+            _ => Play::Hit,
+        }
+    }
+    match hand.hard_total() {
+        17.. => Play::Stand,
+        13..=16 if up <= 6 => Play::Stand,
+        12 if (4..=6).contains(&up) => Play::Stand,
+        11 if up < 11 || hits_soft_17 => double_or(Play::Hit),
+        10 if up <= 9 => double_or(Play::Hit),
+        9 if (3..=6).contains(&up) => double_or(Play::Hit),
+        _ => Play::Hit,
+    }
+}
+
+/// Plays one round from `shoe`, returning what was put down and what came
+/// back.
+fn round(shoe: &mut Shoe, rng: &mut impl GameRng, bet: u32, rules: TableRules) -> Result<(u32, u32), GameError> {
+    let mut dealer_hand = Hand::new();
+    let mut player_hand = Hand::new();
+    for _ in 0..2 {
+        dealer_hand.push(shoe.draw(rng)?);
+        player_hand.push(shoe.draw(rng)?);
+    }
+    let mut hands: FixedVec<Hand, MAX_HANDS> = [player_hand].into_iter().collect();
+    // insurance is always turned down, and an ace up with a natural under
+    // it ends the round there and then
+    if !(dealer_hand.dealer_showing_ace() && dealer_hand.is_blackjack()) {
+        let up = rank(dealer_hand.cards[1]);
+        let mut index = 0;
+        while index < hands.len() {
+            let hands_full = hands.is_full();
+            let hand = &mut hands[index];
+            if hand.is_bust() || hand.is_blackjack() {
+                index += 1;
+                continue
+            }
+            let can_split = hand.can_split() && !hands_full;
+            let can_double = hand.can_double_down(rules.double_any_two);
+            match basic_strategy(hand, up, can_split, can_double, rules.dealer_hits_soft_17) {
+                Play::Hit => hand.push(shoe.draw(rng)?),
+                Play::Stand => index += 1,
+                Play::Double => {
+                    hand.push(shoe.draw(rng)?);
+                    hand.doubled = true;
+                    index += 1;
+                }
+                Play::Split => {
+                    let card = hand.pop().ok_or(GameError::BadState)?;
+                    hand.push(shoe.draw(rng)?);
+                    let mut new_hand = Hand::new();
+                    new_hand.push(card);
+                    new_hand.push(shoe.draw(rng)?);
+                    hands.push(new_hand);
+                }
+            }
+        }
+        if hands.iter().any(|hand| !hand.is_bust() && !hand.is_blackjack()) {
+            while dealer_hand.dealer_must_hit(rules.dealer_hits_soft_17) && !dealer_hand.is_bust() {
+                dealer_hand.push(shoe.draw(rng)?);
+            }
+        }
+    }
+    let end = EndState {
+        player_hands: hands.iter().map(|hand| (*hand, hand.showdown_result(&dealer_hand))).collect(),
+        dealer_hand,
+        bought_insurance: false,
+    };
+    let wagered = hands.iter().map(|hand| hand.stake(bet)).sum();
+    Ok((wagered, end.returns(bet, rules, None)))
+}
+
+/// Plays `rounds` rounds of blackjack at the given odds from shoes shuffled
+/// with `shuffle`, a bet of `bet` each.
+pub fn blackjack(rounds: u64, seed: u64, bet: u32, difficulty: Difficulty, shuffle: Shuffle) -> Result<Report, GameError> {
+    let rules = difficulty.rules();
+    let mut rng = Rng::with_seed(seed);
+    let mut shoe = Shoe::new(&mut rng, shuffle);
+    let mut report = Report::new(bet);
+    for _ in 0..rounds {
+        let (wagered, returned) = round(&mut shoe, &mut rng, bet, rules)?;
+        report.record(wagered, returned);
+    }
+    Ok(report)
+}
@@ -0,0 +1,175 @@
+//! Compact snapshots of a blackjack table, autosaved to disk so a hand in
+//! progress survives the cartridge being closed.
+//!
+//! Per-frame counters (dealing animation frame, cursor position) are left
+//! out so the snapshot only changes when the hand actually moves on.
+
+use alloc::vec::Vec;
+use super::*;
+use crate::save::Reader;
+
+const BETTING: u8 = 0;
+const DEALING: u8 = 1;
+const INSURANCE: u8 = 2;
+const PLAYING: u8 = 3;
+const DEALER_RESOLVING: u8 = 4;
+
+impl Card {
+    fn to_byte(self) -> u8 {
+        (self.suit as u8) << 4 | self.value as u8
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        let value = *CardValue::values().get(((byte & 0x0f) as usize).checked_sub(1)?)?;
+        let suit = *CardSuit::suits().get((byte >> 4) as usize)?;
+        Some(Self { value, suit })
+    }
+}
+
+/// Set in a hand's length byte once it's been doubled down.
+const DOUBLED: u8 = 0x80;
+
+fn write_hand(buf: &mut Vec<u8>, hand: &Hand) {
+    buf.push(hand.cards.len() as u8 | if hand.doubled { DOUBLED } else { 0 });
+    buf.extend(hand.cards.iter().copied().map(Card::to_byte));
+}
+
+fn read_hand(reader: &mut Reader) -> Option<Hand> {
+    let header = reader.u8();
+    let len = (header & !DOUBLED) as usize;
+    if len > MAX_HAND_CARDS {
+        return None
+    }
+    let mut hand = Hand::new();
+    hand.doubled = header & DOUBLED != 0;
+    for _ in 0..len {
+        hand.push(Card::from_byte(reader.u8())?);
+    }
+    Some(hand)
+}
+
+fn write_hands<'a>(buf: &mut Vec<u8>, hands: impl ExactSizeIterator<Item = &'a Hand>) {
+    buf.push(hands.len() as u8);
+    for hand in hands {
+        write_hand(buf, hand);
+    }
+}
+
+fn read_hands(reader: &mut Reader) -> Option<FixedVec<Hand, MAX_HANDS>> {
+    let len = reader.u8() as usize;
+    if len > MAX_HANDS {
+        return None
+    }
+    (0..len).map(|_| read_hand(reader)).collect()
+}
+
+fn read_state(reader: &mut Reader, speed: f32) -> Option<BlackJackState> {
+    let state = match reader.u8() {
+        BETTING => BlackJackState::Betting,
+        DEALING => {
+            let dealer_hand = read_hand(reader)?;
+            let player_hand = read_hand(reader)?;
+            let dealt = dealer_hand.cards.len() + player_hand.cards.len();
+            BlackJackState::Dealing(DealingState {
+                frame: dealt.checked_sub(1).map_or(0, |cue| deal(speed).frame(cue) as u8),
+                dealer_hand,
+                player_hand,
+            })
+        }
+        INSURANCE => {
+            let dealer_hand = read_hand(reader)?;
+            let player_hand = read_hand(reader)?;
+            BlackJackState::Insurance(InsuranceState::new(dealer_hand, player_hand))
+        }
+        PLAYING => {
+            let dealer_hand = read_hand(reader)?;
+            let player_hand_index = reader.u8() as usize;
+            let mut state = PlayingState::new(dealer_hand, Hand::new());
+            state.player_hands = read_hands(reader)?;
+            state.player_hand_index = player_hand_index;
+            BlackJackState::Playing(state)
+        }
+        DEALER_RESOLVING => {
+            let dealer_hand = read_hand(reader)?;
+            BlackJackState::DealerResolving(DealerResolvingState {
+                player_hands: read_hands(reader)?,
+                dealer_hand,
+                frame_count: 0,
+            })
+        }
+        _ => return None,
+    };
+    Some(state)
+}
+
+impl BlackJack {
+    pub(super) fn write_snapshot(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&self.table.player_bank.to_le_bytes());
+        buf.extend_from_slice(&self.table.player_bet.to_le_bytes());
+        buf.extend_from_slice(&self.table.total_bet.to_le_bytes());
+        buf.extend_from_slice(&self.table.rounds.to_le_bytes());
+        buf.extend_from_slice(&self.table.rng.get_seed().to_le_bytes());
+        buf.extend_from_slice(&self.table.horn.seed.to_le_bytes());
+        buf.extend_from_slice(&(self.table.horn.cards.len() as u16).to_le_bytes());
+        match self.state.current() {
+            // the hand is settled on the first frame of the end state, so it
+            // resumes at the next bet
+            BlackJackState::Betting | BlackJackState::End(_) => {
+                buf.push(BETTING);
+            }
+            BlackJackState::Dealing(state) => {
+                buf.push(DEALING);
+                write_hand(&mut buf, &state.dealer_hand);
+                write_hand(&mut buf, &state.player_hand);
+            }
+            BlackJackState::Insurance(state) => {
+                buf.push(INSURANCE);
+                write_hand(&mut buf, &state.dealer_hand);
+                write_hand(&mut buf, &state.player_hand);
+            }
+            BlackJackState::Playing(state) => {
+                buf.push(PLAYING);
+                write_hand(&mut buf, &state.dealer_hand);
+                buf.push(state.player_hand_index as u8);
+                write_hands(&mut buf, state.player_hands.iter());
+            }
+            BlackJackState::DealerResolving(state) => {
+                buf.push(DEALER_RESOLVING);
+                write_hand(&mut buf, &state.dealer_hand);
+                write_hands(&mut buf, state.player_hands.iter());
+            }
+        }
+        buf.push(self.table.horn.shuffle.to_byte());
+        buf
+    }
+
+    /// Restores a table from `write_snapshot`, leaving it untouched if the
+    /// snapshot doesn't parse.
+    pub(super) fn read_snapshot(&mut self, snapshot: &[u8]) -> bool {
+        let reader = &mut Reader::new(snapshot);
+        let player_bank = reader.u32();
+        let player_bet = reader.u32();
+        let total_bet = reader.u32();
+        let rounds = reader.u32();
+        let rng_seed = reader.u64();
+        let shoe_seed = reader.u64();
+        let shoe_remaining = reader.u16() as usize;
+        let state = read_state(reader, self.table.shared_state.animation_speed);
+        // snapshots from before riffles read as the machine shuffle
+        let shuffle = Shuffle::from_byte(reader.u8());
+        match state {
+            Some(state) => {
+                self.table.player_bank = player_bank;
+                self.table.player_bet = player_bet;
+                self.table.total_bet = total_bet;
+                self.table.rounds = rounds;
+                self.table.rng.seed(rng_seed);
+                self.table.horn = Shoe::with_seed(shoe_seed, shuffle, shoe_remaining);
+                self.state = Machine::new(state);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Stacking the shoe, in debug builds, to set up a rare hand like a split
+//! into blackjack or a dealer drawing to 22 without waiting for the shuffle
+//! to deal it. Holding Z and double tapping right while betting opens it.
+//!
+//! Each of the next `STACK_CARDS` cards is either picked or left to the
+//! shuffle. Left and right move between them and up and down step through
+//! the values, with the shuffle's card before the ace. X stacks them on top
+//! of the shoe in that order and Z backs out. A picked card is swapped up
+//! from further down the shoe, so the shoe still holds what it should; once
+//! a value has run out, its pick is left to the shuffle.
+
+use super::*;
+use crate::ui;
+#[cfg(feature = "cheats")]
+use crate::cheats::Jump;
+
+/// Cards that can be picked, counted from the top of the shoe.
+const STACK_CARDS: usize = 10;
+const LEFT: i32 = 20;
+const TOP: i32 = 56;
+const SPACING: i32 = 12;
+/// Who the first cards are dealt to, in the order they come out.
+const DEALT_TO: [&str; 4] = ["D", "Y", "D", "Y"];
+
+pub(super) struct StackScreen {
+    picks: [Option<CardValue>; STACK_CARDS],
+    cursor: usize,
+}
+
+impl StackScreen {
+    fn new() -> Self {
+        Self { picks: [None; STACK_CARDS], cursor: 0 }
+    }
+
+    /// Steps the picked card through the shuffle's card then each value.
+    fn step(&mut self, step: isize) {
+        let values = CardValue::values();
+        let len = values.len() as isize + 1;
+        let position = self.picks[self.cursor].map_or(0, |value| value as isize);
+        let position = (position + step).rem_euclid(len) as usize;
+        self.picks[self.cursor] = position.checked_sub(1).map(|index| values[index]);
+    }
+
+    /// `Some(true)` to stack the picks and `Some(false)` to back out.
+    fn update(&mut self, inputs: &Inputs) -> Option<bool> {
+        if inputs.tap_left || inputs.tap_right {
+            let step = if inputs.tap_right { 1 } else { STACK_CARDS - 1 };
+            self.cursor = (self.cursor + step) % STACK_CARDS;
+            sfx::cursor_move();
+        }
+        if inputs.tap_up {
+            self.step(1);
+        } else if inputs.tap_down {
+            self.step(-1);
+        }
+        if inputs.tap_x {
+            sfx::confirm();
+            return Some(true)
+        }
+        if inputs.tap_z {
+            sfx::cancel();
+            return Some(false)
+        }
+        None
+    }
+
+    fn draw(&self, display: Display) {
+        unsafe { *DRAW_COLORS = 0x23; }
+        rect(10, 40, 140, 48);
+        unsafe { *DRAW_COLORS = 0x01; }
+        layout::draw("Stack the shoe", 80, 46, Align::Center);
+        for (index, pick) in self.picks.iter().enumerate() {
+            let x = LEFT + SPACING * index as i32;
+            let focused = index == self.cursor;
+            ui::set_text_colors(if focused { 0x43 } else { 0x01 }, display);
+            match pick {
+                Some(value) => text(fixed_format!("{}", value), x, TOP),
+                None => text("?", x, TOP),
+            }
+            if focused {
+                ui::draw_focus_box(x, TOP, 8, display);
+            }
+            unsafe { *DRAW_COLORS = 0x01; }
+            text(DEALT_TO.get(index).unwrap_or(&"+"), x, TOP + 10);
+        }
+        Label::new(b"\x86\x87: card \x80: stack", LEFT, 76).draw();
+    }
+}
+
+impl Shoe {
+    /// Brings each picked value to the top of the shoe in order, leaving
+    /// the shuffle's card wherever there's no pick.
+    pub(super) fn stack(&mut self, picks: &[Option<CardValue>]) {
+        let len = self.cards.len();
+        for (depth, pick) in picks.iter().enumerate().take(len) {
+            let top = len - 1 - depth;
+            let Some(value) = pick else { continue };
+            // cards above `top` are already stacked
+            if let Some(index) = self.cards[..=top].iter().rposition(|card| card.value == *value) {
+                self.cards.swap(index, top);
+            }
+        }
+    }
+}
+
+impl BlackJack {
+    /// Opens, runs and closes the stacking screen. Returns true while it
+    /// has the table's inputs.
+    pub(super) fn stack_shoe(&mut self, inputs: &Inputs) -> bool {
+        let betting = matches!(self.state.current(), BlackJackState::Betting);
+        if self.stacker.is_none() && betting && self.tutorial.is_none() && inputs.press_z && inputs.double_right {
+            // Z brought up the leave prompt on its way to the combo
+            self.table.modal = None;
+            self.stacker = Some(StackScreen::new());
+            return true
+        }
+        let Some(stacker) = &mut self.stacker else {
+            return false
+        };
+        match stacker.update(inputs) {
+            Some(true) => {
+                log!(Info, "blackjack", "stacked {:?}", stacker.picks);
+                self.table.horn.stack(&stacker.picks);
+                self.stacker = None;
+            }
+            Some(false) => self.stacker = None,
+            None => {}
+        }
+        true
+    }
+
+    /// Stacks a natural for the next hand, unless it's the lesson's.
+    #[cfg(feature = "cheats")]
+    pub fn force_jackpot(&mut self) -> bool {
+        if self.tutorial.is_some() {
+            return false
+        }
+        self.table.horn.stack(&[None, Some(CardValue::Ace), None, Some(CardValue::King)]);
+        true
+    }
+
+    /// Deals a hand on the table minimum straight into the state `jump`
+    /// names, stacking the shoe for it so the cards still come out of it.
+    /// Returns whether it could, which it can't in the lesson, mid-hand or
+    /// short of the bets.
+    #[cfg(feature = "cheats")]
+    pub fn jump(&mut self, jump: Jump) -> bool {
+        use CardValue::*;
+        // dealt to the dealer, you, the dealer and you, then a card to each
+        // side of the split
+        let (picks, bets): (&[Option<CardValue>], u32) = match jump {
+            Jump::Insurance => (&[None, None, Some(Ace), None], 1),
+            Jump::SplitHand => (&[Some(Ten), Some(Eight), Some(Six), Some(Eight), Some(Ten), Some(Ace)], 2),
+            _ => return false,
+        };
+        let table = &mut self.table;
+        let minimum_bet = Promotion::minimum_bet(table.shared_state.promotion, MINIMUM_BET);
+        let betting = matches!(self.state.current(), BlackJackState::Betting);
+        if self.tutorial.is_some() || !betting || table.player_bank < minimum_bet * bets {
+            return false
+        }
+        table.horn.stack(picks);
+        let Ok(cards) = picks.iter().map(|_| table.horn.draw(&mut table.rng)).collect::<Result<Vec<Card>, _>>() else {
+            return false
+        };
+        let hand = |dealt: [usize; 2]| {
+            let mut hand = Hand::new();
+            for index in dealt {
+                hand.push(cards[index]);
+            }
+            hand
+        };
+        let dealer_hand = hand([0, 2]);
+        let next = match jump {
+            Jump::Insurance => BlackJackState::Insurance(InsuranceState::new(dealer_hand, hand([1, 3]))),
+            _ => {
+                let mut player_hands = FixedVec::new();
+                player_hands.push(hand([1, 4]));
+                player_hands.push(hand([3, 5]));
+                BlackJackState::DealerResolving(DealerResolvingState { player_hands, dealer_hand, frame_count: 0 })
+            }
+        };
+        table.player_bet = minimum_bet;
+        // the deal puts the bet down and the split the second one
+        self.state.go(&mut self.table, BlackJackState::Dealing(DealingState::new()));
+        self.table.player_bank -= minimum_bet * (bets - 1);
+        self.table.total_bet += minimum_bet * (bets - 1);
+        self.state.go(&mut self.table, next);
+        log!(Info, "blackjack", "jumped to {}", self.state_name());
+        true
+    }
+
+    pub(super) fn draw_stacker(&self, display: Display) {
+        if let Some(stacker) = &self.stacker {
+            stacker.draw(display);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacked_cards_come_off_the_top_in_order() {
+        let mut shoe = Shoe::with_seed(9, Shuffle::default(), SHOE_CARDS);
+        let mut before: Vec<u8> = shoe.cards.iter().map(|card| card.value as u8).collect();
+        let mut rng = Rng::with_seed(0);
+        shoe.stack(&[Some(CardValue::Eight), None, Some(CardValue::Eight), Some(CardValue::Ace)]);
+        let untouched = shoe.cards[shoe.cards.len() - 2];
+        let dealt: Vec<CardValue> = (0..4).map(|_| shoe.draw(&mut rng).unwrap().value).collect();
+        assert_eq!(dealt, [CardValue::Eight, untouched.value, CardValue::Eight, CardValue::Ace]);
+        // cards were moved, not made up
+        let mut after: Vec<u8> = shoe.cards.iter().map(|card| card.value as u8).chain(dealt.iter().map(|value| *value as u8)).collect();
+        before.sort();
+        after.sort();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    #[cfg(feature = "cheats")]
+    fn jumps_bet_and_deal_from_the_shoe() {
+        let _console = crate::headless::session();
+        let table = || {
+            let mut game = BlackJack::new(4);
+            let mut state = PlayerState::default();
+            state.set_bank(100);
+            game.share_state(state);
+            game
+        };
+        let mut game = table();
+        let cards_left = game.table.horn.cards.len();
+        assert!(game.jump(Jump::SplitHand));
+        let BlackJackState::DealerResolving(state) = game.state.current() else { panic!("jumped to {}", game.state_name()) };
+        let totals: Vec<u8> = state.player_hands.iter().map(Hand::best_total).collect();
+        assert_eq!((totals.as_slice(), state.dealer_hand.best_total()), (&[18, 19][..], 16));
+        assert_eq!((game.table.player_bank, game.table.total_bet), (80, 20));
+        assert_eq!(game.table.horn.cards.len(), cards_left - 6);
+        assert!(!game.jump(Jump::Insurance), "not mid-hand");
+
+        let mut game = table();
+        assert!(game.jump(Jump::Insurance));
+        assert!(matches!(game.state.current(), BlackJackState::Insurance(state) if state.dealer_hand.dealer_showing_ace()));
+        assert_eq!(game.table.player_bank, 90);
+    }
+
+    #[test]
+    fn picks_step_through_the_shuffle_and_every_value() {
+        let mut screen = StackScreen::new();
+        screen.step(-1);
+        assert_eq!(screen.picks[0], Some(CardValue::King));
+        screen.step(1);
+        assert_eq!(screen.picks[0], None);
+        screen.step(1);
+        assert_eq!(screen.picks[0], Some(CardValue::Ace));
+    }
+}
@@ -0,0 +1,90 @@
+//! The blackjack lesson: two hands dealt from a stacked shoe, one to bet,
+//! split and hit and one to buy insurance, played with practice chips.
+
+use super::*;
+use crate::tutorial::{Prompt, Step, Tutorial};
+
+/// Chips to play the lesson with. The player's own bank is never touched.
+pub(super) const PRACTICE_BANK: u32 = 1000;
+
+/// The menu's game a finished lesson sits the player down at.
+pub(super) const NEXT_GAME: &str = "Blackjack";
+
+/// Every card of the lesson in the order it's dealt. Each hand goes dealer
+/// hole card, player, dealer face up, player, then hits.
+const DEALT: [(CardValue, CardSuit); 12] = [
+    (CardValue::Ten, CardSuit::Spade),
+    (CardValue::Eight, CardSuit::Club),
+    (CardValue::Six, CardSuit::Heart),
+    (CardValue::Eight, CardSuit::Diamond),
+    // the split hands' second cards
+    (CardValue::Three, CardSuit::Spade),
+    (CardValue::Two, CardSuit::Heart),
+    // the hit on 11, then the dealer busting 16
+    (CardValue::Nine, CardSuit::Club),
+    (CardValue::Seven, CardSuit::Diamond),
+    (CardValue::King, CardSuit::Heart),
+    (CardValue::Ten, CardSuit::Club),
+    (CardValue::Ace, CardSuit::Spade),
+    (CardValue::Nine, CardSuit::Diamond),
+];
+
+pub(super) fn stacked_shoe() -> Shoe {
+    let cards = DEALT.iter().rev().map(|(value, suit)| Card { value: *value, suit: *suit }).collect();
+    Shoe { cards, seed: 0, shuffle: Shuffle::default() }
+}
+
+fn betting(game: &BlackJack) -> bool {
+    matches!(game.state.current(), BlackJackState::Betting)
+}
+
+fn playing(game: &BlackJack) -> Option<&PlayingState> {
+    match game.state.current() {
+        BlackJackState::Playing(state) if state.pending.is_none() => Some(state),
+        _ => None,
+    }
+}
+
+fn unsplit(game: &BlackJack) -> bool {
+    playing(game).is_some_and(|state| state.player_hands.len() == 1)
+}
+
+fn split(game: &BlackJack) -> bool {
+    playing(game).is_some_and(|state| state.player_hands.len() == 2 && state.player_hands[0].cards.len() == 2)
+}
+
+fn hit_first(game: &BlackJack) -> bool {
+    playing(game).is_some_and(|state| state.player_hand_index == 0 && state.player_hands[0].cards.len() == 3)
+}
+
+fn on_second(game: &BlackJack) -> bool {
+    playing(game).is_some_and(|state| state.player_hand_index == 1)
+}
+
+fn insurance(game: &BlackJack) -> bool {
+    matches!(game.state.current(), BlackJackState::Insurance(_))
+}
+
+fn ended(game: &BlackJack) -> bool {
+    matches!(game.state.current(), BlackJackState::End(_))
+}
+
+const STEPS: [Step<BlackJack>; 13] = [
+    Step { caption: b"\x86 raises your bet", highlight: (104, 0, 56, 20), prompt: Prompt::Up, ready: betting },
+    Step { caption: b"\x80 places it and deals", highlight: (0, 151, 96, 9), prompt: Prompt::X, ready: betting },
+    Step { caption: b"A pair can be split into two hands. \x87 picks Split", highlight: (2, 151, 58, 9), prompt: Prompt::Down, ready: unsplit },
+    Step { caption: b"\x80 splits the 8s", highlight: (52, 95, 26, 18), prompt: Prompt::X, ready: unsplit },
+    Step { caption: b"Each hand gets a new card. \x86 picks Hit", highlight: (2, 142, 58, 9), prompt: Prompt::Up, ready: split },
+    Step { caption: b"\x80 takes a card on 11", highlight: (25, 95, 26, 18), prompt: Prompt::X, ready: split },
+    Step { caption: b"20 is strong. \x85 picks Stand", highlight: (62, 142, 58, 9), prompt: Prompt::Right, ready: hit_first },
+    Step { caption: b"\x80 stands on 20", highlight: (25, 95, 40, 18), prompt: Prompt::X, ready: hit_first },
+    Step { caption: b"The dealer shows 6 and will likely bust. \x80 stands on 10", highlight: (58, 65, 26, 18), prompt: Prompt::X, ready: on_second },
+    Step { caption: b"The dealer bust, so both hands win! \x80 goes on", highlight: (58, 65, 42, 18), prompt: Prompt::X, ready: ended },
+    Step { caption: b"\x80 deals another hand", highlight: (0, 151, 96, 9), prompt: Prompt::X, ready: betting },
+    Step { caption: b"An ace is showing. Insurance pays if the dealer has blackjack. \x80 buys it", highlight: (72, 65, 14, 18), prompt: Prompt::X, ready: insurance },
+    Step { caption: b"Blackjack! Insurance paid back the bet. \x80 takes you to a real table", highlight: (58, 65, 26, 18), prompt: Prompt::X, ready: ended },
+];
+
+pub(super) fn lesson() -> Tutorial<BlackJack> {
+    Tutorial::new(&STEPS)
+}
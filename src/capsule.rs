@@ -0,0 +1,382 @@
+//! Bug-report capsules, holding enough to play a game back frame for frame:
+//! the seed and shared state it launched with and every input since.
+//!
+//! Holding Z and double tapping down on the first gamepad traces the
+//! capsule for the last game launched as lines of Crockford base32. Run
+//! together and set as `CAPSULE` for a debug build, they're played back from
+//! boot, so a filed report of a wrong payout plays out the same again.
+//!
+//! The input log keeps `MAX_RUNS` changes of input. Once it fills, later
+//! frames aren't kept and the trace says so; launching the game again
+//! starts a fresh capsule. Long presses and double taps under way as a game
+//! launches aren't kept either, so a capsule is best taken from a launch
+//! with nothing held.
+//!
+//! Encoded (little endian):
+//! - `version: u8`
+//! - `game: u8`, index into `GAMES`
+//! - `seed: u64`
+//! - `banks: [u32; 4]`, `rounds: u32`, `session_start_bank: u32`,
+//!   `active_seats: u8`
+//! - `promotion: u8`, 0 for none, 1 for double blackjack and 2 for happy
+//!   hour, then `hands_left: u8`
+//! - `loss_limit: u32`, `0` if off, `difficulty: u8`, `controls: u8` laid
+//!   out as in the save, `animation_speed: u8`, `timings: [u16; 4]` as in
+//!   the save
+//! - `snapshot_len: u8`, `0` for a fresh launch, then the snapshot resumed
+//! - `previous: Frame`, the inputs on the frame the game launched
+//! - `num_runs: u16`, then `num_runs` x (`Frame`, `frames: u16`)
+//! - `check: u16`, Fletcher-16 of everything before it
+//!
+//! A `Frame` is `gamepads: [u8; 4]`, `mouse_x: i16`, `mouse_y: i16` and
+//! `mouse_buttons: u8`.
+
+use alloc::vec::Vec;
+use crate::{events::Promotion, fixed::FixedVec, model::Inputs, passcode::ALPHABET, save::fletcher16, wasm4::*, PlayerState};
+#[cfg(debug_assertions)]
+use crate::{rules::Difficulty, save::Reader, timers::AnimationSpeed};
+
+const VERSION: u8 = 2;
+/// Changes of input the log has room for, 12 bytes each.
+pub const MAX_RUNS: usize = 256;
+/// Characters in each traced line.
+const LINE_LEN: usize = 64;
+
+/// Every gamepad and the mouse as they stood for a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub gamepads: [u8; 4],
+    pub mouse: (i16, i16),
+    pub mouse_buttons: u8,
+}
+
+impl Frame {
+    pub fn of(inputs: &[Inputs; 4]) -> Self {
+        let mouse = &inputs[0];
+        Self {
+            gamepads: inputs.map(|inputs| inputs.gamepad),
+            mouse: (mouse.mouse_x as i16, mouse.mouse_y as i16),
+            mouse_buttons: (mouse.press_mouse_left as u8 * MOUSE_LEFT) | (mouse.press_mouse_right as u8 * MOUSE_RIGHT),
+        }
+    }
+
+    /// Puts the frame in the console's registers, where this frame's inputs
+    /// are read from next.
+    #[cfg(debug_assertions)]
+    pub fn write(&self) {
+        unsafe {
+            for (index, gamepad) in self.gamepads.iter().enumerate() {
+                *GAMEPAD1.add(index).cast_mut() = *gamepad;
+            }
+            *MOUSE_X.cast_mut() = self.mouse.0;
+            *MOUSE_Y.cast_mut() = self.mouse.1;
+            *MOUSE_BUTTONS.cast_mut() = self.mouse_buttons;
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.gamepads);
+        buf.extend_from_slice(&self.mouse.0.to_le_bytes());
+        buf.extend_from_slice(&self.mouse.1.to_le_bytes());
+        buf.push(self.mouse_buttons);
+    }
+
+    #[cfg(debug_assertions)]
+    fn decode(reader: &mut Reader) -> Self {
+        Self {
+            gamepads: reader.bytes(),
+            mouse: (reader.u16() as i16, reader.u16() as i16),
+            mouse_buttons: reader.u8(),
+        }
+    }
+}
+
+/// A frame of input and how many frames in a row it was held.
+type Run = (Frame, u16);
+
+/// Where a capsule plays back from.
+#[derive(Clone)]
+pub struct Start {
+    pub game: usize,
+    pub seed: u64,
+    pub state: PlayerState,
+    pub snapshot: Option<Vec<u8>>,
+    /// The inputs on the frame the game launched, which the first frame
+    /// played back tells its presses from.
+    pub previous: Frame,
+}
+
+fn encode_state(buf: &mut Vec<u8>, state: &PlayerState) {
+    for bank in state.banks {
+        buf.extend_from_slice(&bank.to_le_bytes());
+    }
+    buf.extend_from_slice(&state.rounds.to_le_bytes());
+    buf.extend_from_slice(&state.session_start_bank.to_le_bytes());
+    buf.push(state.active_seats);
+    buf.extend_from_slice(&match state.promotion {
+        None => [0, 0],
+        Some(Promotion::DoubleBlackjack { hands_left }) => [1, hands_left],
+        Some(Promotion::HappyHour { hands_left }) => [2, hands_left],
+    });
+    let settings = &state.settings;
+    buf.extend_from_slice(&settings.loss_limit.unwrap_or(0).to_le_bytes());
+    buf.push(settings.difficulty as u8);
+    let controls = &settings.controls;
+    buf.push(controls.swap_buttons as u8 | (controls.direct_actions as u8) << 1 | (controls.mirrored as u8) << 2);
+    buf.push(settings.animation_speed as u8);
+    for user in 0..4 {
+        buf.extend_from_slice(&controls.packed_timings(user).to_le_bytes());
+    }
+}
+
+#[cfg(debug_assertions)]
+fn decode_state(reader: &mut Reader) -> PlayerState {
+    let mut state = PlayerState { looks: crate::avatars::Look::DEFAULTS, ..Default::default() };
+    state.banks = [(); 4].map(|_| reader.u32());
+    state.rounds = reader.u32();
+    state.session_start_bank = reader.u32();
+    state.active_seats = reader.u8();
+    let (promotion, hands_left) = (reader.u8(), reader.u8());
+    state.promotion = match promotion {
+        1 => Some(Promotion::DoubleBlackjack { hands_left }),
+        2 => Some(Promotion::HappyHour { hands_left }),
+        _ => None,
+    };
+    let settings = &mut state.settings;
+    let loss_limit = reader.u32();
+    settings.loss_limit = if loss_limit == 0 { None } else { Some(loss_limit) };
+    settings.difficulty = Difficulty::from_byte(reader.u8());
+    let controls = reader.u8();
+    settings.controls.swap_buttons = controls & 1 != 0;
+    settings.controls.direct_actions = controls & 2 != 0;
+    settings.controls.mirrored = controls & 4 != 0;
+    settings.animation_speed = AnimationSpeed::from_byte(reader.u8());
+    for user in 0..4 {
+        settings.controls.unpack_timings(user, reader.u16());
+    }
+    state
+}
+
+/// Keeps the capsule for the last game launched.
+pub struct Recorder {
+    start: Option<Start>,
+    /// The latest frame recorded, launched or not.
+    last: Frame,
+    /// Changes of input since the launch. Kept inline, so the log lives
+    /// with the runtime outside the heap, which couldn't spare a block this
+    /// size.
+    runs: FixedVec<Run, MAX_RUNS>,
+    /// Set once the log ran out of room and stopped.
+    full: bool,
+}
+
+impl Recorder {
+    pub const fn new() -> Self {
+        Self { start: None, last: Frame { gamepads: [0; 4], mouse: (0, 0), mouse_buttons: 0 }, runs: FixedVec::new(), full: false }
+    }
+
+    /// Logs a frame's inputs, once a game has launched.
+    pub fn record(&mut self, frame: Frame) {
+        self.last = frame;
+        if self.start.is_none() || self.full {
+            return
+        }
+        let runs = &mut self.runs;
+        if let Some((held, frames)) = runs.last_mut() {
+            if *held == frame && *frames < u16::MAX {
+                *frames += 1;
+                return
+            }
+        }
+        if runs.is_full() {
+            self.full = true;
+        } else {
+            runs.push((frame, 1));
+        }
+    }
+
+    /// Starts a fresh capsule for a game launching this frame.
+    pub fn start(&mut self, game: usize, seed: u64, state: PlayerState, snapshot: Option<&[u8]>) {
+        self.runs.clear();
+        self.full = false;
+        self.start = Some(Start { game, seed, state, snapshot: snapshot.map(Vec::from), previous: self.last });
+    }
+
+    pub fn started(&self) -> Option<&Start> {
+        self.start.as_ref()
+    }
+
+    /// Frames logged since the launch.
+    pub fn frames(&self) -> u32 {
+        self.runs.iter().map(|(_, frames)| *frames as u32).sum()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    pub fn encode(&self) -> Option<Vec<u8>> {
+        let start = self.start.as_ref()?;
+        let runs = &self.runs;
+        let mut buf = Vec::with_capacity(64 + runs.len() * 11);
+        buf.push(VERSION);
+        buf.push(start.game as u8);
+        buf.extend_from_slice(&start.seed.to_le_bytes());
+        encode_state(&mut buf, &start.state);
+        let snapshot = start.snapshot.as_deref().unwrap_or_default();
+        buf.push(snapshot.len() as u8);
+        buf.extend_from_slice(snapshot);
+        start.previous.encode(&mut buf);
+        buf.extend_from_slice(&(runs.len() as u16).to_le_bytes());
+        for (frame, frames) in runs.iter() {
+            frame.encode(&mut buf);
+            buf.extend_from_slice(&frames.to_le_bytes());
+        }
+        buf.extend_from_slice(&fletcher16(&buf).to_le_bytes());
+        Some(buf)
+    }
+}
+
+/// Hands `bytes` to `line` as lines of base32, five bits a character.
+pub fn to_text(bytes: &[u8], mut line: impl FnMut(&str)) {
+    let mut text = FixedVec::<u8, LINE_LEN>::new();
+    let (mut bits, mut held) = (0u32, 0);
+    let mut put = |value: u32, text: &mut FixedVec<u8, LINE_LEN>| {
+        text.push(ALPHABET[(value & 0x1f) as usize]);
+        if text.is_full() {
+            line(core::str::from_utf8(text).unwrap_or_default());
+            text.clear();
+        }
+    };
+    for byte in bytes {
+        bits = bits << 8 | *byte as u32;
+        held += 8;
+        while held >= 5 {
+            held -= 5;
+            put(bits >> held, &mut text);
+        }
+    }
+    if held > 0 {
+        put(bits << (5 - held), &mut text);
+    }
+    if !text.is_empty() {
+        line(core::str::from_utf8(&text).unwrap_or_default());
+    }
+}
+
+/// Reads back the bytes `to_text` wrote, ignoring whitespace between
+/// lines.
+#[cfg(debug_assertions)]
+pub fn from_text(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut bits, mut held) = (0u32, 0);
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = ALPHABET.iter().position(|a| *a == c.to_ascii_uppercase())? as u32;
+        bits = bits << 5 | value;
+        held += 5;
+        if held >= 8 {
+            held -= 8;
+            bytes.push((bits >> held) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// A capsule being played back, a frame of input at a time.
+#[cfg(debug_assertions)]
+pub struct Replay {
+    pub start: Start,
+    runs: Vec<Run>,
+    /// Frames played of the first run left.
+    played: u16,
+}
+
+#[cfg(debug_assertions)]
+impl Replay {
+    /// Reads a capsule, or `None` if it was cut short or mistyped.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let (data, check) = bytes.split_at_checked(bytes.len().checked_sub(2)?)?;
+        if fletcher16(data).to_le_bytes() != check {
+            return None
+        }
+        let reader = &mut Reader::new(data);
+        if reader.u8() != VERSION {
+            return None
+        }
+        let game = reader.u8() as usize;
+        let seed = reader.u64();
+        let state = decode_state(reader);
+        let snapshot_len = reader.u8() as usize;
+        let snapshot = (snapshot_len > 0).then(|| (0..snapshot_len).map(|_| reader.u8()).collect());
+        let previous = Frame::decode(reader);
+        let num_runs = reader.u16();
+        let mut runs: Vec<Run> = (0..num_runs).map(|_| (Frame::decode(reader), reader.u16())).collect();
+        // played from the back, so each frame comes off the end
+        runs.reverse();
+        Some(Self { start: Start { game, seed, state, snapshot, previous }, runs, played: 0 })
+    }
+
+    /// The next frame's inputs, or `None` once the capsule has played out.
+    pub fn next(&mut self) -> Option<Frame> {
+        let (frame, frames) = *self.runs.last()?;
+        self.played += 1;
+        if self.played >= frames {
+            self.runs.pop();
+            self.played = 0;
+        }
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(gamepad: u8) -> Frame {
+        Frame { gamepads: [gamepad, 0, 0, 0], mouse: (-3, 140), mouse_buttons: MOUSE_RIGHT }
+    }
+
+    #[test]
+    fn capsules_play_back_what_was_recorded() {
+        let mut recorder = Recorder::new();
+        recorder.record(frame(BUTTON_UP));
+        assert_eq!(recorder.frames(), 0, "nothing is kept before a launch");
+        let mut state = PlayerState { banks: [750, 0, 0, 20], rounds: 3, active_seats: 0b1001, ..Default::default() };
+        state.promotion = Some(Promotion::HappyHour { hands_left: 4 });
+        state.settings.controls.mirrored = true;
+        recorder.start(2, 0xdead_beef_f00d, state, Some(&[9, 8, 7]));
+        let played = [0, 0, BUTTON_1, BUTTON_1, BUTTON_1, 0, BUTTON_DOWN];
+        for gamepad in played {
+            recorder.record(frame(gamepad));
+        }
+        assert_eq!(recorder.frames(), 7);
+
+        let mut text = alloc::string::String::new();
+        to_text(&recorder.encode().unwrap(), |line| {
+            assert!(line.len() <= LINE_LEN);
+            text.push_str(line);
+            text.push('\n');
+        });
+        let mut replay = Replay::decode(&from_text(&text).unwrap()).unwrap();
+        let start = &replay.start;
+        assert_eq!((start.game, start.seed, start.previous), (2, 0xdead_beef_f00d, frame(BUTTON_UP)));
+        assert_eq!(start.snapshot.as_deref(), Some(&[9, 8, 7][..]));
+        assert_eq!((start.state.banks, start.state.rounds, start.state.active_seats), (state.banks, 3, 0b1001));
+        assert_eq!(start.state.promotion, state.promotion);
+        assert!(start.state.settings.controls.mirrored);
+        let replayed: Vec<Frame> = core::iter::from_fn(|| replay.next()).collect();
+        assert_eq!(replayed, played.map(frame));
+    }
+
+    #[test]
+    fn mistyped_capsules_are_refused() {
+        let mut recorder = Recorder::new();
+        recorder.start(0, 1, PlayerState::default(), None);
+        recorder.record(frame(BUTTON_1));
+        let mut bytes = recorder.encode().unwrap();
+        assert!(Replay::decode(&bytes).is_some());
+        bytes[1] ^= 1;
+        assert!(Replay::decode(&bytes).is_none());
+        assert!(from_text("not base32!").is_none());
+    }
+}
@@ -0,0 +1,149 @@
+//! Playing cards and how they're drawn, shared by every card game.
+
+use core::fmt;
+
+use crate::{
+    sprites::{self, Sprite},
+    ui::Display,
+    wasm4::*,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+pub enum CardValue {
+    Ace = 1,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King
+}
+
+impl fmt::Display for CardValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::Ace => "A",
+            Self::Two => "2",
+            Self::Three => "3",
+            Self::Four => "4",
+            Self::Five => "5",
+            Self::Six => "6",
+            Self::Seven => "7",
+            Self::Eight => "8",
+            Self::Nine => "9",
+            Self::Ten => "T",
+            Self::Jack => "J",
+            Self::Queen => "Q",
+            Self::King => "K",
+        })
+    }
+}
+
+impl CardValue {
+    pub(crate) fn values() -> [Self; 13] {
+        use CardValue::*;
+        [Ace, Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Jack, Queen, King]
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CardSuit {
+    Club,
+    Diamond,
+    Heart,
+    Spade,
+}
+
+impl CardSuit {
+    pub(crate) fn suits() -> [Self; 4] {
+        use CardSuit::*;
+        [Club, Diamond, Heart, Spade]
+    }
+
+    /// Red suits are drawn hollow in colorblind mode, so they don't rely on
+    /// color alone.
+    fn sprite(&self, display: Display) -> Sprite {
+        let colorblind = display.colorblind;
+        match self {
+            Self::Club => sprites::CLUB,
+            Self::Diamond if colorblind => sprites::DIAMOND_OPEN,
+            Self::Diamond => sprites::DIAMOND,
+            Self::Heart if colorblind => sprites::HEART_OPEN,
+            Self::Heart => sprites::HEART,
+            Self::Spade => sprites::SPADE,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Card {
+    pub value: CardValue,
+    pub suit: CardSuit,
+}
+
+impl Card {
+    pub(crate) fn draw_sprite(&self, x: i32, y: i32, face_up: bool, display: Display) {
+        self.draw_card(sprites::CARD, x, y, face_up, display);
+    }
+
+    /// A bigger card with a second suit pip, for games with only a few
+    /// cards on the table.
+    pub(crate) fn draw_large(&self, x: i32, y: i32, face_up: bool, display: Display) {
+        self.draw_card(sprites::CARD_LARGE, x, y, face_up, display);
+        if face_up {
+            self.suit.sprite(display).blit(x + 8, y + 13);
+        }
+    }
+
+    /// Draws the blank card, then the rank with its suit underneath, leaving
+    /// `DRAW_COLORS` set to the suit's color.
+    fn draw_card(&self, card_sprite: Sprite, x: i32, y: i32, face_up: bool, display: Display) {
+        if face_up {
+            unsafe {
+                *DRAW_COLORS = 0x0130;
+            }
+            card_sprite.blit(x, y);
+            match self.suit {
+                CardSuit::Club | CardSuit::Spade => {
+                    unsafe {
+                        *DRAW_COLORS |= 0x3000;
+                    }
+                }
+                CardSuit::Diamond | CardSuit::Heart => {
+                    unsafe {
+                        *DRAW_COLORS |= 0x2000;
+                    }
+                }
+            }
+            let value_sprite = match self.value {
+                CardValue::Ace => sprites::ACE,
+                CardValue::Two => sprites::TWO,
+                CardValue::Three => sprites::THREE,
+                CardValue::Four => sprites::FOUR,
+                CardValue::Five => sprites::FIVE,
+                CardValue::Six => sprites::SIX,
+                CardValue::Seven => sprites::SEVEN,
+                CardValue::Eight => sprites::EIGHT,
+                CardValue::Nine => sprites::NINE,
+                CardValue::Ten => sprites::TEN,
+                CardValue::Jack => sprites::JACK,
+                CardValue::Queen => sprites::QUEEN,
+                CardValue::King => sprites::KING
+            };
+            value_sprite.blit(x + 3, y + 2);
+            self.suit.sprite(display).blit(x + 3, y + 8);
+        } else {
+            unsafe {
+                *DRAW_COLORS = 0x0430;
+            }
+            card_sprite.blit(x, y);
+        }
+    }
+}
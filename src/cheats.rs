@@ -0,0 +1,153 @@
+//! The developer menu, only there with the `cheats` feature, which debug
+//! builds alone may turn on. Holding left and down together on the main
+//! menu opens it.
+//!
+//! It edits player one's bank, rigs the next game launched to pay out a
+//! jackpot, jumps straight to screens and blackjack hands that otherwise
+//! take a while to reach and overrides the animation speed picked in
+//! settings.
+
+use crate::{
+    layout::{self, Align},
+    model::Inputs,
+    money,
+    sfx,
+    ui::{Display, Label, Menu, Style},
+    wasm4::*,
+};
+
+const BANK_ROW: usize = 0;
+const STEP_ROW: usize = 1;
+const JACKPOT_ROW: usize = 2;
+const JUMP_ROW: usize = 3;
+const SPEED_ROW: usize = 4;
+const NUM_ROWS: usize = 5;
+/// What left and right take off or add to the bank.
+const STEPS: [u32; 5] = [1, 10, 100, 1000, 10000];
+/// Animation speeds to override settings with, `None` leaving them be.
+const SPEEDS: [Option<f32>; 5] = [None, Some(0.25), Some(0.5), Some(2.0), Some(4.0)];
+
+/// A screen or blackjack hand to go straight to.
+#[derive(Copy, Clone, Debug)]
+pub enum Jump {
+    Reminder,
+    Standings,
+    Slots,
+    /// A fresh blackjack table, the dealer showing an ace.
+    Insurance,
+    /// A fresh blackjack table, the dealer drawing to a stiff against a
+    /// split pair of eights.
+    SplitHand,
+}
+
+impl Jump {
+    const ALL: [Jump; 5] = [Jump::Reminder, Jump::Standings, Jump::Slots, Jump::Insurance, Jump::SplitHand];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Reminder => "Reminder",
+            Self::Standings => "Standings",
+            Self::Slots => "Slots",
+            Self::Insurance => "Insurance",
+            Self::SplitHand => "Split hand",
+        }
+    }
+}
+
+/// What the menu needs the main menu to do.
+pub enum Cheat {
+    Close,
+    Jump(Jump),
+}
+
+/// Whether player one is holding the combo that opens the menu.
+pub fn combo(inputs: &Inputs) -> bool {
+    inputs.press_left && inputs.press_down && (inputs.long_left || inputs.long_down)
+}
+
+pub struct CheatMenu {
+    pub open: bool,
+    /// Set to rig the next game launched, then cleared as it launches.
+    pub jackpot: bool,
+    menu: Menu,
+    step: usize,
+    jump: usize,
+    speed: usize,
+}
+
+impl CheatMenu {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            jackpot: false,
+            menu: Menu::new(NUM_ROWS, (14, 50), 10, 132, Style::MENU),
+            step: 2,
+            jump: 0,
+            speed: 0,
+        }
+    }
+
+    /// The animation speed to play at instead of the one from settings.
+    pub fn speed(&self) -> Option<f32> {
+        SPEEDS[self.speed]
+    }
+
+    /// Edits `bank` and the menu's own settings, returning what's left for
+    /// the main menu to do.
+    pub fn update(&mut self, inputs: &Inputs, bank: &mut u32) -> Option<Cheat> {
+        self.menu.navigate(inputs);
+        self.menu.clicked(inputs);
+        let step: isize = if inputs.tap_right {
+            1
+        } else if inputs.tap_left {
+            -1
+        } else {
+            0
+        };
+        let cycle = |index: usize, len: usize| (index as isize + step).rem_euclid(len as isize) as usize;
+        match self.menu.focus {
+            BANK_ROW if step > 0 => *bank = bank.saturating_add(STEPS[self.step]),
+            BANK_ROW if step < 0 => *bank = bank.saturating_sub(STEPS[self.step]),
+            STEP_ROW => self.step = cycle(self.step, STEPS.len()),
+            JACKPOT_ROW if step != 0 || inputs.tap_x => self.jackpot = !self.jackpot,
+            JUMP_ROW if inputs.tap_x => {
+                sfx::confirm();
+                return Some(Cheat::Jump(Jump::ALL[self.jump]))
+            }
+            JUMP_ROW => self.jump = cycle(self.jump, Jump::ALL.len()),
+            SPEED_ROW => self.speed = cycle(self.speed, SPEEDS.len()),
+            _ => {}
+        }
+        if step != 0 {
+            sfx::cursor_move();
+        }
+        if inputs.tap_z {
+            sfx::cancel();
+            return Some(Cheat::Close)
+        }
+        None
+    }
+
+    pub fn draw(&self, bank: u32, mouse: (i32, i32), display: Display) {
+        unsafe { *DRAW_COLORS = 0x23; }
+        rect(8, 36, 144, 72);
+        unsafe { *DRAW_COLORS = 0x01; }
+        layout::draw("Cheats", 80, 40, Align::Center);
+        let speed = match self.speed() {
+            Some(speed) => fixed_format!("Speed: {}x", speed),
+            None => fixed_format!("Speed: setting"),
+        };
+        let rows = [
+            fixed_format!("Bank: {}", money::chips(bank)),
+            fixed_format!("Step: {}", STEPS[self.step]),
+            fixed_format!("Jackpot: {}", if self.jackpot { "on" } else { "off" }),
+            fixed_format!("Jump: {}", Jump::ALL[self.jump].name()),
+            speed,
+        ];
+        for (row, label) in rows.iter().enumerate() {
+            self.menu.draw_row(row, label, false, mouse, display);
+        }
+        unsafe { *DRAW_COLORS = 0x01; }
+        Label::new(b"\x84\x85: change \x81: close", 14, 98).draw();
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{sprites, wasm4::*};
+
+/// How much higher each chip on a stack sits than the one below it.
+const CHIP_STEP: i32 = 2;
+/// Chips drawn on one stack; anything past this is left off the top.
+const MAX_STACK: u32 = 10;
+/// Room each stack takes across, gap included.
+const STACK_SPACING: i32 = 9;
+
+/// Chip values from the largest down, with the palette colors of their body
+/// and stripes.
+const DENOMINATIONS: [(u32, u16, u16); 5] = [
+    (500, 4, 3),
+    (100, 3, 1),
+    (25, 2, 3),
+    (5, 2, 1),
+    (1, 1, 2),
+];
+
+/// Draws `amount` as the fewest chips, one stack per value with the largest
+/// on the left, standing on the line `y`.
+pub fn draw_stacks(amount: u32, x: i32, y: i32) {
+    let mut left = amount;
+    let mut stack_x = x;
+    for (value, body, stripe) in DENOMINATIONS {
+        let count = left / value;
+        left %= value;
+        if count == 0 {
+            continue
+        }
+        // outlined in black, with white left see-through
+        unsafe { *DRAW_COLORS = 0x3000 | stripe << 8 | body << 4; }
+        for chip in 0..count.min(MAX_STACK) as i32 {
+            sprites::CHIP.blit(stack_x, y - sprites::CHIP.height as i32 - chip * CHIP_STEP);
+        }
+        stack_x += STACK_SPACING;
+    }
+}
@@ -0,0 +1,98 @@
+//! The screen left up when the cart panics. The panicking frame is traced
+//! and abandoned; each frame after draws the message until X is pressed,
+//! then the menu starts over from the last save.
+
+use core::fmt::{self, Write};
+
+use crate::{
+    effects::BASE_PALETTE,
+    global::Global,
+    layout::{self, Align},
+    wasm4::*,
+};
+
+/// Longest message kept; anything past it is cut off.
+const MESSAGE_LEN: usize = 120;
+
+pub struct Crash {
+    message: [u8; MESSAGE_LEN],
+    len: usize,
+    /// Set once X has been let go, so a press held through the panic
+    /// doesn't dismiss the screen straight away.
+    armed: bool,
+}
+
+impl Write for Crash {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        for &byte in text.as_bytes() {
+            if self.len == MESSAGE_LEN {
+                break
+            }
+            self.message[self.len] = byte;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Crash {
+    fn message(&self) -> &[u8] {
+        &self.message[..self.len]
+    }
+
+    fn draw(&self) {
+        unsafe {
+            *PALETTE = BASE_PALETTE;
+            *DRAW_COLORS = 0x22;
+        }
+        rect(0, 0, SCREEN_SIZE, SCREEN_SIZE);
+        unsafe { *DRAW_COLORS = 0x01; }
+        layout::draw(b"Something went wrong", 80, 10, Align::Center);
+        layout::draw_wrapped(self.message(), 4, 30, 152, Align::Left);
+        layout::draw_wrapped(b"Press \x80 to return to the menu", 80, 136, 152, Align::Center);
+    }
+}
+
+static CRASH: Global<Option<Crash>> = Global::new();
+
+pub fn init() {
+    CRASH.init(None);
+}
+
+/// Keeps the panic's message for the crash screen and draws it straight
+/// away, in case this frame is the last the console runs. Reached from the
+/// panic handler without std and from the panic hook with it.
+pub fn report(message: &dyn fmt::Display, location: Option<&core::panic::Location>) {
+    let mut crash = Crash { message: [0; MESSAGE_LEN], len: 0, armed: false };
+    let _ = write!(crash, "{}", message);
+    if let Some(location) = location {
+        let _ = write!(crash, " at {}:{}", location.file(), location.line());
+    }
+    trace(core::str::from_utf8(crash.message()).unwrap_or("panic"));
+    crash.draw();
+    // the panic may have come from inside the crash screen's own borrow
+    CRASH.reset(Some(crash));
+}
+
+/// Where the cart stands after a panic, checked at the top of each frame.
+pub enum Status {
+    Running,
+    /// The crash screen is up and the frame should go no further.
+    Crashed,
+    /// X was pressed on the crash screen, so the menu should start over.
+    Dismissed,
+}
+
+pub fn update() -> Status {
+    CRASH.with(|slot| {
+        let Some(crash) = slot else { return Status::Running };
+        let x_held = unsafe { *GAMEPAD1 } & BUTTON_1 != 0;
+        if crash.armed && x_held {
+            *slot = None;
+            return Status::Dismissed
+        }
+        crash.armed |= !x_held;
+        crash.draw();
+        Status::Crashed
+    })
+}
@@ -0,0 +1,25 @@
+use crate::{sprites, wasm4::*};
+
+/// The dealer's face, switched by what's happening at the table.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Expression {
+    Neutral,
+    Dealing,
+    /// The player lost the hand.
+    Disappointed,
+    /// The player won the hand.
+    Congratulating,
+}
+
+/// Draws the dealer's head and bow tie, 16x16, in the palette's own colors
+/// with white left see-through.
+pub fn draw(expression: Expression, x: i32, y: i32) {
+    let sprite = match expression {
+        Expression::Neutral => sprites::DEALER_NEUTRAL,
+        Expression::Dealing => sprites::DEALER_DEALING,
+        Expression::Disappointed => sprites::DEALER_DISAPPOINTED,
+        Expression::Congratulating => sprites::DEALER_CONGRATULATING,
+    };
+    unsafe { *DRAW_COLORS = 0x4320; }
+    sprite.blit(x, y);
+}
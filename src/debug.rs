@@ -0,0 +1,64 @@
+//! Numbers for tracking down slow frames and leaks, shown over everything
+//! else. Holding Z and double tapping up on the first gamepad toggles it.
+//!
+//! WASM-4 has no clock to time a frame by, so allocations per frame stand
+//! in as the cost to watch.
+
+use crate::{
+    model::{Inputs, Model},
+    wasm4::*,
+    PlayerState,
+};
+
+pub struct DebugOverlay {
+    visible: bool,
+    /// The allocation count as the frame began.
+    allocations: usize,
+}
+
+/// Allocations made since the cart started, or 0 when the system allocator
+/// is in use and there's nothing counting.
+fn allocations() -> usize {
+    #[cfg(all(feature = "buddy-alloc", target_family = "wasm"))]
+    return crate::heap::allocations();
+    #[cfg(not(all(feature = "buddy-alloc", target_family = "wasm")))]
+    0
+}
+
+impl DebugOverlay {
+    pub const fn new() -> Self {
+        Self { visible: false, allocations: 0 }
+    }
+
+    pub fn start_frame(&mut self) {
+        self.allocations = allocations();
+    }
+
+    /// Toggles the overlay, then draws it over whatever `game` drew.
+    pub fn end_frame(&mut self, inputs: &Inputs, game: &impl Model<PlayerState>) {
+        if inputs.press_z && inputs.double_up {
+            self.visible = !self.visible;
+        }
+        if !self.visible {
+            return
+        }
+        let frame_allocations = allocations() - self.allocations;
+        unsafe { *DRAW_COLORS = 0x31; }
+        let mut y = 0;
+        let mut line = |line: &str| {
+            text(line, 0, y);
+            y += 8;
+        };
+        #[cfg(all(feature = "buddy-alloc", target_family = "wasm"))]
+        {
+            let (in_use, peak) = crate::heap::usage();
+            line(&fixed_format!("heap {}/{}", in_use, crate::heap::CAPACITY));
+            line(&fixed_format!("peak {}", peak));
+        }
+        line(&fixed_format!("allocs {}/frame", frame_allocations));
+        if let Some(cards) = game.cards_left() {
+            line(&fixed_format!("shoe {}", cards));
+        }
+        line(game.state_name());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{anim::{Easing, Tween}, timers, ui::Display, wasm4::*};
+
+/// White, red, black and yellow, set at startup and put back after a flash.
+pub const BASE_PALETTE: [u32; 4] = [0xffffff, 0xc60e0e, 0x000000, 0xffef00];
+/// Blue and orange in place of red and yellow, told apart by any kind of
+/// color vision.
+const COLORBLIND_PALETTE: [u32; 4] = [0xffffff, 0x0072b2, 0x000000, 0xe69f00];
+
+const SHAKE_FRAMES: u8 = timers::secs(0.2) as u8;
+const FLASH_FRAMES: u8 = 8;
+/// Frames each flash holds, stretched out in high contrast mode.
+const FLASH_PERIOD: u8 = 2;
+const SLOW_FLASH_PERIOD: u8 = 8;
+/// How long a wipe takes, in seconds at the animation speed.
+const WIPE_SECONDS: f32 = 0.25;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum ScreenEffect {
+    /// Jolts the picture around for a moment, for a bust or a slam.
+    Shake,
+    /// Blinks the palette, for a big win.
+    Flash,
+}
+
+/// Shakes and flashes requested by games, applied to the finished frame so
+/// nothing has to draw at an offset.
+pub struct ScreenEffects {
+    shake_frames: u8,
+    flash_frames: u8,
+}
+
+impl ScreenEffects {
+    pub const fn new() -> Self {
+        Self {
+            shake_frames: 0,
+            flash_frames: 0,
+        }
+    }
+
+    pub fn request(&mut self, effect: ScreenEffect, display: Display) {
+        match effect {
+            ScreenEffect::Shake => self.shake_frames = SHAKE_FRAMES,
+            ScreenEffect::Flash => self.flash_frames = FLASH_FRAMES / FLASH_PERIOD * flash_period(display),
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.shake_frames > 0 || self.flash_frames > 0
+    }
+
+    pub fn update(&mut self) {
+        self.shake_frames = self.shake_frames.saturating_sub(1);
+        self.flash_frames = self.flash_frames.saturating_sub(1);
+    }
+
+    /// Offsets the framebuffer and picks the palette. Call once everything
+    /// else has been drawn.
+    pub fn apply(&self, display: Display) {
+        if self.shake_frames > 0 {
+            // back and forth, settling as it runs out
+            let amplitude = 1 + self.shake_frames as i32 / 4;
+            let dx = if self.shake_frames.is_multiple_of(2) { amplitude } else { -amplitude };
+            let dy = [0, 1, 0, -1][self.shake_frames as usize % 4];
+            shift_framebuffer(dx, dy);
+        }
+        let mut palette = if display.colorblind { COLORBLIND_PALETTE } else { BASE_PALETTE };
+        if self.flash_frames / flash_period(display) % 2 == 1 {
+            // the background and yellow trade places, lighting the table up
+            palette.swap(0, 3);
+        }
+        unsafe { *PALETTE = palette; }
+    }
+}
+
+fn flash_period(display: Display) -> u8 {
+    if display.high_contrast { SLOW_FLASH_PERIOD } else { FLASH_PERIOD }
+}
+
+/// Moves every pixel by `(dx, dy)`, filling the uncovered edge with the
+/// background color.
+fn shift_framebuffer(dx: i32, dy: i32) {
+    let size = SCREEN_SIZE as i32;
+    let framebuffer = unsafe { &mut *FRAMEBUFFER };
+    let drawn = *framebuffer;
+    // four pixels to a byte, leftmost in the low bits
+    let pixel = |x: i32, y: i32| {
+        let index = (y * size + x) as usize;
+        drawn[index / 4] >> (index % 4 * 2) & 0b11
+    };
+    for y in 0..size {
+        for x in 0..size {
+            let (from_x, from_y) = (x - dx, y - dy);
+            let color = if (0..size).contains(&from_x) && (0..size).contains(&from_y) { pixel(from_x, from_y) } else { 0 };
+            let index = (y * size + x) as usize;
+            let shift = index % 4 * 2;
+            framebuffer[index / 4] = framebuffer[index / 4] & !(0b11 << shift) | color << shift;
+        }
+    }
+}
+
+/// Which way a wipe clears off the screen.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Wipe {
+    /// Into a game, the curtain pulls away to the right.
+    Enter,
+    /// Back to the menu, the curtain pulls away to the left.
+    Return,
+}
+
+/// A curtain drawn over the new scene for a moment after switching, so it
+/// slides into view instead of cutting in.
+pub struct Transition {
+    wipe: Wipe,
+    tween: Tween,
+}
+
+impl Transition {
+    pub fn new(wipe: Wipe, frame: u32, speed: f32) -> Self {
+        Self {
+            wipe,
+            tween: Tween::new(frame, timers::anim(WIPE_SECONDS, speed), Easing::EaseInOut),
+        }
+    }
+
+    pub fn done(&self, frame: u32) -> bool {
+        self.tween.done(frame)
+    }
+
+    pub fn draw(&self, frame: u32) {
+        let width = self.tween.lerp(frame, SCREEN_SIZE as i32, 0);
+        if width <= 0 {
+            return
+        }
+        let (x, edge) = match self.wipe {
+            Wipe::Enter => (SCREEN_SIZE as i32 - width, SCREEN_SIZE as i32 - width),
+            Wipe::Return => (0, width - 1),
+        };
+        unsafe { *DRAW_COLORS = 0x3; }
+        rect(x, 0, width as u32, SCREEN_SIZE);
+        unsafe { *DRAW_COLORS = 0x4; }
+        vline(edge, 0, SCREEN_SIZE);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{model::Inputs, timers::{self, Countdown}, wasm4::*};
+
+/// How long a speech bubble stays up.
+const BUBBLE_FRAMES: u32 = timers::secs(2.0);
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Emote {
+    NiceHand,
+    Ouch,
+    HurryUp,
+    GoodGame,
+}
+
+impl Emote {
+    fn text(&self) -> &'static str {
+        match self {
+            Self::NiceHand => "Nice hand!",
+            Self::Ouch => "Ouch!",
+            Self::HurryUp => "Hurry up!",
+            Self::GoodGame => "gg",
+        }
+    }
+
+    /// The emote for whichever direction is held, going clockwise from up.
+    fn from_inputs(inputs: &Inputs) -> Option<Self> {
+        if inputs.press_up {
+            Some(Self::NiceHand)
+        } else if inputs.press_right {
+            Some(Self::Ouch)
+        } else if inputs.press_down {
+            Some(Self::HurryUp)
+        } else if inputs.press_left {
+            Some(Self::GoodGame)
+        } else {
+            None
+        }
+    }
+}
+
+/// Preset chat for multiplayer games. Holding a direction opens a picker
+/// with the four emotes around the player's marker and releasing it sends
+/// the highlighted one as a speech bubble.
+pub struct Emotes {
+    picking: [Option<Emote>; 4],
+    bubbles: [Option<(Emote, Countdown)>; 4],
+}
+
+impl Emotes {
+    pub fn new() -> Self {
+        Self {
+            picking: [None; 4],
+            bubbles: [None; 4],
+        }
+    }
+
+    /// `free` marks the players whose d-pad isn't being used by the game.
+    pub fn update(&mut self, inputs: &[Inputs; 4], free: [bool; 4]) {
+        for player in 0..4 {
+            if let Some((_, showing)) = &mut self.bubbles[player] {
+                if !showing.tick() {
+                    self.bubbles[player] = None;
+                }
+            }
+            if !free[player] {
+                self.picking[player] = None;
+                continue
+            }
+            match (Emote::from_inputs(&inputs[player]), self.picking[player]) {
+                (Some(emote), _) => self.picking[player] = Some(emote),
+                (None, Some(emote)) => {
+                    self.bubbles[player] = Some((emote, Countdown::new(BUBBLE_FRAMES)));
+                    self.picking[player] = None;
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    pub fn draw(&self) {
+        for player in 0..4 {
+            let y = 64 + 12 * player as i32;
+            if let Some(picked) = self.picking[player] {
+                // the four choices around the player's marker
+                unsafe { *DRAW_COLORS = 0x31; }
+                text(fixed_format!("P{}", player + 1), 68, 86);
+                for (emote, x, y) in [
+                    (Emote::NiceHand, 80, 74),
+                    (Emote::Ouch, 96, 86),
+                    (Emote::HurryUp, 80, 98),
+                    (Emote::GoodGame, 64, 86),
+                ] {
+                    unsafe { *DRAW_COLORS = if emote == picked { 0x43 } else { 0x31 }; }
+                    let label = emote.text();
+                    let x = match emote {
+                        Emote::GoodGame => x - 8 * label.len() as i32,
+                        Emote::Ouch => x,
+                        _ => x - 4 * label.len() as i32,
+                    };
+                    text(label, x, y);
+                }
+            } else if let Some((emote, _)) = self.bubbles[player] {
+                let message = fixed_format!("P{}: {}", player + 1, emote.text());
+                unsafe { *DRAW_COLORS = 0x31; }
+                rect(2, y - 2, 8 * message.len() as u32 + 4, 12);
+                unsafe { *DRAW_COLORS = 0x03; }
+                text(message, 4, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{fixed::{FixedString, LINE_LEN}, rng::GameRng, timers::{self, Countdown}, wasm4::*};
+
+const TOAST_FRAMES: u32 = timers::secs(3.0);
+/// One in this many game launches starts a promotion.
+const PROMOTION_ODDS: u32 = 5;
+
+/// A limited-time casino promotion. Games consult the active promotion when
+/// settling payouts and call `hand_played` once per settled hand.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Promotion {
+    /// Blackjack naturals pay double for the next few hands.
+    DoubleBlackjack { hands_left: u8 },
+    /// Table minimums are halved for the next few hands.
+    HappyHour { hands_left: u8 },
+}
+
+impl Promotion {
+    pub fn roll(rng: &mut impl GameRng) -> Option<Self> {
+        if rng.u32(..PROMOTION_ODDS) != 0 {
+            return None
+        }
+        rng.pick(&[Self::DoubleBlackjack { hands_left: 10 }, Self::HappyHour { hands_left: 10 }]).copied()
+    }
+
+    pub fn announcement(&self) -> &'static str {
+        match self {
+            Self::DoubleBlackjack { .. } => "2x Blackjack pays!",
+            Self::HappyHour { .. } => "Happy hour: 1/2 min",
+        }
+    }
+
+    pub fn blackjack_winnings(promotion: Option<Self>, winnings: u32) -> u32 {
+        match promotion {
+            Some(Self::DoubleBlackjack { .. }) => winnings * 2,
+            _ => winnings,
+        }
+    }
+
+    pub fn minimum_bet(promotion: Option<Self>, minimum: u32) -> u32 {
+        match promotion {
+            Some(Self::HappyHour { .. }) => minimum / 2,
+            _ => minimum,
+        }
+    }
+
+    /// Counts down the promotion, returning `None` once it has run out.
+    pub fn hand_played(self) -> Option<Self> {
+        match self {
+            Self::DoubleBlackjack { hands_left: 1 }
+            | Self::HappyHour { hands_left: 1 } => None,
+            Self::DoubleBlackjack { hands_left } => Some(Self::DoubleBlackjack { hands_left: hands_left - 1 }),
+            Self::HappyHour { hands_left } => Some(Self::HappyHour { hands_left: hands_left - 1 }),
+        }
+    }
+}
+
+/// A short message drawn over whatever screen is active.
+pub struct Toast {
+    message: FixedString<LINE_LEN>,
+    showing: Countdown,
+}
+
+impl Toast {
+    pub fn new(message: &str) -> Self {
+        Self {
+            message: fixed_format!("{}", message),
+            showing: Countdown::new(TOAST_FRAMES),
+        }
+    }
+
+    /// Returns false once the toast has finished showing.
+    pub fn tick(&mut self) -> bool {
+        self.showing.tick()
+    }
+
+    pub fn draw(&self) {
+        unsafe { *DRAW_COLORS = 0x43; }
+        rect(0, 44, 160, 14);
+        unsafe { *DRAW_COLORS = 0x04; }
+        let x = 80 - 4 * self.message.len() as i32;
+        text(&*self.message, x, 47);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Scripted;
+
+    #[test]
+    fn promotions_roll_one_launch_in_five() {
+        assert_eq!(Promotion::roll(&mut Scripted::new(&[1])), None);
+        assert_eq!(Promotion::roll(&mut Scripted::new(&[0, 1])), Some(Promotion::HappyHour { hands_left: 10 }));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::wasm4::*;
+
+/// Table cloth picked in settings and drawn under the cards by every table
+/// game.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum Felt {
+    #[default]
+    Classic,
+    Dotted,
+    Striped,
+    Gold,
+}
+
+impl Felt {
+    const ALL: [Felt; 4] = [Felt::Classic, Felt::Dotted, Felt::Striped, Felt::Gold];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Dotted => "Dotted",
+            Self::Striped => "Striped",
+            Self::Gold => "Gold",
+        }
+    }
+
+    /// Reads a felt stored as its `u8` repr, falling back to the default for
+    /// unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        Self::ALL.get(byte as usize).copied().unwrap_or_default()
+    }
+
+    pub fn cycled(&self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(*self as isize + step).rem_euclid(len) as usize]
+    }
+
+    /// Draws an oval of felt with a black edge.
+    pub fn draw(&self, x: i32, y: i32, width: u32, height: u32) {
+        unsafe { *DRAW_COLORS = if *self == Self::Gold { 0x34 } else { 0x32 }; }
+        oval(x, y, width, height);
+        let pattern_colors = match self {
+            Self::Dotted => 0x11,
+            Self::Striped => 0x33,
+            Self::Classic | Self::Gold => return,
+        };
+        unsafe { *DRAW_COLORS = pattern_colors; }
+        // half axes and center, doubled to stay in whole pixels
+        let (a, b) = (width as i32, height as i32);
+        let (cx, cy) = (2 * x + a, 2 * y + b);
+        for row in (y + 4..y + height as i32 - 4).step_by(4) {
+            // half width of the oval on this row, pulled in from the edge
+            let dy = (2 * row - cy) as f32 / b as f32;
+            let half = (a as f32 / 2.0 * libm::sqrtf((1.0 - dy * dy).max(0.0))) as i32 - 3;
+            if half <= 0 {
+                continue
+            }
+            let left = cx / 2 - half;
+            match self {
+                Self::Dotted => {
+                    let offset = if (row - y) % 8 == 0 { 0 } else { 2 };
+                    for column in (left + offset..cx / 2 + half).step_by(4) {
+                        rect(column, row, 1, 1);
+                    }
+                }
+                _ => hline(left, row, 2 * half as u32),
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! A vector kept in a fixed-size array, for the cards, hands and shoes that
+//! change every round, so play itself never goes to the heap. Text drawn
+//! every frame gets the same treatment with `FixedString`.
+
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+/// Bytes in a line of text built with `fixed_format!`, more than fit across
+/// the screen.
+pub const LINE_LEN: usize = 32;
+
+/// `format!` into a `FixedString` on the stack rather than a `String`, for
+/// text drawn every frame.
+macro_rules! fixed_format {
+    ($($arg:tt)+) => {
+        $crate::fixed::FixedString::<{ $crate::fixed::LINE_LEN }>::from_args(format_args!($($arg)+))
+    };
+}
+
+/// Up to `N` items stored inline. Reads go through the slice it derefs to;
+/// pushing past `N` panics, like indexing past the end of a slice.
+pub struct FixedVec<T: Copy, const N: usize> {
+    items: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> FixedVec<T, N> {
+    pub const fn new() -> Self {
+        Self { items: [MaybeUninit::uninit(); N], len: 0 }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn push(&mut self, item: T) {
+        assert!(self.len < N, "FixedVec is full");
+        self.items[self.len] = MaybeUninit::new(item);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        // everything below the old length was written by `push`
+        Some(unsafe { self.items[self.len].assume_init() })
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<T: Copy, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+    }
+}
+
+impl<T: Copy, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T: Copy, const N: usize> Clone for FixedVec<T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy, const N: usize> Copy for FixedVec<T, N> {}
+
+impl<T: Copy, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> Extend<T> for FixedVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Copy, const N: usize> FromIterator<T> for FixedVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut items = Self::new();
+        items.extend(iter);
+        items
+    }
+}
+
+/// Up to `N` bytes of text stored inline. Whatever would run past `N` is
+/// left off, a whole piece at a time, so it's always valid UTF-8.
+#[derive(Copy, Clone, Default)]
+pub struct FixedString<const N: usize>(FixedVec<u8, N>);
+
+impl<const N: usize> FixedString<N> {
+    pub const fn new() -> Self {
+        Self(FixedVec::new())
+    }
+
+    /// `args` written out the way `format!` would, cut short if they don't
+    /// fit.
+    pub fn from_args(args: fmt::Arguments) -> Self {
+        let mut text = Self::new();
+        // running out of room leaves what fit, which is all there is to do
+        let _ = fmt::Write::write_fmt(&mut text, args);
+        text
+    }
+}
+
+impl<const N: usize> fmt::Write for FixedString<N> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if self.0.len() + text.len() > N {
+            return Err(fmt::Error)
+        }
+        self.0.extend(text.bytes());
+        Ok(())
+    }
+}
+
+impl<const N: usize> Deref for FixedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // only ever written whole `str`s
+        unsafe { core::str::from_utf8_unchecked(&self.0) }
+    }
+}
+
+impl<const N: usize> AsRef<str> for FixedString<N> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedString<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl<const N: usize> fmt::Debug for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for FixedString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        &**self == *other
+    }
+}
@@ -0,0 +1,79 @@
+//! The states a game moves through, each with its own frame of play and
+//! hooks for moving in and out, run by a `Machine`.
+//!
+//! A state only holds what belongs to it, like the hands on the table while
+//! they're played. Everything that outlasts a state, the chips and the shoe
+//! say, is the machine's `Context`, which every state is handed in turn. A
+//! state hands over to the next by value, so nothing has to be cloned
+//! across or left behind in the old one.
+
+/// What a state wants once its frame is up.
+pub enum Transition<S: State> {
+    /// Carry on in this state next frame.
+    Stay,
+    /// Leave this state for another.
+    To(S),
+    /// The machine has run its course, with something for whoever runs it.
+    Finish(S::Output),
+}
+
+pub trait State: Sized {
+    /// What outlasts any one state.
+    type Context;
+    /// What a frame of play is given.
+    type Input;
+    /// What a finished machine hands back.
+    type Output;
+    type Error;
+
+    /// For logs and the debug overlay.
+    fn name(&self) -> &'static str;
+
+    /// A frame of play in this state.
+    fn update(&mut self, context: &mut Self::Context, input: &Self::Input) -> Result<Transition<Self>, Self::Error>;
+
+    /// Runs as the machine moves into this state.
+    fn on_enter(&mut self, _context: &mut Self::Context) {}
+
+    /// Runs as the machine moves out of this state, before the next is
+    /// entered.
+    fn on_exit(&mut self, _context: &mut Self::Context) {}
+}
+
+/// Holds the current state and moves between states, running their hooks
+/// on the way.
+pub struct Machine<S> {
+    state: S,
+}
+
+impl<S: State> Machine<S> {
+    /// Starts in `state` without entering it, so a state restored from a
+    /// snapshot doesn't run its hooks a second time.
+    pub const fn new(state: S) -> Self {
+        Self { state }
+    }
+
+    pub fn current(&self) -> &S {
+        &self.state
+    }
+
+    /// A frame of play in the current state, moving on if it asks to.
+    /// Returns the output once the machine finishes.
+    pub fn update(&mut self, context: &mut S::Context, input: &S::Input) -> Result<Option<S::Output>, S::Error> {
+        match self.state.update(context, input)? {
+            Transition::Stay => Ok(None),
+            Transition::To(next) => {
+                self.go(context, next);
+                Ok(None)
+            }
+            Transition::Finish(output) => Ok(Some(output)),
+        }
+    }
+
+    /// Moves to `next` from outside any state's frame, hooks and all.
+    pub fn go(&mut self, context: &mut S::Context, next: S) {
+        self.state.on_exit(context);
+        self.state = next;
+        self.state.on_enter(context);
+    }
+}
@@ -0,0 +1,147 @@
+//! Everything the menu can hand control to, as one enum so whichever is
+//! running sits in `MainGame` by value rather than in a box.
+
+use alloc::vec::Vec;
+use crate::{
+    effects::ScreenEffect,
+    model::{GameError, GameEvent, Inputs, Model},
+    particles::Burst,
+    settings::SettingsScreen,
+    PlayerState,
+};
+#[cfg(feature = "blackjack")]
+use crate::BlackJack;
+#[cfg(feature = "holdem")]
+use crate::HoldEm;
+#[cfg(feature = "party")]
+use crate::PartyMode;
+#[cfg(feature = "versus")]
+use crate::Versus;
+
+/// Each game's variant is only there when its feature is enabled.
+#[allow(clippy::large_enum_variant)]
+pub enum Game {
+    #[cfg(feature = "blackjack")]
+    BlackJack(BlackJack),
+    #[cfg(feature = "party")]
+    Party(PartyMode),
+    #[cfg(feature = "versus")]
+    Versus(Versus),
+    #[cfg(feature = "holdem")]
+    HoldEm(HoldEm),
+    Settings(SettingsScreen),
+}
+
+/// Runs `$body` with `$game` bound to whichever screen is inside.
+macro_rules! dispatch {
+    ($self:expr, $game:ident => $body:expr) => {
+        match $self {
+            #[cfg(feature = "blackjack")]
+            Game::BlackJack($game) => $body,
+            #[cfg(feature = "party")]
+            Game::Party($game) => $body,
+            #[cfg(feature = "versus")]
+            Game::Versus($game) => $body,
+            #[cfg(feature = "holdem")]
+            Game::HoldEm($game) => $body,
+            Game::Settings($game) => $body,
+        }
+    };
+}
+
+impl Game {
+    /// Rigs the next hand to pay out a jackpot, in games that have one.
+    /// Returns whether it could.
+    #[cfg(feature = "cheats")]
+    pub fn force_jackpot(&mut self) -> bool {
+        match self {
+            #[cfg(feature = "blackjack")]
+            Game::BlackJack(game) => game.force_jackpot(),
+            #[cfg(feature = "holdem")]
+            Game::HoldEm(game) => game.force_jackpot(),
+            _ => false,
+        }
+    }
+
+    /// Deals straight into the hand `jump` names, in games that have it.
+    /// Returns whether it could.
+    #[cfg(feature = "cheats")]
+    #[cfg_attr(not(feature = "blackjack"), allow(unused_variables))]
+    pub fn jump(&mut self, jump: crate::cheats::Jump) -> bool {
+        match self {
+            #[cfg(feature = "blackjack")]
+            Game::BlackJack(game) => game.jump(jump),
+            _ => false,
+        }
+    }
+}
+
+impl Model<PlayerState> for Game {
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<PlayerState> {
+        dispatch!(self, game => game.update(inputs))
+    }
+
+    fn draw(&self) {
+        dispatch!(self, game => game.draw())
+    }
+
+    fn share_state(&mut self, state: PlayerState) {
+        dispatch!(self, game => game.share_state(state))
+    }
+
+    fn peek_state(&self) -> Option<PlayerState> {
+        dispatch!(self, game => game.peek_state())
+    }
+
+    fn bet_live(&self) -> bool {
+        dispatch!(self, game => game.bet_live())
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        dispatch!(self, game => game.snapshot())
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> bool {
+        dispatch!(self, game => game.restore(snapshot))
+    }
+
+    fn needs_redraw(&self) -> bool {
+        dispatch!(self, game => game.needs_redraw())
+    }
+
+    fn take_burst(&mut self) -> Option<Burst> {
+        dispatch!(self, game => game.take_burst())
+    }
+
+    fn take_screen_effect(&mut self) -> Option<ScreenEffect> {
+        dispatch!(self, game => game.take_screen_effect())
+    }
+
+    fn take_error(&mut self) -> Option<GameError> {
+        dispatch!(self, game => game.take_error())
+    }
+
+    fn state_name(&self) -> &'static str {
+        dispatch!(self, game => game.state_name())
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        dispatch!(self, game => game.cards_left())
+    }
+
+    fn on_enter(&mut self) {
+        dispatch!(self, game => game.on_enter())
+    }
+
+    fn on_exit(&mut self) {
+        dispatch!(self, game => game.on_exit())
+    }
+
+    fn on_pause(&mut self) {
+        dispatch!(self, game => game.on_pause())
+    }
+
+    fn on_resume(&mut self) {
+        dispatch!(self, game => game.on_resume())
+    }
+}
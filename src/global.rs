@@ -0,0 +1,48 @@
+//! A home for state that lives across frames, without `static mut`.
+
+use core::cell::{Cell, UnsafeCell};
+
+/// A value set once in `start` and lent out to each frame after that.
+/// Borrowing it again while it's already lent out panics rather than
+/// aliasing.
+pub struct Global<T> {
+    value: UnsafeCell<Option<T>>,
+    lent: Cell<bool>,
+}
+
+// WASM-4 runs a cart on a single thread, so the cell is never reached from
+// two threads at once.
+unsafe impl<T> Sync for Global<T> {}
+
+impl<T> Global<T> {
+    pub const fn new() -> Self {
+        Self { value: UnsafeCell::new(None), lent: Cell::new(false) }
+    }
+
+    pub fn init(&self, value: T) {
+        self.with_slot(|slot| {
+            assert!(slot.is_none(), "global initialized twice");
+            *slot = Some(value);
+        })
+    }
+
+    /// Runs `f` on the value. Panics before `init`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.with_slot(|slot| f(slot.as_mut().expect("global used before init")))
+    }
+
+    fn with_slot<R>(&self, f: impl FnOnce(&mut Option<T>) -> R) -> R {
+        assert!(!self.lent.replace(true), "global borrowed twice");
+        // the flag keeps this the only reference until it's cleared
+        let result = f(unsafe { &mut *self.value.get() });
+        self.lent.set(false);
+        result
+    }
+
+    /// Swaps in a new value even if a panic left the old one lent out. The
+    /// panicked frame never resumes, so nothing still holds the old one.
+    pub fn reset(&self, value: T) {
+        self.lent.set(false);
+        self.with_slot(|slot| *slot = Some(value));
+    }
+}
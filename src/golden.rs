@@ -0,0 +1,140 @@
+//! Screens drawn on the headless console and compared with the images in
+//! `tests/golden`, to catch layout slipping on the 160x160 screen.
+//!
+//! A screen that no longer matches is written to `target/golden` to look
+//! over. If the change is meant, `UPDATE_GOLDEN=1 cargo test` writes every
+//! screen drawn over its golden image.
+
+use alloc::{format, vec, vec::Vec};
+use std::{env, fs, path::Path};
+
+use crate::{effects, headless::Session, sprites, wasm4::*, Runtime};
+
+const SIZE: usize = SCREEN_SIZE as usize;
+
+/// The framebuffer as RGB, through the palette it was drawn with.
+fn capture() -> Vec<u8> {
+    let (framebuffer, palette) = unsafe { (*FRAMEBUFFER, *PALETTE) };
+    (0..SIZE * SIZE)
+        .flat_map(|index| {
+            let color = palette[(framebuffer[index / 4] >> (index % 4 * 2) & 3) as usize];
+            [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+        })
+        .collect()
+}
+
+fn read_png(path: &Path) -> Option<Vec<u8>> {
+    let mut reader = png::Decoder::new(fs::File::open(path).ok()?).read_info().ok()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).ok()?;
+    pixels.truncate(info.buffer_size());
+    Some(pixels)
+}
+
+fn write_png(path: &Path, pixels: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut encoder = png::Encoder::new(fs::File::create(path).unwrap(), SCREEN_SIZE, SCREEN_SIZE);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
+
+/// Compares what's on screen with the golden image called `name`.
+pub fn check(name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let golden = root.join("tests/golden").join(format!("{}.png", name));
+    let pixels = capture();
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&golden, &pixels);
+        return
+    }
+    let expected = read_png(&golden)
+        .unwrap_or_else(|| panic!("no golden image at {}, run with UPDATE_GOLDEN=1 to draw it", golden.display()));
+    if pixels != expected {
+        let actual = root.join("target/golden").join(format!("{}.png", name));
+        write_png(&actual, &pixels);
+        let different = pixels.chunks(3).zip(expected.chunks(3)).filter(|(a, b)| a != b).count();
+        panic!("{} differs from its golden image in {} pixels, see {}", name, different, actual.display());
+    }
+}
+
+/// Runs the cart as the console would, from `start`, pressing `buttons` on
+/// the first gamepad for a frame at each of `presses`.
+fn run(console: &Session, frames: usize, presses: &[(usize, u8)]) -> Runtime {
+    let mut runtime = Runtime::new();
+    unsafe { *PALETTE = effects::BASE_PALETTE; }
+    runtime.game.init();
+    for frame in 0..frames {
+        let buttons = presses.iter().find(|(at, _)| *at == frame).map_or(0, |(_, buttons)| *buttons);
+        console.set_gamepad(0, buttons);
+        runtime.update();
+    }
+    runtime
+}
+
+#[test]
+fn slot_select() {
+    let console = crate::headless::session();
+    run(&console, 30, &[]);
+    check("slot_select");
+}
+
+// the golden menu lists every game
+#[test]
+#[cfg(all(feature = "blackjack", feature = "party", feature = "versus", feature = "holdem"))]
+fn menu() {
+    use crate::{model::Model, profiles::Profile, save::SaveData};
+
+    let console = crate::headless::session();
+    SaveData { profiles: vec![Profile::new(*b"BEN")], ..SaveData::default() }.store(0).unwrap();
+    // X picks the first slot, then its only profile
+    let runtime = run(&console, 90, &[(10, BUTTON_1), (20, BUTTON_1)]);
+    assert_eq!(runtime.game.state_name(), "Menu");
+    check("menu");
+}
+
+/// An asset PNG packed four pixels to a byte by palette slot, the way
+/// `build.rs` packs it, to check the cart's copy against.
+fn read_asset(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(format!("{}.png", name));
+    let mut decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let channels = info.color_type.samples();
+    let mut data = vec![0; (info.width * info.height).div_ceil(4) as usize];
+    for (index, pixel) in buf[..info.buffer_size()].chunks(channels).enumerate() {
+        let rgb = match pixel.len() {
+            1 | 2 => u32::from_be_bytes([0, pixel[0], pixel[0], pixel[0]]),
+            _ => u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]),
+        };
+        let slot = effects::BASE_PALETTE.iter().position(|color| *color == rgb).unwrap() as u8;
+        data[index / 4] |= slot << ((3 - index % 4) * 2);
+    }
+    data
+}
+
+#[test]
+fn sprites_decode_to_their_assets() {
+    let mut buffer = [0; 4096];
+    for (name, sprite) in sprites::ALL {
+        assert_eq!(sprite.decode(&mut buffer), read_asset(name), "{} decodes wrong", name);
+    }
+}
+
+#[test]
+fn truncated_sprites_decode_short() {
+    let mut full = [0; 4096];
+    let mut buffer = [0; 4096];
+    for (name, sprite) in sprites::ALL {
+        let full = sprite.decode(&mut full);
+        for len in 0.. {
+            let Some(cut) = sprite.truncated(len) else { break };
+            let decoded = cut.decode(&mut buffer);
+            assert!(full.starts_with(decoded), "{} cut to {} bytes decodes wrong", name, len);
+        }
+        // a buffer too small stops the sprite short as well
+        let short = full.len() / 2;
+        assert!(full.starts_with(sprite.decode(&mut buffer[..short])), "{} overran its buffer", name);
+    }
+}
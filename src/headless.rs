@@ -0,0 +1,215 @@
+//! A stand-in for the console when the cart runs on the host, for tests.
+//! It keeps its own copy of the console's memory for the registers and
+//! framebuffer and a disk, and prints whatever is traced. Sound is dropped.
+//!
+//! Drawing follows the console's rules for `DRAW_COLORS`, clipping and
+//! sprite flags closely enough to catch layout changes, but it isn't the
+//! console's rasterizer: ovals and lines can land a pixel off from it, and
+//! the console's font isn't bundled, so each glyph comes out as its byte's
+//! bits in stripes.
+
+use alloc::vec::Vec;
+use std::sync::{Mutex, PoisonError};
+
+use crate::{platform::Platform, wasm4::*};
+
+/// Bytes up to the end of the framebuffer, which is all of WASM-4's memory
+/// the cart reads or writes directly.
+const MEMORY_SIZE: usize = 0x19a0;
+const DISK_SIZE: usize = 1024;
+
+// words rather than bytes, so the palette and draw colors are aligned
+static mut RAM: [u32; MEMORY_SIZE / 4] = [0; MEMORY_SIZE / 4];
+
+/// Where `wasm4`'s register addresses start from off the console.
+pub const MEMORY: *mut u8 = (&raw mut RAM).cast();
+
+static DISK: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+fn with_disk<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    // a test that panics mid-frame shouldn't take the others down with it
+    f(&mut DISK.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+const SIZE: i32 = SCREEN_SIZE as i32;
+
+fn draw_colors() -> u16 {
+    unsafe { *DRAW_COLORS }
+}
+
+/// Sets a pixel to a `DRAW_COLORS` entry: 0 leaves it be and 1 to 4 pick a
+/// palette color.
+fn point(color: u16, x: i32, y: i32) {
+    if color == 0 || !(0..SIZE).contains(&x) || !(0..SIZE).contains(&y) {
+        return
+    }
+    let index = (y * SIZE + x) as usize;
+    let shift = (index % 4) * 2;
+    let byte = unsafe { &mut (*FRAMEBUFFER)[index / 4] };
+    *byte = *byte & !(3 << shift) | (((color - 1) & 3) as u8) << shift;
+}
+
+pub struct Headless;
+
+impl Platform for Headless {
+    fn blit(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32) {
+        Self::blit_sub(sprite, x, y, width, height, 0, 0, width, flags)
+    }
+
+    fn blit_sub(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, src_x: u32, src_y: u32, stride: u32, flags: u32) {
+        let colors = draw_colors();
+        for row in 0..height {
+            for col in 0..width {
+                let sx = if flags & BLIT_FLIP_X != 0 { width - 1 - col } else { col };
+                let sy = if flags & BLIT_FLIP_Y != 0 { height - 1 - row } else { row };
+                let bit = ((src_y + sy) * stride + src_x + sx) as usize;
+                let index = if flags & BLIT_2BPP != 0 {
+                    sprite[bit / 4] >> (6 - bit % 4 * 2) & 3
+                } else {
+                    sprite[bit / 8] >> (7 - bit % 8) & 1
+                };
+                // rotating turns the sprite a quarter anticlockwise
+                let (dx, dy) = if flags & BLIT_ROTATE != 0 { (row, width - 1 - col) } else { (col, row) };
+                point(colors >> (index * 4) & 0xf, x + dx as i32, y + dy as i32);
+            }
+        }
+    }
+
+    fn line(x1: i32, y1: i32, x2: i32, y2: i32) {
+        let color = draw_colors() & 0xf;
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (step_x, step_y) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y, mut error) = (x1, y1, dx + dy);
+        loop {
+            point(color, x, y);
+            if (x, y) == (x2, y2) {
+                break
+            }
+            if error * 2 >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if error * 2 <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn oval(x: i32, y: i32, width: u32, height: u32) {
+        let colors = draw_colors();
+        let (fill, stroke) = (colors & 0xf, colors >> 4 & 0xf);
+        let (radius_x, radius_y) = (width as f32 / 2.0, height as f32 / 2.0);
+        let inside = |px: i32, py: i32| {
+            let nx = (px - x) as f32 + 0.5 - radius_x;
+            let ny = (py - y) as f32 + 0.5 - radius_y;
+            (nx / radius_x).powi(2) + (ny / radius_y).powi(2) <= 1.0
+        };
+        for py in y..y + height as i32 {
+            for px in x..x + width as i32 {
+                if !inside(px, py) {
+                    continue
+                }
+                let edge = !(inside(px - 1, py) && inside(px + 1, py) && inside(px, py - 1) && inside(px, py + 1));
+                point(if edge && stroke != 0 { stroke } else { fill }, px, py);
+            }
+        }
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) {
+        let colors = draw_colors();
+        let (fill, stroke) = (colors & 0xf, colors >> 4 & 0xf);
+        let (right, bottom) = (x + width as i32 - 1, y + height as i32 - 1);
+        for py in y..=bottom {
+            for px in x..=right {
+                let edge = px == x || px == right || py == y || py == bottom;
+                point(if edge && stroke != 0 { stroke } else { fill }, px, py);
+            }
+        }
+    }
+
+    fn text(text: &[u8], x: i32, y: i32) {
+        let colors = draw_colors();
+        let (mut left, mut top) = (x, y);
+        for &byte in text {
+            match byte {
+                0 => break,
+                b'\n' => {
+                    left = x;
+                    top += 8;
+                }
+                _ => {
+                    for row in 0..8 {
+                        for col in 0..8 {
+                            let on = (1..7).contains(&row) && byte >> (7 - col) & 1 != 0;
+                            point(if on { colors & 0xf } else { colors >> 4 & 0xf }, left + col, top + row);
+                        }
+                    }
+                    left += 8;
+                }
+            }
+        }
+    }
+
+    fn vline(x: i32, y: i32, len: u32) {
+        let color = draw_colors() & 0xf;
+        for py in y..y + len as i32 {
+            point(color, x, py);
+        }
+    }
+
+    fn hline(x: i32, y: i32, len: u32) {
+        let color = draw_colors() & 0xf;
+        for px in x..x + len as i32 {
+            point(color, px, y);
+        }
+    }
+
+    fn tone(_: u32, _: u32, _: u32, _: u32) {}
+
+    fn diskr(dest: &mut [u8]) -> u32 {
+        with_disk(|disk| {
+            let len = dest.len().min(disk.len());
+            dest[..len].copy_from_slice(&disk[..len]);
+            len as u32
+        })
+    }
+
+    fn diskw(src: &[u8]) -> u32 {
+        with_disk(|disk| {
+            let len = src.len().min(DISK_SIZE);
+            *disk = src[..len].to_vec();
+            len as u32
+        })
+    }
+
+    fn trace(text: &str) {
+        // shown by `cargo test` when the test fails
+        std::eprintln!("{}", text);
+    }
+}
+
+/// Sole use of the headless console for one test. There's only one
+/// console, so tests that touch it wait their turn rather than racing.
+#[cfg(test)]
+pub struct Session {
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+/// Waits for the console, then wipes its memory and disk.
+#[cfg(test)]
+pub fn session() -> Session {
+    static SESSION: Mutex<()> = Mutex::new(());
+    let lock = SESSION.lock().unwrap_or_else(PoisonError::into_inner);
+    unsafe { RAM = [0; MEMORY_SIZE / 4] };
+    with_disk(Vec::clear);
+    Session { _lock: lock }
+}
+
+#[cfg(test)]
+impl Session {
+    /// Holds `buttons` down on a gamepad, numbered from 0, until changed.
+    pub fn set_gamepad(&self, gamepad: usize, buttons: u8) {
+        unsafe { *GAMEPAD1.add(gamepad).cast_mut() = buttons };
+    }
+}
@@ -0,0 +1,64 @@
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use buddy_alloc::{BuddyAllocParam, FastAllocParam, NonThreadsafeAlloc};
+
+// These values can be tuned
+const FAST_HEAP_SIZE: usize = 4 * 1024; // 4 KB
+const HEAP_SIZE: usize = 16 * 1024; // 16 KB
+const LEAF_SIZE: usize = 16;
+
+static mut FAST_HEAP: [u8; FAST_HEAP_SIZE] = [0u8; FAST_HEAP_SIZE];
+static mut HEAP: [u8; HEAP_SIZE] = [0u8; HEAP_SIZE];
+
+/// The buddy allocator, keeping count of what it hands out for the debug
+/// overlay.
+struct Counted {
+    alloc: NonThreadsafeAlloc,
+    in_use: AtomicUsize,
+    peak: AtomicUsize,
+    allocations: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for Counted {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.alloc.alloc(layout);
+        if !ptr.is_null() {
+            let in_use = self.in_use.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            self.peak.fetch_max(in_use, Ordering::Relaxed);
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.alloc.dealloc(ptr, layout);
+        self.in_use.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOC: Counted = Counted {
+    alloc: unsafe {
+        let fast_param = FastAllocParam::new(FAST_HEAP.as_ptr(), FAST_HEAP_SIZE);
+        let buddy_param = BuddyAllocParam::new(HEAP.as_ptr(), HEAP_SIZE, LEAF_SIZE);
+        NonThreadsafeAlloc::new(fast_param, buddy_param)
+    },
+    in_use: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+    allocations: AtomicUsize::new(0),
+};
+
+/// Bytes allocated right now and at most so far, out of both heaps.
+pub fn usage() -> (usize, usize) {
+    (ALLOC.in_use.load(Ordering::Relaxed), ALLOC.peak.load(Ordering::Relaxed))
+}
+
+pub const CAPACITY: usize = FAST_HEAP_SIZE + HEAP_SIZE;
+
+/// Allocations made since the cart started.
+pub fn allocations() -> usize {
+    ALLOC.allocations.load(Ordering::Relaxed)
+}
@@ -0,0 +1,201 @@
+use crate::{
+    layout::{self, Align, LINE_HEIGHT},
+    model::Inputs,
+    sfx,
+    ui::{self, Display, Menu, Style},
+    wasm4::*,
+};
+
+const TEXT_WIDTH: i32 = 148;
+/// The part of the screen pages scroll through, between the title and the
+/// controls along the bottom.
+const VIEW_TOP: i32 = 18;
+const VIEW_BOTTOM: i32 = 140;
+
+/// A picture at the top of a page, drawn by the game it's about.
+pub struct Diagram {
+    pub height: i32,
+    /// Draws the diagram with its top edge at the given y.
+    pub draw: fn(i32, Display),
+}
+
+/// One page of a game's rules. Text longer than the screen scrolls, and the
+/// diagram scrolls away with it.
+pub struct Page {
+    pub title: &'static str,
+    pub diagram: Option<Diagram>,
+    /// May hold button glyphs like `\x80`.
+    pub text: &'static [u8],
+}
+
+impl Page {
+    fn diagram_height(&self) -> i32 {
+        self.diagram.as_ref().map_or(0, |diagram| diagram.height + 4)
+    }
+
+    /// How far the page can scroll before its last line is at the bottom.
+    fn max_scroll(&self) -> i32 {
+        let height = self.diagram_height() + LINE_HEIGHT * layout::wrap(self.text, TEXT_WIDTH).len() as i32;
+        (height - (VIEW_BOTTOM - VIEW_TOP)).max(0)
+    }
+}
+
+/// Paging through a game's rules, one page across at a time.
+pub struct RulesScreen {
+    pages: &'static [Page],
+    page: usize,
+    scroll: i32,
+}
+
+impl RulesScreen {
+    pub fn new(pages: &'static [Page]) -> Self {
+        Self { pages, page: 0, scroll: 0 }
+    }
+
+    /// Returns true once Z closes the rules.
+    pub fn update(&mut self, inputs: &Inputs) -> bool {
+        if inputs.tap_z {
+            sfx::cancel();
+            return true
+        }
+        let page_count = self.pages.len();
+        if inputs.tap_right && self.page + 1 < page_count {
+            self.page += 1;
+            self.scroll = 0;
+            sfx::cursor_move();
+        }
+        if inputs.tap_left && self.page > 0 {
+            self.page -= 1;
+            self.scroll = 0;
+            sfx::cursor_move();
+        }
+        let max_scroll = self.pages.get(self.page).map_or(0, Page::max_scroll);
+        if inputs.tap_down {
+            self.scroll = (self.scroll + LINE_HEIGHT).min(max_scroll);
+        }
+        if inputs.tap_up {
+            self.scroll = (self.scroll - LINE_HEIGHT).max(0);
+        }
+        false
+    }
+
+    pub fn draw(&self, display: Display) {
+        unsafe { *DRAW_COLORS = 0x11; }
+        rect(0, 0, SCREEN_SIZE, SCREEN_SIZE);
+        let Some(page) = self.pages.get(self.page) else { return };
+        ui::set_text_colors(0x03, display);
+        text(page.title, 4, 4);
+        layout::draw(fixed_format!("{}/{}", self.page + 1, self.pages.len()), 156, 4, Align::Right);
+        unsafe { *DRAW_COLORS = 0x03; }
+        hline(0, VIEW_TOP - 4, SCREEN_SIZE);
+        // anything only partly inside the view is left off rather than
+        // drawn over the title or controls
+        let mut y = VIEW_TOP - self.scroll;
+        if let Some(diagram) = &page.diagram {
+            if y >= VIEW_TOP && y + diagram.height <= VIEW_BOTTOM {
+                (diagram.draw)(y, display);
+            }
+        }
+        y += page.diagram_height();
+        ui::set_text_colors(0x03, display);
+        for line in layout::wrap(page.text, TEXT_WIDTH) {
+            if y >= VIEW_TOP && y + LINE_HEIGHT <= VIEW_BOTTOM {
+                layout::draw(line, 6, y, Align::Left);
+            }
+            y += LINE_HEIGHT;
+        }
+        unsafe { *DRAW_COLORS = 0x03; }
+        hline(0, VIEW_BOTTOM, SCREEN_SIZE);
+        ui::set_text_colors(0x02, display);
+        let t = b"\x84\x85: page \x86\x87: scroll";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 142);
+        }
+        let t = b"\x81: back";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+}
+
+/// What the player picked from the pause menu.
+pub enum PauseChoice {
+    Resume,
+    Leave,
+}
+
+const RESUME: usize = 0;
+const RULES: usize = 1;
+const LEAVE: usize = 2;
+
+/// The menu over a paused game, with the way into its rules.
+pub struct PauseMenu {
+    menu: Menu,
+    pages: &'static [Page],
+    rules: Option<RulesScreen>,
+}
+
+impl PauseMenu {
+    pub fn new(pages: &'static [Page]) -> Self {
+        Self {
+            menu: Menu::new(3, (40, 60), 12, 80, Style::MENU),
+            pages,
+            rules: None,
+        }
+    }
+
+    /// `can_leave` is false while chips are on the table, which greys out
+    /// leaving so a bet is never walked away from. Z resumes.
+    pub fn update(&mut self, inputs: &Inputs, can_leave: bool) -> Option<PauseChoice> {
+        if let Some(rules) = &mut self.rules {
+            if rules.update(inputs) {
+                self.rules = None;
+            }
+            return None
+        }
+        if inputs.tap_z {
+            sfx::cancel();
+            return Some(PauseChoice::Resume)
+        }
+        self.menu.navigate(inputs);
+        let picked = match self.menu.clicked(inputs) {
+            Some(row) => Some(row),
+            None if inputs.tap_x => Some(self.menu.focus),
+            None => None,
+        };
+        match picked {
+            Some(RESUME) => {
+                sfx::confirm();
+                Some(PauseChoice::Resume)
+            }
+            Some(RULES) if !self.pages.is_empty() => {
+                sfx::confirm();
+                self.rules = Some(RulesScreen::new(self.pages));
+                None
+            }
+            Some(LEAVE) if can_leave => {
+                sfx::confirm();
+                Some(PauseChoice::Leave)
+            }
+            Some(_) => {
+                sfx::error();
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn draw(&self, can_leave: bool, mouse: (i32, i32), display: Display) {
+        if let Some(rules) = &self.rules {
+            rules.draw(display);
+            return
+        }
+        unsafe { *DRAW_COLORS = 0x31; }
+        rect(30, 40, 100, 62);
+        ui::set_text_colors(0x03, display);
+        layout::draw("Paused", 80, 46, Align::Center);
+        self.menu.draw_row(RESUME, "Resume", false, mouse, display);
+        self.menu.draw_row(RULES, "Rules", self.pages.is_empty(), mouse, display);
+        self.menu.draw_row(LEAVE, "Leave table", !can_leave, mouse, display);
+    }
+}
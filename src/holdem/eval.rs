@@ -0,0 +1,161 @@
+//! Ranks the best five-card poker hand out of any number of cards.
+
+use crate::{cards::{Card, CardValue}, fixed::FixedVec};
+
+const NAMES: [&str; 9] = [
+    "High card",
+    "Pair",
+    "Two pair",
+    "Three of a kind",
+    "Straight",
+    "Flush",
+    "Full house",
+    "Four of a kind",
+    "Straight flush",
+];
+
+/// Poker rank of a card, aces high.
+fn rank(card: &Card) -> u32 {
+    match card.value {
+        CardValue::Ace => 14,
+        value => value as u32,
+    }
+}
+
+/// Highest card of a straight within the set bits of `ranks`, counting the
+/// ace as low too.
+fn straight_high(ranks: u32) -> Option<u32> {
+    let ranks = if ranks & 1 << 14 != 0 { ranks | 1 << 1 } else { ranks };
+    (5..=14).rev().find(|high| (high - 4..=*high).all(|rank| ranks & 1 << rank != 0))
+}
+
+/// Packs a category and up to five tie-breaking ranks into one comparable
+/// number.
+fn pack(category: u32, kickers: &[u32]) -> u32 {
+    let mut score = category;
+    for index in 0..5 {
+        score = score << 4 | kickers.get(index).copied().unwrap_or(0);
+    }
+    score
+}
+
+/// The hand's strength: a higher score beats a lower one, equal scores tie.
+pub fn score(cards: &[Card]) -> u32 {
+    let mut counts = [0u8; 15];
+    let mut suit_ranks = [0u32; 4];
+    for card in cards {
+        counts[rank(card) as usize] += 1;
+        suit_ranks[card.suit as usize] |= 1 << rank(card);
+    }
+    let ranks = (2..=14).fold(0, |ranks, rank| if counts[rank] > 0 { ranks | 1 << rank } else { ranks });
+    let flush = suit_ranks.iter().copied().find(|ranks| ranks.count_ones() >= 5);
+
+    if let Some(high) = flush.and_then(straight_high) {
+        return pack(8, &[high])
+    }
+    // (count, rank) from the biggest group down, higher ranks first
+    let mut groups: FixedVec<(u8, u32), 13> = (2..=14u32).rev().filter(|rank| counts[*rank as usize] > 0).map(|rank| (counts[rank as usize], rank)).collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    // `lead` followed by the `n` highest ranks not in it
+    let kickers = |lead: &[u32], n: usize| -> FixedVec<u32, 5> {
+        let mut kickers: FixedVec<u32, 5> = lead.iter().copied().collect();
+        kickers.extend((2..=14).rev().filter(|rank| ranks & 1 << rank != 0 && !lead.contains(rank)).take(n));
+        kickers
+    };
+    match &groups[..] {
+        [(4, quad), ..] => return pack(7, &kickers(&[*quad], 1)),
+        [(3, trips), (pair, pair_rank), ..] if *pair >= 2 => return pack(6, &[*trips, *pair_rank]),
+        _ => {}
+    }
+    if let Some(flush) = flush {
+        let top: FixedVec<u32, 5> = (2..=14).rev().filter(|rank| flush & 1 << rank != 0).take(5).collect();
+        return pack(5, &top)
+    }
+    if let Some(high) = straight_high(ranks) {
+        return pack(4, &[high])
+    }
+    match &groups[..] {
+        [(3, trips), ..] => pack(3, &kickers(&[*trips], 2)),
+        [(2, high), (2, low), ..] => pack(2, &kickers(&[*high, *low], 1)),
+        [(2, pair), ..] => pack(1, &kickers(&[*pair], 3)),
+        _ => pack(0, &kickers(&[], 5)),
+    }
+}
+
+/// Whether a score is an ace-high straight flush.
+pub fn is_royal(score: u32) -> bool {
+    score == pack(8, &[14])
+}
+
+pub fn name(score: u32) -> &'static str {
+    NAMES[(score >> 20) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::CardSuit;
+
+    /// Cards written like "As Td 5c", rank then suit.
+    fn hand(text: &str) -> FixedVec<Card, 7> {
+        text.split(' ')
+            .map(|card| {
+                let bytes = card.as_bytes();
+                let rank = b"A23456789TJQK".iter().position(|rank| *rank == bytes[0]).unwrap();
+                let suit = b"cdhs".iter().position(|suit| *suit == bytes[1]).unwrap();
+                Card { value: CardValue::values()[rank], suit: CardSuit::suits()[suit] }
+            })
+            .collect()
+    }
+
+    fn named(text: &str) -> &'static str {
+        name(score(&hand(text)))
+    }
+
+    #[test]
+    fn aces_play_low_in_the_wheel() {
+        assert_eq!(named("Ah 2c 3d 4s 5h 9c Kd"), "Straight");
+        // the wheel is the lowest straight
+        assert!(score(&hand("2h 3c 4d 5s 6h 9c Kd")) > score(&hand("Ah 2c 3d 4s 5h 9c Kd")));
+        assert_eq!(named("As 2s 3s 4s 5s 9c Kd"), "Straight flush");
+        assert!(!is_royal(score(&hand("As 2s 3s 4s 5s 9c Kd"))));
+        assert!(is_royal(score(&hand("As Ks Qs Js Ts 9c Kd"))));
+    }
+
+    #[test]
+    fn a_straight_and_a_flush_apart_are_only_a_flush() {
+        // the spades and the straight share cards but don't make a run
+        assert_eq!(named("5s 6s 7h 8s 9d Ks 2s"), "Flush");
+        assert_eq!(named("5s 6s 7s 8s 9s Ts 2h"), "Straight flush");
+        // six to the straight flush plays the higher one
+        assert!(score(&hand("5s 6s 7s 8s 9s Ts 2h")) > score(&hand("5s 6s 7s 8s 9s 2d 2h")));
+    }
+
+    #[test]
+    fn two_trips_make_a_full_house() {
+        assert_eq!(named("9c 9d 9h 4s 4c 4d Ah"), "Full house");
+        // nines full of fours, whatever the ace
+        assert_eq!(score(&hand("9c 9d 9h 4s 4c 4d Ah")), score(&hand("9c 9d 9h 4s 4c 2d 3h")));
+        assert!(score(&hand("9c 9d 9h 4s 4c 4d Ah")) < score(&hand("9c 9d 9h 5s 5c 2d 3h")));
+    }
+
+    #[test]
+    fn kickers_break_ties_in_order() {
+        // two pair plays the best kicker, even from a third pair
+        assert_eq!(named("Kc Kd 7h 7s 3c 3d 2h"), "Two pair");
+        assert!(score(&hand("Kc Kd 7h 7s 3c 3d 2h")) < score(&hand("Kc Kd 7h 7s 4c 3d 2h")));
+        assert!(score(&hand("Kc Kd 7h 7s 4c 3d 2h")) < score(&hand("Kc Kd 8h 8s 2c 3d 4h")));
+        // a pair compares its kickers highest first
+        assert!(score(&hand("Jc Jd Ah 9s 4c 3d 2h")) > score(&hand("Jc Jd Kh Qs Tc 3d 2h")));
+        assert!(score(&hand("Jc Jd Ah 9s 5c 3d 2h")) > score(&hand("Jc Jd Ah 9s 4c 3d 2h")));
+    }
+
+    #[test]
+    fn the_same_best_five_split_the_pot() {
+        // both play the board's broadway straight
+        let board = "Ts Jh Qd Kc Ac";
+        assert_eq!(score(&hand(&(board.to_owned() + " 2h 3h"))), score(&hand(&(board.to_owned() + " 9d 4s"))));
+        // a kicker past the fifth card doesn't count
+        assert_eq!(score(&hand("Ac Ad Kh Qs 9c 3d 2h")), score(&hand("Ah As Kd Qc 9h 4c 2d")));
+    }
+}
@@ -0,0 +1,65 @@
+//! Rules pages for the pause menu.
+
+use crate::{
+    cards::{Card, CardSuit, CardValue},
+    help::{Diagram, Page},
+    ui::Display,
+};
+
+/// A board of five cards, like the one dealt face up between the players.
+fn draw_board(cards: [(CardValue, CardSuit); 5], y: i32, display: Display) {
+    for (index, (value, suit)) in cards.into_iter().enumerate() {
+        Card { value, suit }.draw_large(22 + 24 * index as i32, y, true, display);
+    }
+}
+
+fn draw_shared(y: i32, display: Display) {
+    draw_board(
+        [
+            (CardValue::Two, CardSuit::Club),
+            (CardValue::Nine, CardSuit::Heart),
+            (CardValue::King, CardSuit::Spade),
+            (CardValue::Five, CardSuit::Diamond),
+            (CardValue::Nine, CardSuit::Club),
+        ],
+        y,
+        display,
+    );
+}
+
+fn draw_straight_flush(y: i32, display: Display) {
+    draw_board(
+        [
+            (CardValue::Ten, CardSuit::Heart),
+            (CardValue::Jack, CardSuit::Heart),
+            (CardValue::Queen, CardSuit::Heart),
+            (CardValue::King, CardSuit::Heart),
+            (CardValue::Ace, CardSuit::Heart),
+        ],
+        y,
+        display,
+    );
+}
+
+pub const RULES: [Page; 4] = [
+    Page {
+        title: "Hold'em",
+        diagram: Some(Diagram { height: 21, draw: draw_shared }),
+        text: b"Each player gets two hole cards. Five more are dealt face up on the board for both to share. The best five cards out of your seven win the pot.",
+    },
+    Page {
+        title: "Hands",
+        diagram: Some(Diagram { height: 21, draw: draw_straight_flush }),
+        text: b"From best to worst: straight flush, four of a kind, full house, flush, straight, three of a kind, two pair, pair, high card.",
+    },
+    Page {
+        title: "Betting",
+        diagram: None,
+        text: b"Blinds are 5 and 10. Bets and raises are 10 before the turn and 20 after, with at most three a street. Check or call, bet or raise, or fold to give up the pot.",
+    },
+    Page {
+        title: "Passing",
+        diagram: None,
+        text: b"Both players share one console. Before your cards are shown the other player looks away, and the match ends when one of you has every chip.",
+    },
+];
@@ -0,0 +1,566 @@
+mod eval;
+mod help;
+
+use crate::{
+    cards::{Card, CardSuit, CardValue},
+    chips,
+    dealer::{self, Expression},
+    effects::ScreenEffect,
+    fixed::{FixedString, FixedVec, LINE_LEN},
+    model::{local_player, GameError, GameEvent, Inputs, Model},
+    money,
+    music::{self, Track},
+    particles::{Burst, Effect},
+    rng::Rng,
+    sfx,
+    shuffle::ShuffleStrategy,
+    ui::{Menu, Style},
+    wasm4::*,
+    PlayerState,
+};
+
+pub use help::RULES;
+
+const STARTING_STACK: u32 = 500;
+const SMALL_BLIND: u32 = 5;
+const BIG_BLIND: u32 = 10;
+/// Bets and raises per street before betting is capped.
+const MAX_RAISES: u8 = 3;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+impl Street {
+    /// Fixed limit: small bets before the turn, big bets after.
+    fn bet_size(&self) -> u32 {
+        match self {
+            Self::Preflop | Self::Flop => BIG_BLIND,
+            Self::Turn | Self::River => 2 * BIG_BLIND,
+        }
+    }
+}
+
+enum Phase {
+    /// Waiting for the other player to look away before showing `player`
+    /// their hole cards.
+    Pass(usize),
+    Act(usize),
+    /// Both hands face up. `None` is a split pot.
+    Showdown(Option<usize>),
+    /// Someone folded and `usize` takes the pot.
+    Folded(usize),
+    MatchOver(usize),
+}
+
+#[derive(Copy, Clone)]
+enum Action {
+    CheckCall,
+    BetRaise,
+    Fold,
+}
+
+const ACTIONS: [Action; 3] = [Action::CheckCall, Action::BetRaise, Action::Fold];
+
+/// Heads-up limit hold'em for players one and two on the same console. Hole
+/// cards are only shown after a hand-off screen so the other player can look
+/// away. The player's real bank is left untouched.
+pub struct HoldEm {
+    rng: Rng,
+    deck: FixedVec<Card, 52>,
+    hole_cards: [FixedVec<Card, 2>; 2],
+    board: FixedVec<Card, 5>,
+    stacks: [u32; 2],
+    /// Chips each player has put in on the current street.
+    bets: [u32; 2],
+    pot: u32,
+    street: Street,
+    raises: u8,
+    acted: [bool; 2],
+    /// The button posts the small blind and acts first before the flop.
+    button: usize,
+    phase: Phase,
+    actions: Menu,
+    /// Mouse position from the last update, for hover highlighting.
+    mouse: (i32, i32),
+    player_state: PlayerState,
+    /// Chips flying for the match winner, waiting to be picked up by the menu.
+    burst: Option<Burst>,
+    /// A flash for the match winner, waiting to be picked up by the menu.
+    screen_effect: Option<ScreenEffect>,
+    /// A fanfare for a royal flush or the match winner, waiting to be
+    /// picked up by the menu.
+    sting: Option<&'static Track>,
+    /// Why the match was just abandoned, waiting to be picked up by the menu.
+    error: Option<GameError>,
+}
+
+impl HoldEm {
+    pub fn new(random_seed: u64) -> Self {
+        Self {
+            rng: Rng::with_seed(random_seed),
+            deck: FixedVec::new(),
+            hole_cards: [FixedVec::new(); 2],
+            board: FixedVec::new(),
+            stacks: [STARTING_STACK; 2],
+            bets: [0; 2],
+            pot: 0,
+            street: Street::Preflop,
+            raises: 0,
+            acted: [false; 2],
+            // the first hand moves the button to player one
+            button: 1,
+            phase: Phase::Pass(0),
+            actions: Menu::new(ACTIONS.len(), (10, 124), 9, 100, Style::MENU),
+            mouse: (0, 0),
+            player_state: PlayerState::default(),
+            burst: None,
+            screen_effect: None,
+            sting: None,
+            error: None,
+        }
+    }
+
+    fn start_hand(&mut self) -> Result<(), GameError> {
+        let settings = &self.player_state.settings;
+        let shuffle = settings.shuffle(settings.holdem_shuffle);
+        if shuffle.strategy == ShuffleStrategy::Riffle && !self.hole_cards[0].is_empty() {
+            // the hands and board are swept onto the stub and riffled back
+            // in, so a sloppy dealer leaves some of the last hand together
+            for cards in self.hole_cards {
+                self.deck.extend(cards.iter().copied());
+            }
+            self.deck.extend(self.board.iter().copied());
+            shuffle.apply(&mut self.rng, &mut self.deck);
+        } else {
+            self.deck = CardSuit::suits()
+                .into_iter()
+                .flat_map(|suit| CardValue::values().into_iter().map(move |value| Card { value, suit }))
+                .collect();
+            shuffle.apply_fresh(&mut self.rng, &mut self.deck);
+        }
+        self.button = 1 - self.button;
+        for player in [self.button, 1 - self.button] {
+            self.hole_cards[player] = [self.deal()?, self.deal()?].into_iter().collect();
+        }
+        sfx::card_deal();
+        self.board.clear();
+        self.pot = 0;
+        self.bets = [0; 2];
+        self.street = Street::Preflop;
+        self.raises = 1;
+        self.acted = [false; 2];
+        let small_blind = self.button;
+        self.put_in(small_blind, SMALL_BLIND);
+        self.put_in(1 - small_blind, BIG_BLIND);
+        self.phase = Phase::Pass(small_blind);
+        Ok(())
+    }
+
+    fn deal(&mut self) -> Result<Card, GameError> {
+        self.deck.pop().ok_or(GameError::OutOfCards)
+    }
+
+    /// Deals player one a royal flush in spades, two cards in the hand
+    /// under way and the rest on the flop.
+    #[cfg(feature = "cheats")]
+    pub fn force_jackpot(&mut self) -> bool {
+        let royal = [CardValue::Ace, CardValue::King, CardValue::Queen, CardValue::Jack, CardValue::Ten]
+            .map(|value| Card { value, suit: CardSuit::Spade });
+        let in_royal = |card: &&Card| card.suit == CardSuit::Spade && royal.iter().any(|royal| royal.value == card.value);
+        let mut rest: FixedVec<Card, 52> =
+            self.deck.iter().chain(self.hole_cards.iter().flat_map(|cards| cards.iter())).filter(|card| !in_royal(card)).copied().collect();
+        let (Some(first), Some(second)) = (rest.pop(), rest.pop()) else {
+            return false
+        };
+        self.hole_cards = [royal[..2].iter().copied().collect(), [first, second].into_iter().collect()];
+        rest.extend(royal[2..].iter().copied());
+        self.deck = rest;
+        true
+    }
+
+    /// Moves up to `amount` from a stack into the pot.
+    fn put_in(&mut self, player: usize, amount: u32) {
+        let amount = amount.min(self.stacks[player]);
+        if amount > 0 {
+            sfx::chips(amount);
+        }
+        self.stacks[player] -= amount;
+        self.bets[player] += amount;
+        self.pot += amount;
+    }
+
+    fn owed(&self, player: usize) -> u32 {
+        self.bets[1 - player].saturating_sub(self.bets[player])
+    }
+
+    fn can_raise(&self, player: usize) -> bool {
+        self.raises < MAX_RAISES && self.stacks[player] > self.owed(player) && self.stacks[1 - player] > 0
+    }
+
+    fn act(&mut self, player: usize, action: Action) -> Result<(), GameError> {
+        match action {
+            Action::Fold => {
+                sfx::win(self.pot);
+                sfx::payout(self.pot);
+                self.stacks[1 - player] += self.pot;
+                log!(Info, "holdem", "player {} folds a pot of {}", player, self.pot);
+                self.phase = Phase::Folded(1 - player);
+                return Ok(())
+            }
+            Action::CheckCall => {
+                self.put_in(player, self.owed(player));
+            }
+            Action::BetRaise => {
+                self.put_in(player, self.owed(player) + self.street.bet_size());
+                self.raises += 1;
+                self.acted = [false; 2];
+            }
+        }
+        self.acted[player] = true;
+        if self.acted[1 - player] && self.bets[0] == self.bets[1] || self.stacks[player] == 0 && self.owed(1 - player) == 0 {
+            self.next_street()?;
+        } else {
+            self.phase = Phase::Pass(1 - player);
+        }
+        Ok(())
+    }
+
+    fn next_street(&mut self) -> Result<(), GameError> {
+        // a short all-in call leaves part of the last bet uncalled
+        for player in 0..2 {
+            let uncalled = self.owed(1 - player);
+            self.stacks[player] += uncalled;
+            self.pot -= uncalled;
+        }
+        self.bets = [0; 2];
+        self.raises = 0;
+        self.acted = [false; 2];
+        let all_in = self.stacks.contains(&0);
+        self.street = match self.street {
+            Street::Preflop => {
+                sfx::card_deal();
+                for _ in 0..3 {
+                    let card = self.deal()?;
+                    self.board.push(card);
+                }
+                Street::Flop
+            }
+            Street::Flop => {
+                sfx::card_deal();
+                let card = self.deal()?;
+                self.board.push(card);
+                Street::Turn
+            }
+            Street::Turn => {
+                sfx::card_deal();
+                let card = self.deal()?;
+                self.board.push(card);
+                Street::River
+            }
+            Street::River => {
+                self.showdown();
+                return Ok(())
+            }
+        };
+        if all_in {
+            // nobody can bet, so run the board out
+            self.next_street()?;
+        } else {
+            // the big blind acts first after the flop
+            self.phase = Phase::Pass(1 - self.button);
+        }
+        Ok(())
+    }
+
+    fn showdown(&mut self) {
+        sfx::card_flip();
+        let scores = [0, 1].map(|player| eval::score(&self.cards(player)));
+        let winner = if scores[0] > scores[1] {
+            Some(0)
+        } else if scores[1] > scores[0] {
+            Some(1)
+        } else {
+            None
+        };
+        if winner.is_some_and(|winner| eval::is_royal(scores[winner])) {
+            self.sting = Some(&music::JACKPOT);
+        }
+        match winner {
+            Some(winner) => self.stacks[winner] += self.pot,
+            None => {
+                self.stacks[0] += self.pot / 2;
+                self.stacks[1] += self.pot - self.pot / 2;
+            }
+        }
+        if winner.is_some() {
+            sfx::win(self.pot);
+        }
+        sfx::payout(self.pot);
+        log!(Info, "holdem", "showdown for {}, winner {:?}", self.pot, winner);
+        self.phase = Phase::Showdown(winner);
+    }
+
+    /// A player's hole cards with the board.
+    fn cards(&self, player: usize) -> FixedVec<Card, 7> {
+        self.hole_cards[player].iter().chain(self.board.iter()).copied().collect()
+    }
+
+    fn score_name(&self, player: usize) -> &'static str {
+        eval::name(eval::score(&self.cards(player)))
+    }
+
+    fn next_hand(&mut self) -> Result<(), GameError> {
+        match self.stacks.iter().position(|stack| *stack == 0) {
+            Some(busted) => {
+                self.phase = Phase::MatchOver(1 - busted);
+                // the winner takes every chip on the table
+                self.burst = Some(Burst { effect: Effect::Chips, x: 80, y: 70 });
+                self.sting = Some(&music::JACKPOT);
+                self.screen_effect = Some(ScreenEffect::Flash);
+            }
+            None => self.start_hand()?,
+        }
+        Ok(())
+    }
+
+    /// Leaves for the menu with how each player's stack did, so the session
+    /// standings count the match. Chips in a pot left unfinished count for
+    /// neither player.
+    fn leave(&self) -> PlayerState {
+        let results = [0, 1, 2, 3].map(|player| self.stacks.get(player).map_or(0, |stack| *stack as i64 - STARTING_STACK as i64));
+        PlayerState { results: Some(results), ..self.player_state }
+    }
+
+    /// Leaves for the menu over an error. The real bank was never touched.
+    fn abandon(&mut self, error: GameError) -> PlayerState {
+        self.error = Some(error);
+        self.player_state
+    }
+
+    fn draw_table(&self, face_up: [bool; 2]) {
+        let display = self.player_state.settings.display();
+        self.player_state.settings.felt.draw(4, 40, 152, 38);
+        let expression = match self.phase {
+            Phase::Pass(_) | Phase::Act(_) => Expression::Dealing,
+            Phase::Showdown(None) => Expression::Neutral,
+            Phase::Showdown(Some(_)) | Phase::Folded(_) | Phase::MatchOver(_) => Expression::Congratulating,
+        };
+        dealer::draw(expression, 136, 2);
+        unsafe { *DRAW_COLORS = 0x02; }
+        text(fixed_format!("Pot: {}", money::chips(self.pot)), 10, 4);
+        for (index, card) in self.board.iter().enumerate() {
+            card.draw_large(22 + 24 * index as i32, 48, true, display);
+        }
+        for (player, face_up) in face_up.into_iter().enumerate() {
+            let y = if player == 0 { 100 } else { 20 };
+            unsafe { *DRAW_COLORS = 0x02; }
+            let you = if local_player() == Some(player) { " (you)" } else { "" };
+            self.player_state.looks[player].draw(4, y + 4);
+            text(fixed_format!("P{} {}{}", player + 1, money::compact(self.stacks[player]), you), 14, y + 4);
+            if self.button == player {
+                text("D", 150, y + 4);
+            }
+            for (index, card) in self.hole_cards[player].iter().enumerate() {
+                card.draw_large(104 + 18 * index as i32, y - 2, face_up, display);
+            }
+            if self.bets[player] > 0 {
+                unsafe { *DRAW_COLORS = 0x02; }
+                let label = fixed_format!("bet {}", money::chips(self.bets[player]));
+                text(label, 4, y + 14);
+                chips::draw_stacks(self.bets[player], 8 + 8 * label.len() as i32, y + 22);
+            }
+        }
+    }
+
+    fn action_label(&self, player: usize, action: Action) -> FixedString<LINE_LEN> {
+        let owed = self.owed(player);
+        match action {
+            Action::CheckCall if owed == 0 => fixed_format!("Check"),
+            Action::CheckCall => fixed_format!("Call {}", money::chips(owed.min(self.stacks[player]))),
+            Action::BetRaise if self.bets[1 - player] == 0 => fixed_format!("Bet {}", money::chips(self.street.bet_size())),
+            Action::BetRaise => fixed_format!("Raise {}", money::chips(self.street.bet_size())),
+            Action::Fold => fixed_format!("Fold"),
+        }
+    }
+
+    /// A frame of play, leaving with the player's state when it's over.
+    fn step(&mut self, inputs: [Inputs; 4]) -> Option<PlayerState> {
+        if self.error.is_some() {
+            return Some(self.player_state)
+        }
+        match self.phase {
+            Phase::Pass(player) => {
+                if inputs[player].tap_x {
+                    // the player's own hole cards turn face up
+                    sfx::card_flip();
+                    self.actions.focus = 0;
+                    self.phase = Phase::Act(player);
+                }
+                if inputs[0].tap_z {
+                    return Some(self.leave())
+                }
+            }
+            Phase::Act(player) => {
+                let player_inputs = inputs[player];
+                self.mouse = player_inputs.mouse();
+                self.actions.navigate(&player_inputs);
+                let clicked = self.actions.clicked(&player_inputs);
+                if player_inputs.tap_x || clicked.is_some() {
+                    match ACTIONS[self.actions.focus] {
+                        Action::BetRaise if !self.can_raise(player) => sfx::error(),
+                        Action::Fold if self.owed(player) == 0 => sfx::error(),
+                        action => {
+                            sfx::confirm();
+                            if let Err(error) = self.act(player, action) {
+                                return Some(self.abandon(error))
+                            }
+                        }
+                    }
+                }
+            }
+            Phase::Showdown(_) | Phase::Folded(_) => {
+                if inputs[0].tap_x || inputs[1].tap_x {
+                    if let Err(error) = self.next_hand() {
+                        return Some(self.abandon(error))
+                    }
+                }
+                if inputs[0].tap_z {
+                    return Some(self.leave())
+                }
+            }
+            Phase::MatchOver(_) => {
+                if inputs[0].tap_x || inputs[0].tap_z {
+                    return Some(self.leave())
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Model<PlayerState> for HoldEm {
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<PlayerState> {
+        let before = self.state_name();
+        let result = self.step(inputs);
+        let after = self.state_name();
+        if after != before {
+            log!(Debug, "holdem", "{} -> {}, pot {}, stacks {:?}", before, after, self.pot, self.stacks);
+        }
+        match result {
+            Some(state) => GameEvent::Exit { state },
+            None => self.sting.take().map_or(GameEvent::Continue, GameEvent::PlaySfx),
+        }
+    }
+
+    fn draw(&self) {
+        match self.phase {
+            Phase::Pass(player) => {
+                self.draw_table([false; 2]);
+                unsafe { *DRAW_COLORS = 0x23; }
+                rect(10, 70, 140, 26);
+                unsafe { *DRAW_COLORS = 0x01; }
+                text(fixed_format!("Player {}'s turn", player + 1), 16, 74);
+                text(fixed_format!("P{} look away!", 2 - player), 16, 84);
+                unsafe { *DRAW_COLORS = 0x02; }
+                let t: &[u8] = if player == 0 { b"P1 \x80: show cards" } else { b"P2 \x80: show cards" };
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 142);
+                }
+                let t = b"\x81: quit";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            Phase::Act(player) => {
+                let mut face_up = [false; 2];
+                face_up[player] = true;
+                self.draw_table(face_up);
+                for (index, action) in ACTIONS.iter().enumerate() {
+                    let disabled = match action {
+                        Action::BetRaise => !self.can_raise(player),
+                        Action::Fold => self.owed(player) == 0,
+                        Action::CheckCall => false,
+                    };
+                    self.actions.draw_row(index, self.action_label(player, *action), disabled, self.mouse, self.player_state.settings.display());
+                }
+            }
+            Phase::Showdown(winner) => {
+                self.draw_table([true; 2]);
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(self.score_name(0), 4, 124);
+                text(self.score_name(1), 4, 36);
+                match winner {
+                    Some(winner) => text(fixed_format!("P{} wins!", winner + 1), 90, 124),
+                    None => text("Split pot", 90, 124),
+                }
+                let t = b"\x80: next \x81: quit";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            Phase::Folded(winner) => {
+                self.draw_table([false; 2]);
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(fixed_format!("P{} folds, P{} wins", 2 - winner, winner + 1), 4, 124);
+                let t = b"\x80: next \x81: quit";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            Phase::MatchOver(winner) => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(fixed_format!("Player {} wins", winner + 1), 24, 60);
+                text("the match!", 40, 70);
+                let t = b"\x80: done";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+        }
+    }
+
+    fn share_state(&mut self, state: PlayerState) {
+        self.player_state = state;
+        // the first hand waits for settings, which say how to shuffle
+        if self.hole_cards[0].is_empty() {
+            // the first update hands this to the menu
+            self.error = self.start_hand().err();
+        }
+    }
+
+    fn needs_redraw(&self) -> bool {
+        // every change at this table comes from a key press
+        false
+    }
+
+    fn take_burst(&mut self) -> Option<Burst> {
+        self.burst.take()
+    }
+
+    fn take_screen_effect(&mut self) -> Option<ScreenEffect> {
+        self.screen_effect.take()
+    }
+
+    fn take_error(&mut self) -> Option<GameError> {
+        self.error.take()
+    }
+
+    fn state_name(&self) -> &'static str {
+        match self.phase {
+            Phase::Pass(_) => "Pass",
+            Phase::Act(_) => "Act",
+            Phase::Showdown(_) => "Showdown",
+            Phase::Folded(_) => "Folded",
+            Phase::MatchOver(_) => "MatchOver",
+        }
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        Some(self.deck.len())
+    }
+}
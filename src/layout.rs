@@ -0,0 +1,63 @@
+use alloc::vec::Vec;
+use crate::wasm4::*;
+
+/// Every glyph in the built-in font, button glyphs like `\x80` included, is
+/// one byte wide on screen.
+pub const GLYPH_WIDTH: i32 = 8;
+pub const LINE_HEIGHT: i32 = 9;
+
+#[derive(Copy, Clone)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+pub fn width(text: &[u8]) -> i32 {
+    text.len() as i32 * GLYPH_WIDTH
+}
+
+/// Splits text into lines no wider than `max_width`, breaking at spaces
+/// where possible and mid-word only when a word alone is too long.
+pub fn wrap(text: &[u8], max_width: i32) -> Vec<&[u8]> {
+    let max_glyphs = (max_width / GLYPH_WIDTH).max(1) as usize;
+    let mut lines = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_glyphs {
+        let split = rest[..=max_glyphs].iter().rposition(|byte| *byte == b' ').filter(|split| *split > 0);
+        match split {
+            Some(split) => {
+                lines.push(&rest[..split]);
+                rest = &rest[split + 1..];
+            }
+            None => {
+                lines.push(&rest[..max_glyphs]);
+                rest = &rest[max_glyphs..];
+            }
+        }
+    }
+    lines.push(rest);
+    lines
+}
+
+/// Draws one line where `x` is its left edge, middle or right edge.
+pub fn draw<T: AsRef<[u8]>>(text: T, x: i32, y: i32, align: Align) {
+    let text = text.as_ref();
+    let left = match align {
+        Align::Left => x,
+        Align::Center => x - width(text) / 2,
+        Align::Right => x - width(text),
+    };
+    unsafe {
+        extern_text(text.as_ptr(), text.len(), left, y);
+    }
+}
+
+/// Wraps and draws text, returning the height it took up.
+pub fn draw_wrapped(text: &[u8], x: i32, y: i32, max_width: i32, align: Align) -> i32 {
+    let lines = wrap(text, max_width);
+    for (index, line) in lines.iter().enumerate() {
+        draw(line, x, y + LINE_HEIGHT * index as i32, align);
+    }
+    LINE_HEIGHT * lines.len() as i32
+}
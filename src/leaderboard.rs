@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+use crate::{model::local_player, money, wasm4::*, PlayerState};
+
+/// Session standings for the couch: every seat's net chips across all games
+/// played since boot, plus how many games each seat came out on top of.
+pub struct Leaderboard {
+    net: [i64; 4],
+    wins: [u32; 4],
+    games: u32,
+}
+
+impl Leaderboard {
+    pub const fn new() -> Self {
+        Self { net: [0; 4], wins: [0; 4], games: 0 }
+    }
+
+    /// Adds a finished game, given the state it was launched with. A match
+    /// played for stacks of its own counts its `results`, anything else what
+    /// the banks moved. The seat that won the most chips takes the game,
+    /// unless nobody came out ahead.
+    pub fn record(&mut self, launch_state: &PlayerState, state: &PlayerState) {
+        let mut best: Option<(usize, i64)> = None;
+        for seat in 0..4 {
+            if !launch_state.is_active(seat) {
+                continue
+            }
+            let net = match state.results {
+                Some(results) => results[seat],
+                None => state.banks[seat] as i64 - launch_state.banks[seat] as i64,
+            };
+            self.net[seat] += net;
+            if net > 0 && best.is_none_or(|(_, best_net)| net > best_net) {
+                best = Some((seat, net));
+            }
+        }
+        if let Some((seat, _)) = best {
+            self.wins[seat] += 1;
+        }
+        self.games += 1;
+    }
+
+    /// Seated players, best first by net chips then by games won.
+    fn ranking(&self, player_state: &PlayerState) -> Vec<usize> {
+        let mut seats: Vec<usize> = (0..4).filter(|seat| player_state.is_active(*seat)).collect();
+        seats.sort_by(|a, b| self.net[*b].cmp(&self.net[*a]).then(self.wins[*b].cmp(&self.wins[*a])));
+        seats
+    }
+
+    pub fn draw(&self, player_state: &PlayerState) {
+        unsafe { *DRAW_COLORS = 0x23; }
+        rect(10, 30, 140, 90);
+        unsafe { *DRAW_COLORS = 0x01; }
+        text("Standings", 44, 36);
+        text(fixed_format!("Games: {}", self.games), 16, 48);
+        for (place, seat) in self.ranking(player_state).into_iter().enumerate() {
+            let y = 62 + 12 * place as i32;
+            let marker = if local_player() == Some(seat) { ">" } else { " " };
+            let net = self.net[seat];
+            let sign = if net < 0 { "-" } else { "+" };
+            let amount = money::compact(net.unsigned_abs().min(u32::MAX as u64) as u32);
+            text(fixed_format!("{}{} P{} {}{}", marker, place + 1, seat + 1, sign, amount), 12, y);
+            text(fixed_format!("W{}", self.wins[seat]), 112, y);
+            player_state.looks[seat].draw(140, y);
+        }
+        let t = b"\x80: continue";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 16, 110);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "versus"))]
+mod tests {
+    use super::*;
+    use crate::{headless, model::{GameEvent, Inputs, Model}, versus::Versus};
+
+    #[test]
+    fn a_versus_match_moves_the_standings() {
+        let _console = headless::session();
+        let launch_state = PlayerState { banks: [250; 4], active_seats: 0b11, ..PlayerState::default() };
+        let mut game = Versus::new(11);
+        game.share_state(launch_state);
+        // X on every gamepad, tapping every other frame so each tap is its
+        // own press
+        let tap_x = Inputs { tap_x: true, ..Inputs::default() };
+        for frame in 0..100_000 {
+            let inputs = if frame % 2 == 0 { tap_x } else { Inputs::default() };
+            if let GameEvent::Exit { state } = game.update([inputs; 4]) {
+                assert_eq!(state.banks, launch_state.banks, "versus never touches the banks");
+                let mut leaderboard = Leaderboard::new();
+                leaderboard.record(&launch_state, &state);
+                assert_eq!(leaderboard.games, 1);
+                assert_ne!(leaderboard.net, [0; 4], "results {:?}", state.results);
+                assert_eq!(leaderboard.net[2..], [0, 0]);
+                return
+            }
+        }
+        panic!("the match never finished, stuck in {}", game.state_name());
+    }
+}
@@ -1,162 +1,1291 @@
-#[cfg(feature = "buddy-alloc")]
-mod alloc;
+// buddy-alloc only takes over on the console; on the host the cart runs
+// headless under std for tests
+#![cfg_attr(all(feature = "buddy-alloc", target_family = "wasm"), no_std)]
+// a cart built without some games still has the helpers only they used
+#![cfg_attr(
+    not(all(feature = "blackjack", feature = "party", feature = "versus", feature = "holdem")),
+    allow(dead_code)
+)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "buddy-alloc", target_family = "wasm"))]
+mod heap;
+#[macro_use]
+mod log;
+#[macro_use]
+mod fixed;
 mod wasm4;
+mod platform;
+#[cfg(not(target_family = "wasm"))]
+mod headless;
+#[cfg(test)]
+mod golden;
+#[cfg(all(feature = "blackjack", not(target_family = "wasm")))]
+pub mod simulate;
+mod achievements;
+mod ambience;
+mod anim;
+mod bet;
+mod bigfont;
+mod capsule;
+#[cfg(feature = "cheats")]
+mod cheats;
+mod cards;
+mod avatars;
+mod chips;
+mod crash;
 mod model;
+mod particles;
+mod passcode;
+mod emotes;
+mod dealer;
+mod debug;
+mod effects;
+mod events;
+mod game;
+mod global;
+mod felt;
+mod fsm;
+mod help;
+mod leaderboard;
+mod lobby;
+mod rng;
+mod rules;
+mod profiles;
+mod save;
+mod settings;
+mod sfx;
+mod shuffle;
+mod slots;
+mod sprites;
+mod stats;
+mod timers;
+mod turns;
+mod tutorial;
+mod layout;
+mod money;
+mod music;
+mod ui;
+use alloc::vec::Vec;
 use wasm4::*;
-use model::{Model, User};
+use model::{local_player, point_in, GameEvent, Gestures, Inputs, Model, User};
+#[cfg(feature = "blackjack")]
 mod blackjack;
+#[cfg(feature = "blackjack")]
 use blackjack::{BlackJack};
+#[cfg(feature = "party")]
+mod party;
+#[cfg(feature = "versus")]
+mod versus;
+#[cfg(feature = "holdem")]
+mod holdem;
+#[cfg(feature = "party")]
+use party::PartyMode;
+#[cfg(feature = "versus")]
+use versus::Versus;
+#[cfg(feature = "holdem")]
+use holdem::HoldEm;
+use achievements::Achievement;
+use events::{Promotion, Toast};
+use debug::DebugOverlay;
+use game::Game;
+use global::Global;
+use help::{Page, PauseChoice, PauseMenu};
+use effects::{ScreenEffects, Transition, Wipe};
+use leaderboard::Leaderboard;
+use ambience::Ambience;
+use music::Music;
+use particles::{Burst, Effect, Particles};
+use avatars::Look;
+use save::{Resume, SaveData, SaveError};
+use settings::{Settings, SettingsScreen};
+use lobby::{Lobby, LobbyResult};
+use slots::SlotScreen;
+use timers::Interval;
+use profiles::{Profile, ProfileScreen, STARTING_BANK};
+use rng::SEED_CODE_LEN;
+use ui::Keyboard;
 
 
-static mut GAMEPAD1_PREV: u8 = 0;
-static mut GAMEPAD2_PREV: u8 = 0;
-static mut GAMEPAD3_PREV: u8 = 0;
-static mut GAMEPAD4_PREV: u8 = 0;
+fn menu_row_y(row: usize) -> i32 {
+    20 + 10 * row as i32
+}
+const SESSION_REMINDER: Interval = Interval::new(timers::secs(30.0 * 60.0));
 
-fn start_frame() {
+/// Without std there's nothing to unwind to, so a panic puts up the crash
+/// screen and abandons the frame.
+#[cfg(all(feature = "buddy-alloc", target_family = "wasm"))]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    crash::report(&info.message(), info.location());
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(target_arch = "wasm32"))]
+    loop {}
+}
 
+/// With std the panic runtime stays in charge, so a hook puts up the same
+/// crash screen before the panic goes on to abort the frame.
+#[cfg(not(all(feature = "buddy-alloc", target_family = "wasm")))]
+fn install_panic_hook() {
+    std::panic::set_hook(alloc::boxed::Box::new(|info| {
+        crash::report(&info.payload_as_str().unwrap_or("panic"), info.location());
+    }));
 }
 
-fn end_frame() {
+#[no_mangle]
+fn start() {
     unsafe {
-        GAMEPAD1_PREV = *GAMEPAD1;
-        GAMEPAD2_PREV = *GAMEPAD2;
-        GAMEPAD3_PREV = *GAMEPAD3;
-        GAMEPAD4_PREV = *GAMEPAD4;
+        *SYSTEM_FLAGS |= SYSTEM_PRESERVE_FRAMEBUFFER;
     }
+    crash::init();
+    #[cfg(not(all(feature = "buddy-alloc", target_family = "wasm")))]
+    install_panic_hook();
+    RUNTIME.init(Runtime::new());
+    boot();
 }
 
-#[no_mangle]
-fn start() {
+/// Brings up the menu from what's on disk, at start and again after a
+/// crash.
+fn boot() {
     unsafe {
-        *PALETTE = [
-            0xffffff,  // white
-            0xc60e0e,  // red
-            0x000000,  // black
-            0xffef00,  // yellow
-        ];
-        GAME.init()
+        *PALETTE = effects::BASE_PALETTE;
     }
+    RUNTIME.with(|runtime| runtime.game.init());
 }
 
+// the developer menu must never reach a release cart
+#[cfg(all(feature = "cheats", not(debug_assertions)))]
+compile_error!("the `cheats` feature is for debug builds only");
+
 #[derive(Copy, Clone)]
 pub struct PlayerState {
-    bank: u32,
+    /// Chips for each gamepad's seat.
+    banks: [u32; 4],
+    /// The seat the running game is betting for.
+    seat: usize,
+    rounds: u32,
+    /// Set by party mode so a game hands control back after one round.
+    single_round: bool,
+    promotion: Option<Promotion>,
+    settings: Settings,
+    session_start_bank: u32,
+    /// Bit per gamepad that has joined in, player one's always set.
+    active_seats: u8,
+    /// Each seat's avatar and color, picked in the lobby.
+    looks: [Look; 4],
+    /// How fast games animate: the settings' speed, or the cheat menu's
+    /// while one's picked.
+    animation_speed: f32,
+    /// Each seat's chips won or lost in a match played for stacks of its
+    /// own, set as party, versus and hold'em leave. Those never touch the
+    /// banks, so the standings count this instead.
+    results: Option<[i64; 4]>,
 }
 
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            banks: [0; 4],
+            seat: 0,
+            rounds: 0,
+            single_round: false,
+            promotion: None,
+            settings: Settings::default(),
+            session_start_bank: 0,
+            active_seats: 1,
+            looks: Look::DEFAULTS,
+            animation_speed: 1.0,
+            results: None,
+        }
+    }
+}
+
+impl PlayerState {
+    fn bank(&self) -> u32 {
+        self.banks[self.seat]
+    }
+
+    fn set_bank(&mut self, bank: u32) {
+        self.banks[self.seat] = bank;
+    }
+    fn is_active(&self, seat: usize) -> bool {
+        self.active_seats & (1 << seat) != 0
+    }
+
+    /// How many gamepads have joined, for games that deal everyone in.
+    fn num_players(&self) -> usize {
+        self.active_seats.count_ones() as usize
+    }
+
+    fn session_net(&self) -> i64 {
+        self.bank() as i64 - self.session_start_bank as i64
+    }
+
+    /// Whether the session's losses have hit the loss limit set in settings.
+    fn loss_limit_reached(&self) -> bool {
+        match self.settings.loss_limit {
+            Some(limit) => self.session_net() <= -(limit as i64),
+            None => false,
+        }
+    }
+}
+
+/// A menu entry: the game's display name, its constructor, which takes a
+/// random seed, the rules pages shown from the pause menu, and whether it
+/// goes through the lobby first.
+pub type GameEntry = (&'static str, fn(u64) -> Game, &'static [Page], bool);
+
+/// Everything on the menu, in order before favorites are moved up. Only the
+/// games whose features are enabled are listed, and saves number games by
+/// their place here, so a trimmed cart's saves don't carry over to the full
+/// one.
+const GAMES: &[GameEntry] = &[
+    #[cfg(feature = "blackjack")]
+    ("Blackjack", |seed| Game::BlackJack(BlackJack::new(seed)), &blackjack::RULES, false),
+    #[cfg(feature = "party")]
+    ("Casino Night", |seed| Game::Party(PartyMode::new(seed)), &party::RULES, true),
+    #[cfg(feature = "versus")]
+    ("Versus", |seed| Game::Versus(Versus::new(seed)), &versus::RULES, true),
+    #[cfg(feature = "holdem")]
+    ("Hold'em", |seed| Game::HoldEm(HoldEm::new(seed)), &holdem::RULES, true),
+    #[cfg(feature = "blackjack")]
+    ("Blackjack 101", |seed| Game::BlackJack(BlackJack::lesson(seed)), &blackjack::RULES, false),
+];
+
 struct MainGame {
     frame_count: u64,
-    games: Option<[(&'static str, fn(u64) -> Box<dyn Model<PlayerState>>); 1]>,
     num_games: usize,
     current_index: usize,
-    current_game: Option<Box<dyn Model<PlayerState>>>,
+    last_index: Option<usize>,
+    playing: Option<usize>,
+    favorites: u32,
+    x_hold_frames: u32,
+    current_game: Option<Game>,
     player_state: PlayerState,
+    launch_state: PlayerState,
+    profiles: Vec<Profile>,
+    /// Index into `profiles` for each gamepad that has picked one.
+    seats: [Option<usize>; 4],
+    profile_screen: Option<ProfileScreen>,
+    slot: usize,
+    slot_screen: Option<SlotScreen>,
+    /// Ready-up screen before a multiplayer game.
+    lobby: Option<Lobby>,
+    /// Shuffle seed entry before sitting at the game at the index.
+    seed_entry: Option<(usize, Keyboard<SEED_CODE_LEN>)>,
+    /// Bit per game index that needs more than one player.
+    multiplayer: u32,
+    play_frames: u32,
+    /// The autosaved round in progress, if any.
+    resume: Option<Resume>,
+    toast: Option<Toast>,
+    particles: Particles,
+    screen_effects: ScreenEffects,
+    /// Wipe over the screen just after a game opens or closes.
+    transition: Option<Transition>,
+    /// Pause menu over the running game, which waits until it's closed.
+    pause: Option<PauseMenu>,
+    music: Music,
+    ambience: Ambience,
+    reminder_open: bool,
+    leaderboard: Leaderboard,
+    /// Standings shown after a multiplayer game until someone presses X.
+    standings_open: bool,
+    /// Mouse position from the last update, for hover highlighting.
+    mouse: (i32, i32),
+    /// The seat that last moved the menu cursor, whose avatar marks it.
+    cursor_seat: usize,
+    idle_frames: u32,
+    /// Button changes and their timing, to seed games.
+    entropy: rng::Entropy,
+    /// The last game launched and every input since, for bug reports.
+    recorder: capsule::Recorder,
+    /// Set while a capsule plays back, so the disk is left alone.
+    replaying: bool,
+    #[cfg(feature = "cheats")]
+    cheats: cheats::CheatMenu,
+    /// Whether the next draw repaints the screen or keeps last frame's.
+    redraw: bool,
 }
 
 impl MainGame {
     pub fn init(&mut self) {
-        if self.games.is_none() {
-            self.games = Some([
-                ("Blackjack", BlackJack::new)
-            ]);
-            self.num_games = 1;
-            self.player_state = PlayerState { bank: 100 };
+        self.num_games = GAMES.len();
+        self.multiplayer = GAMES
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, _, _, multiplayer))| *multiplayer)
+            .fold(0, |mask, (index, _)| mask | 1 << index);
+        self.reset();
+    }
+
+    /// Forgets everything loaded from disk and goes back to slot select.
+    fn reset(&mut self) {
+        self.current_index = 0;
+        self.last_index = None;
+        self.favorites = 0;
+        self.play_frames = 0;
+        self.profiles.clear();
+        self.seats = [None; 4];
+        self.resume = None;
+        self.leaderboard = Leaderboard::new();
+        self.player_state = PlayerState {
+            banks: [STARTING_BANK; 4],
+            session_start_bank: STARTING_BANK,
+            ..Default::default()
+        };
+        self.slot_screen = Some(SlotScreen::new());
+        self.apply_controls();
+    }
+
+    fn apply_controls(&mut self) {
+        sfx::set_volume(self.player_state.settings.sfx_volume);
+        self.player_state.animation_speed = self.player_state.settings.animation_speed.multiplier();
+        #[cfg(feature = "cheats")]
+        if let Some(speed) = self.cheats.speed() {
+            self.player_state.animation_speed = speed;
+        }
+    }
+
+    fn active_profile(&mut self) -> Option<&mut Profile> {
+        self.seats[0].map(|index| &mut self.profiles[index])
+    }
+
+    fn is_multiplayer(&self, index: usize) -> bool {
+        self.multiplayer & (1 << index) != 0
+    }
+
+    fn is_favorite(&self, index: usize) -> bool {
+        self.favorites & (1 << index) != 0
+    }
+
+    /// Game indices in the order they are listed on the menu, favorites first.
+    fn menu_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.num_games).collect();
+        order.sort_by_key(|index| !self.is_favorite(*index));
+        order
+    }
+
+    fn toggle_favorite(&mut self, index: usize) {
+        self.favorites ^= 1 << index;
+        self.current_index = self.menu_order().iter().position(|i| *i == index).unwrap_or(0);
+        self.save();
+    }
+
+    fn load_slot(&mut self, slot: usize) {
+        let save_data = match SaveData::load(slot) {
+            Ok(save_data) => save_data,
+            Err(SaveError::Blank) => SaveData::default(),
+            Err(error) => {
+                log!(Warn, "menu", "slot {} unreadable ({:?}), starting fresh", slot, error);
+                self.toast = Some(Toast::new("Save corrupt, reset"));
+                SaveData::default()
+            }
+        };
+        self.slot = slot;
+        self.favorites = save_data.favorites;
+        self.play_frames = save_data.play_frames;
+        self.profiles = save_data.profiles;
+        self.resume = save_data.resume;
+        self.player_state.settings = save_data.settings;
+        self.apply_controls();
+        self.profile_screen = Some(ProfileScreen::new());
+    }
+
+    fn save(&self) {
+        if self.replaying {
+            return
+        }
+        let stored = SaveData {
+            favorites: self.favorites,
+            play_frames: self.play_frames,
+            profiles: self.profiles.clone(),
+            resume: self.resume.clone(),
+            settings: self.player_state.settings,
+        }.store(self.slot);
+        if let Err(error) = stored {
+            log!(Error, "menu", "slot {} not saved ({:?})", self.slot, error);
+        }
+    }
+
+    /// Relaunches a round that was autosaved for the active profile.
+    fn resume_game(&mut self) {
+        let resume = match &self.resume {
+            Some(resume) if Some(resume.profile) == self.seats[0] && resume.game < self.num_games => resume.clone(),
+            _ => return,
+        };
+        self.launch(resume.game, Some(&resume.snapshot));
+    }
+
+    /// Writes the running game's snapshot to disk whenever it changes.
+    fn autosave(&mut self) {
+        let (game, Some(index), Some(profile)) = (&self.current_game, self.playing, self.seats[0]) else {
+            return
+        };
+        let resume = game.as_ref().and_then(|g| g.snapshot()).map(|snapshot| Resume {
+            game: index,
+            profile,
+            snapshot,
+        });
+        if resume != self.resume {
+            self.resume = resume;
+            self.save();
+        }
+    }
+
+    /// Launches the game at `index`, asking for a shuffle seed first if
+    /// settings say to.
+    fn sit_down(&mut self, index: usize) {
+        if !self.player_state.settings.seed_entry {
+            self.launch(index, None);
+            return
+        }
+        // a random code to take as it is, or delete and type over
+        let code = rng::seed_code(self.entropy.seed(self.frame_count));
+        self.seed_entry = Some((index, Keyboard::filled("Shuffle seed", passcode::ALPHABET, code)));
+    }
+
+    /// Starts the game at `index`, picking up from `snapshot` if there is
+    /// one.
+    fn launch(&mut self, index: usize, snapshot: Option<&[u8]>) {
+        self.launch_on(index, self.entropy.seed(self.frame_count), snapshot);
+    }
+
+    /// Starts the game at `index` shuffled from `seed`, picking up from
+    /// `snapshot` if there is one.
+    fn launch_on(&mut self, index: usize, seed: u64, snapshot: Option<&[u8]>) {
+        if self.player_state.promotion.is_none() {
+            self.player_state.promotion = Promotion::roll(&mut rng::Rng::with_seed(seed));
+            if let Some(promotion) = self.player_state.promotion {
+                self.toast = Some(Toast::new(promotion.announcement()));
+            }
+        }
+        self.start(index, seed, snapshot);
+    }
+
+    /// Starts the game at `index` on `seed` with the shared state as it
+    /// stands, picking up from `snapshot` if there is one.
+    fn start(&mut self, index: usize, seed: u64, snapshot: Option<&[u8]>) {
+        let (_, func, _, _) = &GAMES[index];
+        log!(Info, "menu", "launching {} with seed {} on frame {}", GAMES[index].0, seed, self.frame_count);
+        self.recorder.start(index, seed, self.player_state, snapshot);
+        let mut game = (*func)(seed);
+        game.share_state(self.player_state);
+        self.launch_state = self.player_state;
+        if let Some(snapshot) = snapshot {
+            if game.restore(snapshot) {
+                self.launch_state = game.peek_state().unwrap_or(self.launch_state);
+            }
+        }
+        game.on_enter();
+        #[cfg(feature = "cheats")]
+        if core::mem::take(&mut self.cheats.jackpot) && !game.force_jackpot() {
+            self.toast = Some(Toast::new("No jackpot to rig"));
+        }
+        self.current_game = Some(game);
+        self.transition = Some(Transition::new(Wipe::Enter, self.frame_count as u32, self.player_state.animation_speed));
+        self.last_index = Some(index);
+        self.playing = Some(index);
+    }
+
+    /// Traces the capsule for the last game launched, with what's on screen
+    /// now to check a replay against.
+    fn dump_capsule(&self) {
+        let Some(start) = self.recorder.started() else {
+            trace("capsule: no game launched yet");
+            return
+        };
+        let cards_left = self.cards_left().map_or(-1, |cards| cards as i32);
+        trace(alloc::format!(
+            "capsule: {} seed {} in {} with {} cards left, {} frames{}",
+            GAMES[start.game].0,
+            start.seed,
+            self.state_name(),
+            cards_left,
+            self.recorder.frames(),
+            if self.recorder.is_full() { ", log full" } else { "" },
+        ));
+        if let Some(bytes) = self.recorder.encode() {
+            capsule::to_text(&bytes, |line| trace(line));
+        }
+    }
+
+    /// Launches a capsule's game as it was launched, before its inputs are
+    /// played back.
+    #[cfg(debug_assertions)]
+    fn play_back(&mut self, start: &capsule::Start) {
+        self.replaying = true;
+        self.slot_screen = None;
+        self.profile_screen = None;
+        self.player_state = start.state;
+        self.apply_controls();
+        self.recorder.record(start.previous);
+        self.start(start.game, start.seed, start.snapshot.as_deref());
+    }
+
+    /// Runs the developer menu, which holding left and down opens over the
+    /// main menu. Returns true while it's open.
+    #[cfg(feature = "cheats")]
+    fn update_cheats(&mut self, inputs: &Inputs) -> bool {
+        if !self.cheats.open {
+            if self.current_game.is_none() && cheats::combo(inputs) {
+                sfx::confirm();
+                self.cheats.open = true;
+            }
+            return self.cheats.open
+        }
+        let Some(cheat) = self.cheats.update(inputs, &mut self.player_state.banks[0]) else {
+            return true
+        };
+        self.cheats.open = false;
+        // the edited bank goes to player one's profile, the speed to the timers
+        self.share_state(self.player_state);
+        match cheat {
+            cheats::Cheat::Close => {}
+            cheats::Cheat::Jump(cheats::Jump::Reminder) => self.reminder_open = true,
+            cheats::Cheat::Jump(cheats::Jump::Standings) => self.standings_open = true,
+            cheats::Cheat::Jump(cheats::Jump::Slots) => self.reset(),
+            cheats::Cheat::Jump(jump) => {
+                // the rest are blackjack hands, dealt at a fresh table
+                if let Some(index) = GAMES.iter().position(|(name, ..)| *name == "Blackjack") {
+                    self.launch(index, None);
+                }
+                if !self.current_game.as_mut().is_some_and(|game| game.jump(jump)) {
+                    self.toast = Some(Toast::new("Can't deal that hand"));
+                }
+            }
+        }
+        true
+    }
+
+    /// Counts frames without any input, returning true once the idle timeout
+    /// from settings has passed.
+    fn tick_idle(&mut self, inputs: &[Inputs; 4]) -> bool {
+        if inputs.iter().any(|inputs| inputs.any_press() || inputs.press_mouse_left) {
+            self.idle_frames = 0;
+        } else {
+            self.idle_frames = self.idle_frames.saturating_add(1);
+        }
+        match self.player_state.settings.idle_minutes {
+            Some(minutes) => self.idle_frames >= timers::secs(minutes as f32 * 60.0),
+            None => false,
+        }
+    }
+
+    fn open_settings(&mut self) {
+        let mut settings = Game::Settings(SettingsScreen::new(self.player_state, self.entropy.seed(self.frame_count)));
+        settings.on_enter();
+        self.current_game = Some(settings);
+        self.transition = Some(Transition::new(Wipe::Enter, self.frame_count as u32, self.player_state.animation_speed));
+        self.playing = None;
+    }
+
+    /// Whether the pause menu can close the game: never with a bet on the
+    /// table, and only for games that can hand back the shared state.
+    fn can_leave(&self, game: &Game) -> bool {
+        !game.bet_live() && game.peek_state().is_some()
+    }
+
+    fn record_stats(&mut self, state: PlayerState) {
+        let launch_state = self.launch_state;
+        if let (Some(index), Some(profile)) = (self.playing, self.active_profile()) {
+            let difficulty = launch_state.settings.difficulty;
+            let net = state.bank() as i64 - launch_state.bank() as i64;
+            let net_winnings = net.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            profile.stats[index][difficulty as usize].record(state.rounds - launch_state.rounds, net_winnings);
+            profile.bank = state.bank();
+            if let Some(achievement) = Achievement::unlock(profile, net_winnings) {
+                self.toast = Some(Toast::new(achievement.announcement()));
+                self.particles.spawn(Burst { effect: Effect::Sparkles, x: 80, y: 51 });
+            }
+        }
+    }
+
+    /// Whether this frame has to be drawn from scratch. A game that sits
+    /// still between key presses is left on screen as long as nothing is
+    /// pressed and nothing is moving over it.
+    fn should_redraw(&self, input_active: bool) -> bool {
+        let overlays = self.toast.is_some()
+            || self.particles.active()
+            || self.screen_effects.active()
+            || self.transition.is_some()
+            || self.pause.is_some()
+            || self.reminder_open
+            || self.standings_open;
+        match &self.current_game {
+            Some(game) => input_active || overlays || game.needs_redraw(),
+            None => true,
+        }
+    }
+
+    fn export_stats(&mut self) {
+        let names: Vec<&str> = GAMES[..self.num_games].iter().map(|(name, _, _, _)| *name).collect();
+        stats::trace_stats(&self.profiles, &names, self.play_frames);
+        self.toast = Some(Toast::new("Stats sent to log"));
+    }
+
+    /// Seat markers along the top of the menu, with a join prompt while
+    /// there are free seats.
+    fn draw_seats(&self) {
+        for seat in 0..4 {
+            let active = self.player_state.is_active(seat);
+            unsafe { *DRAW_COLORS = 0x02; }
+            if active {
+                self.player_state.looks[seat].draw(122 + 9 * seat as i32, 4);
+            } else {
+                text(fixed_format!("{}", seat + 1), 122 + 9 * seat as i32, 4);
+            }
+            if local_player() == Some(seat) {
+                // underline our own seat during netplay
+                hline(122 + 9 * seat as i32, 13, 8);
+            }
+        }
+        if self.player_state.num_players() < 4 {
+            unsafe { *DRAW_COLORS = 0x02; }
+            let t = b"\x80: join";
+            unsafe {
+                extern_text(t.as_ptr(), t.len(), 4, 4);
+            }
+        }
+    }
+
+    fn draw_stats_panel(&self, index: usize) {
+        let difficulty = self.player_state.settings.difficulty;
+        let profile = match self.seats[0] {
+            Some(profile) => &self.profiles[profile],
+            None => return,
+        };
+        let stats = profile.stats[index][difficulty as usize];
+        unsafe {
+            *DRAW_COLORS = 0x0020;
+        }
+        rect(10, 95, 140, 42);
+        unsafe {
+            *DRAW_COLORS = (*DRAW_COLORS & 0b1111111100000000) | 0x02
+        }
+        text(difficulty.name(), 14, 99);
+        let unlocked = profile.achievements.count_ones();
+        text(fixed_format!("{}/{}", unlocked, Achievement::ALL.len()), 126, 99);
+        text(fixed_format!("Rounds: {}", stats.rounds_played), 14, 111);
+        text(fixed_format!("Net: {}", money::signed(stats.net_winnings as i64)), 14, 123);
+        let t = b"\x85:log";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 100, 111);
+        }
+    }
+
+    fn draw_reminder(&self) {
+        let state = self
+            .current_game
+            .as_ref()
+            .and_then(|g| g.peek_state())
+            .unwrap_or(self.player_state);
+        unsafe { *DRAW_COLORS = 0x23; }
+        rect(10, 40, 140, 70);
+        unsafe { *DRAW_COLORS = 0x01; }
+        text("Time for a break?", 12, 46);
+        let minutes = self.frame_count / 60 / 60;
+        text(fixed_format!("Played: {}h {:02}m", minutes / 60, minutes % 60), 16, 62);
+        text(fixed_format!("Net: {}", money::signed(state.session_net())), 16, 74);
+        let t = b"\x80: keep playing";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 16, 96);
         }
     }
 }
 
 impl Model<PlayerState> for MainGame {
     fn draw(&self) {
+        if !self.redraw {
+            return
+        }
+        // the framebuffer is kept between frames, so clear it ourselves
+        unsafe { (*FRAMEBUFFER).fill(0); }
+        let display = self.player_state.settings.display();
         match self {
+            Self { slot_screen: Some(screen), .. } => {
+                screen.draw();
+            }
+            Self { profile_screen: Some(screen), .. } => {
+                screen.draw(&self.profiles, display);
+            }
+            Self { lobby: Some(lobby), .. } => {
+                let (name, _, _, _) = GAMES[lobby.game];
+                lobby.draw(name, &self.player_state);
+            }
+            Self { seed_entry: Some((_, entry)), .. } => {
+                entry.draw(display);
+            }
             Self { current_game: Some(g), .. } => {
                 g.draw()
             }
             Self { current_game: None, .. } => {
-                for (index, (name, _)) in self.games.unwrap().iter().enumerate() {
+                self.draw_seats();
+                for (index, game_index) in self.menu_order().into_iter().enumerate() {
+                    let (name, _, _, _) = GAMES[game_index];
                     if index == self.current_index {
-                        unsafe {
-                            *DRAW_COLORS = (*DRAW_COLORS & 0b1111111100000000) | 0x32
-                        }
+                        ui::set_text_colors(0x32, display);
+                    } else if point_in(self.mouse, 10, menu_row_y(index), 120, 10) {
+                        ui::set_text_colors(0x42, display);
                     } else {
-                        unsafe {
-                            *DRAW_COLORS = (*DRAW_COLORS & 0b1111111100000000) | 0x02
-                        }
+                        ui::set_text_colors(0x02, display);
+                    }
+                    if self.is_favorite(game_index) {
+                        text("*", 10, menu_row_y(index));
+                    }
+                    text(name, 20, menu_row_y(index));
+                    if index == self.current_index {
+                        ui::draw_focus_box(10, menu_row_y(index), 120, display);
+                        self.player_state.looks[self.cursor_seat].draw(132, menu_row_y(index));
                     }
-                    text(&name, 20, (20 + 10 * index) as _);
+                }
+                let settings_hovered = point_in(self.mouse, 10, menu_row_y(self.num_games), 120, 10);
+                ui::set_text_colors(if self.current_index == self.num_games {
+                    0x32
+                } else if settings_hovered {
+                    0x42
+                } else {
+                    0x02
+                }, display);
+                text("Settings", 20, menu_row_y(self.num_games));
+                if self.current_index == self.num_games {
+                    ui::draw_focus_box(10, menu_row_y(self.num_games), 120, display);
+                    self.player_state.looks[self.cursor_seat].draw(132, menu_row_y(self.num_games));
+                }
+                if self.current_index < self.num_games {
+                    self.draw_stats_panel(self.menu_order()[self.current_index]);
+                }
+                if let Some(index) = self.last_index {
+                    let (name, _, _, _) = GAMES[index];
+                    ui::set_text_colors(0x02, display);
+                    let t = b"\x81: Continue";
+                    unsafe {
+                        extern_text(t.as_ptr(), t.len(), 0, 142);
+                    }
+                    text(name, 20, 151);
                 }
             }
         }
+        if let Some(transition) = &self.transition {
+            transition.draw(self.frame_count as u32);
+        }
+        if let (Some(pause), Some(game)) = (&self.pause, &self.current_game) {
+            pause.draw(self.can_leave(game), self.mouse, display);
+        }
+        if let Some(toast) = &self.toast {
+            toast.draw();
+        }
+        self.particles.draw(display);
+        if self.standings_open {
+            self.leaderboard.draw(&self.player_state);
+        }
+        if self.reminder_open {
+            self.draw_reminder();
+        }
+        #[cfg(feature = "cheats")]
+        if self.cheats.open {
+            self.cheats.draw(self.player_state.banks[0], self.mouse, display);
+        }
+        self.screen_effects.apply(display);
     }
 
-    fn update(&mut self, inputs: [crate::model::Inputs; 4]) -> Option<PlayerState> {
+    fn update(&mut self, inputs: [crate::model::Inputs; 4]) -> GameEvent<PlayerState> {
         self.frame_count += 1;
+        self.entropy.stir(self.frame_count, inputs.map(|inputs| inputs.gamepad));
+        self.recorder.record(capsule::Frame::of(&inputs));
+        let input_active = inputs.iter().any(Inputs::active) || inputs[0].mouse() != self.mouse;
+        self.redraw = self.should_redraw(input_active);
+        self.mouse = inputs[0].mouse();
+        if let Some(toast) = &mut self.toast {
+            if !toast.tick() {
+                self.toast = None;
+            }
+        }
+        self.particles.update();
+        self.screen_effects.update();
+        if self.transition.as_ref().is_some_and(|transition| transition.done(self.frame_count as u32)) {
+            self.transition = None;
+        }
+        // the settings screen runs as a game too, but keeps the menu's music
+        let at_table = self.current_game.is_some() && self.playing.is_some();
+        self.music.play(Some(if at_table { &music::TABLE } else { &music::THEME }));
+        let settings = &self.player_state.settings;
+        self.music.update(settings.music_volume);
+        self.ambience.update(at_table, settings.ambience_volume);
+        sfx::update();
+        if SESSION_REMINDER.due(self.frame_count) {
+            self.reminder_open = true;
+        }
+        if self.reminder_open {
+            if inputs[0].tap_x {
+                self.reminder_open = false;
+            }
+            return GameEvent::Continue
+        }
+        if self.standings_open {
+            if inputs.iter().enumerate().any(|(seat, inputs)| self.player_state.is_active(seat) && inputs.tap_x) {
+                self.standings_open = false;
+            }
+            return GameEvent::Continue
+        }
+        if let Some(screen) = &mut self.slot_screen {
+            if let Some(slot) = screen.update(inputs[0]) {
+                self.slot_screen = None;
+                self.load_slot(slot);
+            }
+            return GameEvent::Continue
+        }
+        self.play_frames = self.play_frames.saturating_add(1);
+        if let Some(screen) = &mut self.profile_screen {
+            if let Some(seats) = screen.update(inputs, &mut self.profiles) {
+                self.seats = seats;
+                self.profile_screen = None;
+                // everyone who picked a profile is dealt in
+                self.player_state.active_seats = seats
+                    .iter()
+                    .enumerate()
+                    .fold(1, |active, (seat, profile)| if profile.is_some() { active | 1 << seat } else { active });
+                for (seat, profile) in seats.iter().enumerate() {
+                    if let Some(profile) = profile {
+                        self.player_state.banks[seat] = self.profiles[*profile].bank;
+                    }
+                }
+                self.player_state.session_start_bank = self.player_state.bank();
+                self.save();
+                self.resume_game();
+            }
+            return GameEvent::Continue
+        }
+        if let Some(lobby) = &mut self.lobby {
+            match lobby.update(inputs, &mut self.player_state) {
+                Some(LobbyResult::Start) => {
+                    let game = lobby.game;
+                    self.lobby = None;
+                    self.sit_down(game);
+                }
+                Some(LobbyResult::Cancel) => self.lobby = None,
+                None => {}
+            }
+            return GameEvent::Continue
+        }
+        if let Some((index, entry)) = &mut self.seed_entry {
+            if let Some(code) = entry.update(&inputs[0]) {
+                let index = *index;
+                self.seed_entry = None;
+                if let Some(code) = code {
+                    self.launch_on(index, rng::seed_from_code(&code), None);
+                    // a promotion's announcement goes first
+                    if self.toast.is_none() {
+                        let code = core::str::from_utf8(&code).unwrap_or_default();
+                        self.toast = Some(Toast::new(&fixed_format!("Seed {}", code)));
+                    }
+                }
+            }
+            return GameEvent::Continue
+        }
+        #[cfg(feature = "cheats")]
+        if self.update_cheats(&inputs[0]) {
+            return GameEvent::Continue
+        }
+        let mut leave = false;
+        let can_leave = self.current_game.as_ref().is_some_and(|game| self.can_leave(game));
+        if let Some(pause) = &mut self.pause {
+            match pause.update(&inputs[0], can_leave) {
+                Some(PauseChoice::Resume) => {
+                    self.pause = None;
+                    if let Some(game) = &mut self.current_game {
+                        game.on_resume();
+                    }
+                }
+                Some(PauseChoice::Leave) => {
+                    self.pause = None;
+                    leave = true;
+                }
+                None => return GameEvent::Continue,
+            }
+            if !leave {
+                return GameEvent::Continue
+            }
+        }
+        // holding down or right-clicking pauses a game, but not settings
+        if let (Some(index), Some(game)) = (self.playing, &mut self.current_game) {
+            if inputs[0].long_down || inputs[0].tap_mouse_right {
+                game.on_pause();
+                let (_, _, pages, _) = GAMES[index];
+                self.pause = Some(PauseMenu::new(pages));
+                return GameEvent::Continue
+            }
+        }
+        let idle = self.tick_idle(&inputs);
+        let mut launch = None;
+        let mut open_settings = false;
+        let mut export_stats = false;
+        let mut joined = None;
+        let mut favorite = None;
+        let menu_order = self.menu_order();
         match self {
             Self { current_game: Some(g), .. } => {
-                if let Some(state) = g.update(inputs) {
+                let event = if leave {
+                    g.peek_state().map_or(GameEvent::Continue, |state| GameEvent::Exit { state })
+                } else {
+                    g.update(inputs)
+                };
+                let mut finished = None;
+                let mut keep = None;
+                let mut erased = false;
+                match event {
+                    GameEvent::Continue => {}
+                    GameEvent::Exit { state } => finished = Some(state),
+                    GameEvent::SwitchTo { state, game } => {
+                        finished = Some(state);
+                        // a game trimmed out of this cart is just left
+                        launch = GAMES.iter().position(|(name, _, _, _)| *name == game);
+                    }
+                    GameEvent::RequestSave => keep = g.peek_state(),
+                    GameEvent::PlaySfx(sting) => self.music.sting(sting),
+                    GameEvent::EraseSave => erased = true,
+                }
+                if let Some(burst) = g.take_burst() {
+                    self.particles.spawn(burst);
+                }
+                if let Some(effect) = g.take_screen_effect() {
+                    self.screen_effects.request(effect, self.player_state.settings.display());
+                }
+                // a game that errors hands its state back as it leaves
+                let error = g.take_error();
+                if let Some(error) = error {
+                    log!(Error, "menu", "game left with an error: {}", error.message());
+                }
+                if erased {
+                    g.on_exit();
                     self.current_game = None;
-                    self.share_state(state);
+                    self.transition = Some(Transition::new(Wipe::Return, self.frame_count as u32, self.player_state.animation_speed));
+                    log!(Info, "menu", "erasing save slot {}", self.slot);
+                    SaveData::erase(self.slot);
+                    self.reset();
+                    self.toast = Some(Toast::new("Save data deleted"));
+                    return GameEvent::Continue
+                }
+                let mut timed_out = false;
+                // an abandoned table goes back to the menu, but never with a
+                // bet on it
+                if finished.is_none() && idle && !g.bet_live() {
+                    finished = g.peek_state();
+                    timed_out = finished.is_some();
+                }
+                if let Some(state) = finished {
+                    g.on_exit();
+                    self.current_game = None;
+                    self.transition = Some(Transition::new(Wipe::Return, self.frame_count as u32, self.player_state.animation_speed));
+                    if timed_out {
+                        self.toast = Some(Toast::new("Idle, back to menu"));
+                    }
+                    if let Some(error) = error {
+                        self.toast = Some(Toast::new(error.message()));
+                    }
+                    log!(Info, "menu", "back from a game with banks {:?}", state.banks);
+                    self.resume = None;
+                    self.record_stats(state);
+                    if self.playing.is_some() && self.launch_state.num_players() > 1 {
+                        self.leaderboard.record(&self.launch_state, &state);
+                        self.standings_open = true;
+                    }
+                    self.share_state(PlayerState { results: None, ..state });
+                } else {
+                    // the round only moves on a key press or while the game says
+                    // it's moving
+                    let moved = input_active || g.needs_redraw();
+                    if let Some(state) = keep {
+                        self.share_state(state);
+                    }
+                    if moved {
+                        self.autosave();
+                    }
                 }
             }
             Self {
-                current_game,
-                player_state,
-                games: Some(games),
+                current_game: None,
                 num_games,
                 current_index,
+                last_index,
+                x_hold_frames,
+                player_state,
+                cursor_seat,
                 ..
             } => {
-                let first_player_inputs = inputs[0];
-                if first_player_inputs.tap_down {
-                    *current_index = (*current_index + 1) % *num_games;
+                // the last menu row is the settings screen
+                let num_rows = *num_games + 1;
+                // gamepads 2-4 join with X and leave by holding Z
+                for (seat, seat_inputs) in inputs.iter().enumerate().skip(1) {
+                    if !player_state.is_active(seat) && seat_inputs.tap_x {
+                        player_state.active_seats |= 1 << seat;
+                        joined = Some(seat);
+                    } else if player_state.is_active(seat) && seat_inputs.long_z {
+                        player_state.active_seats &= !(1 << seat);
+                        if *cursor_seat == seat {
+                            *cursor_seat = 0;
+                        }
+                    }
                 }
-                if first_player_inputs.tap_up {
+                // anyone seated can drive the menu, not just player one
+                let seated_inputs = [0, 1, 2, 3].map(|seat| {
+                    if player_state.is_active(seat) && joined != Some(seat) { inputs[seat] } else { Inputs::default() }
+                });
+                let menu_inputs = Inputs::merge(&seated_inputs);
+                if let Some(seat) = seated_inputs.iter().position(|inputs| inputs.any_tap()) {
+                    *cursor_seat = seat;
+                }
+                if menu_inputs.tap_down || menu_inputs.tap_up {
+                    sfx::cursor_move();
+                }
+                if menu_inputs.tap_down {
+                    *current_index = (*current_index + 1) % num_rows;
+                }
+                if menu_inputs.tap_up {
                     if *current_index == 0 {
-                        *current_index = *num_games - 1;
+                        *current_index = num_rows - 1;
                     } else {
-                        *current_index = (*current_index - 1) % *num_games;
+                        *current_index = (*current_index - 1) % num_rows;
                     }
                 }
-                if first_player_inputs.tap_x {
-                    let (_, func) = &games[*current_index];
-                    let mut game = (*func)(
-                        self.frame_count + unsafe { *MOUSE_X + *MOUSE_Y } as u64
-                    );
-                    game.share_state(*player_state);
-                    *current_game = Some(game);
+                // tapping X launches the game, holding it toggles a favorite
+                if menu_inputs.press_x && (menu_inputs.tap_x || *x_hold_frames > 0) {
+                    *x_hold_frames += 1;
+                    if menu_inputs.long_x {
+                        // the press is used up, so letting go doesn't launch too
+                        *x_hold_frames = 0;
+                        if *current_index < *num_games {
+                            favorite = Some(menu_order[*current_index]);
+                        }
+                    }
+                } else {
+                    if *x_hold_frames > 0 {
+                        if *current_index == *num_games {
+                            open_settings = true;
+                        } else {
+                            launch = Some(menu_order[*current_index]);
+                        }
+                    }
+                    *x_hold_frames = 0;
+                }
+                // clicking a row picks it straight away
+                let clicked_row = (0..num_rows).find(|row| menu_inputs.clicked(10, menu_row_y(*row), 120, 10));
+                if let Some(row) = clicked_row {
+                    *current_index = row;
+                    if row == *num_games {
+                        open_settings = true;
+                    } else {
+                        launch = Some(menu_order[row]);
+                    }
+                }
+                if menu_inputs.tap_z {
+                    launch = *last_index;
+                }
+                if menu_inputs.tap_right && *current_index < *num_games {
+                    export_stats = true;
                 }
             },
-            _ => unreachable!()
         }
-        None
+        if favorite.is_some() || launch.is_some() || open_settings || joined.is_some() {
+            sfx::confirm();
+        }
+        if let Some(index) = favorite {
+            self.toggle_favorite(index);
+        }
+        if let Some(index) = launch {
+            if self.is_multiplayer(index) {
+                self.lobby = Some(Lobby::new(index));
+            } else {
+                self.sit_down(index);
+            }
+        }
+        if open_settings {
+            self.open_settings();
+        }
+        if export_stats {
+            self.export_stats();
+        }
+        if let Some(seat) = joined {
+            self.toast = Some(Toast::new(["", "Player 2 joined", "Player 3 joined", "Player 4 joined"][seat]));
+        }
+        GameEvent::Continue
     }
 
     fn share_state(&mut self, state: PlayerState) {
         self.player_state = state;
+        self.apply_controls();
+        // every seated profile keeps what its seat won or lost
+        for (seat, profile) in self.seats.iter().enumerate() {
+            if let Some(profile) = profile {
+                self.profiles[*profile].bank = state.banks[seat];
+            }
+        }
+        self.save();
     }
+
+    fn state_name(&self) -> &'static str {
+        match self {
+            Self { slot_screen: Some(_), .. } => "Slots",
+            Self { profile_screen: Some(_), .. } => "Profiles",
+            Self { lobby: Some(_), .. } => "Lobby",
+            Self { seed_entry: Some(_), .. } => "Seed",
+            Self { current_game: Some(game), .. } => game.state_name(),
+            Self { current_game: None, .. } => "Menu",
+        }
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        self.current_game.as_ref().and_then(Game::cards_left)
+    }
+}
+
+
+/// Everything the cart keeps from one frame to the next.
+struct Runtime {
+    game: MainGame,
+    /// Each gamepad's buttons as of last frame, to tell presses from holds.
+    gamepads_prev: [u8; 4],
+    mouse_buttons_prev: u8,
+    /// Each user's long press and double tap timers.
+    gestures: [Gestures; 4],
+    debug: DebugOverlay,
+    /// A capsule from the `CAPSULE` build variable, being played back.
+    #[cfg(debug_assertions)]
+    replay: Option<capsule::Replay>,
+    /// Whether `replay` has launched its game yet.
+    #[cfg(debug_assertions)]
+    replay_started: bool,
 }
 
+impl Runtime {
+    fn new() -> Self {
+        let player_state = PlayerState::default();
+        let game = MainGame {
+            frame_count: 0,
+            num_games: 0,
+            current_index: 0,
+            last_index: None,
+            playing: None,
+            favorites: 0,
+            x_hold_frames: 0,
+            current_game: None,
+            player_state,
+            launch_state: player_state,
+            profiles: Vec::new(),
+            seats: [None; 4],
+            profile_screen: None,
+            slot: 0,
+            slot_screen: None,
+            lobby: None,
+            seed_entry: None,
+            multiplayer: 0,
+            play_frames: 0,
+            resume: None,
+            toast: None,
+            particles: Particles::new(),
+            screen_effects: ScreenEffects::new(),
+            transition: None,
+            pause: None,
+            music: Music::new(),
+            ambience: Ambience::new(),
+            reminder_open: false,
+            leaderboard: Leaderboard::new(),
+            standings_open: false,
+            mouse: (0, 0),
+            cursor_seat: 0,
+            idle_frames: 0,
+            entropy: rng::Entropy::default(),
+            recorder: capsule::Recorder::new(),
+            replaying: false,
+            #[cfg(feature = "cheats")]
+            cheats: cheats::CheatMenu::new(),
+            redraw: true,
+        };
+        Self {
+            game,
+            gamepads_prev: [0; 4],
+            mouse_buttons_prev: 0,
+            gestures: [Gestures::new(); 4],
+            debug: DebugOverlay::new(),
+            #[cfg(debug_assertions)]
+            replay: Self::load_capsule(),
+            #[cfg(debug_assertions)]
+            replay_started: false,
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn load_capsule() -> Option<capsule::Replay> {
+        let text = option_env!("CAPSULE")?;
+        let replay = capsule::from_text(text).as_deref().and_then(capsule::Replay::decode);
+        match &replay {
+            Some(replay) if replay.start.game < GAMES.len() => {}
+            _ => {
+                log!(Error, "capsule", "CAPSULE doesn't read as a capsule for this cart");
+                return None
+            }
+        }
+        replay
+    }
+
+    /// Launches the capsule being played back on its first frame, then puts
+    /// each frame's inputs where they're read from.
+    #[cfg(debug_assertions)]
+    fn play_back(&mut self) {
+        let Some(replay) = &mut self.replay else {
+            return
+        };
+        if !self.replay_started {
+            self.replay_started = true;
+            self.game.play_back(&replay.start);
+            self.gamepads_prev = replay.start.previous.gamepads;
+            self.mouse_buttons_prev = replay.start.previous.mouse_buttons;
+            self.gestures = [Gestures::new(); 4];
+        }
+        match replay.next() {
+            Some(frame) => frame.write(),
+            None => {
+                trace("capsule played back, over to you");
+                self.replay = None;
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        #[cfg(debug_assertions)]
+        self.play_back();
+        let users = [User::One, User::Two, User::Three, User::Four];
+        let controls = self.game.player_state.settings.controls;
+        let inputs = core::array::from_fn(|index| {
+            users[index].get_inputs(self.gamepads_prev[index], self.mouse_buttons_prev, &controls, &mut self.gestures[index])
+        });
 
-static mut GAME: MainGame = MainGame {
-    frame_count: 0,
-    games: None,
-    num_games: 0,
-    current_index: 0,
-    current_game: None,
-    player_state: PlayerState { bank: 0 }
-};
+        self.debug.start_frame();
+        self.game.update(inputs);
+        self.game.draw();
+        self.debug.end_frame(&inputs[0], &self.game);
+        if inputs[0].press_z && inputs[0].double_down {
+            self.game.dump_capsule();
+        }
+
+        self.gamepads_prev = users.map(|user| user.gamepad());
+        self.mouse_buttons_prev = unsafe { *MOUSE_BUTTONS };
+    }
+}
+
+static RUNTIME: Global<Runtime> = Global::new();
 
 #[no_mangle]
-unsafe fn update() {
-    start_frame();
-    let inputs = [
-        User::One.get_inputs(),
-        User::Two.get_inputs(),
-        User::Three.get_inputs(),
-        User::Four.get_inputs(),
-    ];
-
-    GAME.update(inputs);
-    GAME.draw();
-
-    end_frame();
+fn update() {
+    match crash::update() {
+        crash::Status::Running => {}
+        crash::Status::Crashed => return,
+        crash::Status::Dismissed => {
+            RUNTIME.reset(Runtime::new());
+            boot();
+        }
+    }
+    RUNTIME.with(Runtime::update);
 }
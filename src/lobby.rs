@@ -0,0 +1,103 @@
+use crate::{model::{local_player, netplay_active, Inputs}, sfx, wasm4::*, PlayerState};
+
+/// Ready-up screen shown before a multiplayer game. Seats join with X like on
+/// the menu, then each seated player presses X again to ready up; the game
+/// starts once everyone seated is ready. Until then the d-pad picks a seat's
+/// avatar and color.
+pub struct Lobby {
+    pub game: usize,
+    ready: [bool; 4],
+}
+
+pub enum LobbyResult {
+    Start,
+    Cancel,
+}
+
+impl Lobby {
+    pub fn new(game: usize) -> Self {
+        Self { game, ready: [false; 4] }
+    }
+
+    pub fn update(&mut self, inputs: [Inputs; 4], player_state: &mut PlayerState) -> Option<LobbyResult> {
+        for (seat, seat_inputs) in inputs.iter().enumerate() {
+            if player_state.is_active(seat) && !self.ready[seat] {
+                let look = &mut player_state.looks[seat];
+                if seat_inputs.tap_left || seat_inputs.tap_right {
+                    look.next_avatar(if seat_inputs.tap_left { -1 } else { 1 });
+                }
+                if seat_inputs.tap_up || seat_inputs.tap_down {
+                    look.next_color(if seat_inputs.tap_up { -1 } else { 1 });
+                }
+                if seat_inputs.tap_left || seat_inputs.tap_right || seat_inputs.tap_up || seat_inputs.tap_down {
+                    sfx::cursor_move();
+                }
+            }
+            if !seat_inputs.tap_x {
+                continue
+            }
+            if player_state.is_active(seat) {
+                self.ready[seat] = !self.ready[seat];
+                if self.ready[seat] { sfx::confirm() } else { sfx::cancel() }
+            } else {
+                sfx::confirm();
+                player_state.active_seats |= 1 << seat;
+            }
+        }
+        if inputs[0].tap_z {
+            sfx::cancel();
+            return Some(LobbyResult::Cancel)
+        }
+        let everyone_ready = (0..4).all(|seat| !player_state.is_active(seat) || self.ready[seat]);
+        if everyone_ready && player_state.num_players() > 1 {
+            return Some(LobbyResult::Start)
+        }
+        None
+    }
+
+    pub fn draw(&self, name: &str, player_state: &PlayerState) {
+        unsafe { *DRAW_COLORS = 0x02; }
+        text(name, 80 - 4 * name.len() as i32, 10);
+        if netplay_active() {
+            text("Netplay lobby", 28, 22);
+        }
+        for seat in 0..4 {
+            let y = 40 + 14 * seat as i32;
+            let ready = self.ready[seat];
+            unsafe { *DRAW_COLORS = if ready { 0x32 } else { 0x02 }; }
+            text(fixed_format!("Player {}", seat + 1), 20, y);
+            if player_state.is_active(seat) {
+                player_state.looks[seat].draw(88, y);
+            }
+            if local_player() == Some(seat) {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(">", 8, y);
+            }
+            unsafe { *DRAW_COLORS = 0x02; }
+            let status = if !player_state.is_active(seat) {
+                "--"
+            } else if ready {
+                "Ready"
+            } else {
+                "Waiting"
+            };
+            text(status, 100, y);
+        }
+        unsafe { *DRAW_COLORS = 0x02; }
+        if player_state.num_players() < 2 {
+            text("Need 2+ players", 20, 100);
+        }
+        let t = b"\x84\x85: avatar \x86\x87: color";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 133);
+        }
+        let t = b"\x80: join/ready";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 142);
+        }
+        let t = b"\x81: back";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+}
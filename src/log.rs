@@ -0,0 +1,53 @@
+//! Levelled messages to the debug console. Errors always go out; the
+//! `log-warn`, `log-info` and `log-debug` features each let through one
+//! more level, and anything quieter is compiled out along with its
+//! formatting.
+//!
+//! Each line starts with its level and what it's about, like
+//! `[info blackjack] paid $40 on $20`, so a console full of them can be
+//! filtered by game.
+
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+        }
+    }
+}
+
+/// The quietest level that gets through, from the `log-*` features.
+pub const MAX_LEVEL: Level = if cfg!(feature = "log-debug") {
+    Level::Debug
+} else if cfg!(feature = "log-info") {
+    Level::Info
+} else if cfg!(feature = "log-warn") {
+    Level::Warn
+} else {
+    Level::Error
+};
+
+/// `log!(Info, "blackjack", "paid {}", amount)` traces the message under
+/// that level and subject, if the level is enabled.
+macro_rules! log {
+    ($level:ident, $subject:expr, $($arg:tt)+) => {
+        if $crate::log::Level::$level <= $crate::log::MAX_LEVEL {
+            $crate::wasm4::trace(alloc::format!(
+                "[{} {}] {}",
+                $crate::log::Level::$level.name(),
+                $subject,
+                alloc::format!($($arg)+),
+            ));
+        }
+    };
+}
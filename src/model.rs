@@ -1,6 +1,178 @@
-use crate::{wasm4::*, GAMEPAD1_PREV, GAMEPAD2_PREV, GAMEPAD3_PREV, GAMEPAD4_PREV};
+use alloc::vec::Vec;
+use crate::{effects::ScreenEffect, music::Track, particles::Burst, timers, wasm4::*};
 
-#[derive(Copy, Clone, Debug)]
+/// Gamepad buttons in the order gesture state is kept for them.
+pub const BUTTONS: [u8; 6] = [BUTTON_1, BUTTON_2, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, BUTTON_DOWN];
+
+/// How soon a button's long press and double tap fire, picked per user and
+/// per button in settings.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum GestureTiming {
+    #[default]
+    Normal,
+    Quick,
+    Relaxed,
+}
+
+impl GestureTiming {
+    const ALL: [GestureTiming; 3] = [GestureTiming::Normal, GestureTiming::Quick, GestureTiming::Relaxed];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Quick => "Quick",
+            Self::Relaxed => "Relaxed",
+        }
+    }
+
+    /// Reads a timing stored as its `u8` repr, falling back to the default
+    /// for unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        Self::ALL.get(byte as usize).copied().unwrap_or_default()
+    }
+
+    pub fn cycled(&self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(*self as isize + step).rem_euclid(len) as usize]
+    }
+
+    /// How long the button has to be held to fire `long_*` once.
+    pub fn long_press_frames(&self) -> u32 {
+        match self {
+            Self::Normal => timers::secs(0.5),
+            Self::Quick => timers::secs(0.3),
+            Self::Relaxed => timers::secs(0.8),
+        }
+    }
+
+    /// A second tap within this many frames of the first fires `double_*`.
+    pub fn double_tap_frames(&self) -> u32 {
+        match self {
+            Self::Normal => timers::secs(0.25),
+            Self::Quick => timers::secs(0.15),
+            Self::Relaxed => timers::secs(0.4),
+        }
+    }
+}
+
+/// Player one's control preferences. The button layout applies to every
+/// user's gamepad as it is read, so games never need to know about it.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Controls {
+    /// Z confirms and X backs out.
+    pub swap_buttons: bool,
+    /// The d-pad fires actions directly instead of moving a cursor, in games
+    /// that offer it.
+    pub direct_actions: bool,
+    /// Left-handed preset: left/right and up/down trade places.
+    pub mirrored: bool,
+    /// Gesture timings for each user's buttons, in `BUTTONS` order. They
+    /// follow a button's role, so with X and Z swapped the confirm button
+    /// keeps X's timing.
+    pub timings: [[GestureTiming; 6]; 4],
+}
+
+/// Exchanges two button bits in a gamepad byte.
+fn swap_bits(gamepad: u8, a: u8, b: u8) -> u8 {
+    let mut swapped = gamepad & !(a | b);
+    if gamepad & a != 0 {
+        swapped |= b;
+    }
+    if gamepad & b != 0 {
+        swapped |= a;
+    }
+    swapped
+}
+
+impl Controls {
+    /// A user's gesture timings packed two bits a button, as saved.
+    pub fn packed_timings(&self, user: usize) -> u16 {
+        self.timings[user].iter().enumerate().fold(0, |packed, (index, timing)| packed | (*timing as u16) << (2 * index))
+    }
+
+    pub fn unpack_timings(&mut self, user: usize, packed: u16) {
+        for (index, timing) in self.timings[user].iter_mut().enumerate() {
+            *timing = GestureTiming::from_byte((packed >> (2 * index) & 3) as u8);
+        }
+    }
+
+    /// Rewrites raw gamepad bits so the rest of the input layer sees the
+    /// buttons in their usual roles.
+    fn remap(&self, gamepad: u8) -> u8 {
+        let mut mapped = gamepad;
+        if self.swap_buttons {
+            mapped = swap_bits(mapped, BUTTON_1, BUTTON_2);
+        }
+        if self.mirrored {
+            mapped = swap_bits(mapped, BUTTON_LEFT, BUTTON_RIGHT);
+            mapped = swap_bits(mapped, BUTTON_UP, BUTTON_DOWN);
+        }
+        mapped
+    }
+}
+
+/// Whether a netplay session is running. Only gamepads are synchronized
+/// between clients, so anything local (the mouse, control preferences) has
+/// to stay out of game logic while it is.
+pub fn netplay_active() -> bool {
+    unsafe { *NETPLAY & 0b100 != 0 }
+}
+
+/// The gamepad this client is playing on during netplay, so screens can
+/// point out "you". Drawing may differ per client; game logic must not.
+pub fn local_player() -> Option<usize> {
+    if netplay_active() {
+        Some(unsafe { *NETPLAY & 0b11 } as usize)
+    } else {
+        None
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ButtonTimer {
+    held_frames: u32,
+    frames_since_tap: u32,
+}
+
+const IDLE_TIMER: ButtonTimer = ButtonTimer { held_frames: 0, frames_since_tap: u32::MAX };
+
+/// One user's hold and double tap timers, carried from frame to frame by
+/// whoever reads the gamepads.
+#[derive(Copy, Clone)]
+pub struct Gestures([ButtonTimer; 6]);
+
+impl Gestures {
+    pub const fn new() -> Self {
+        Self([IDLE_TIMER; 6])
+    }
+
+    /// Advances the timers by a frame, returning which buttons (in `BUTTONS`
+    /// order) fired a long press or double tap.
+    fn update(&mut self, gamepad: u8, pressed_this_frame: u8, timings: &[GestureTiming; 6]) -> ([bool; 6], [bool; 6]) {
+        let mut long = [false; 6];
+        let mut double = [false; 6];
+        for (index, button) in BUTTONS.iter().enumerate() {
+            let timer = &mut self.0[index];
+            if gamepad & button != 0 {
+                timer.held_frames += 1;
+            } else {
+                timer.held_frames = 0;
+            }
+            long[index] = timer.held_frames == timings[index].long_press_frames();
+            if pressed_this_frame & button != 0 {
+                double[index] = timer.frames_since_tap < timings[index].double_tap_frames();
+                // a third quick tap starts a new pair rather than firing again
+                timer.frames_since_tap = if double[index] { u32::MAX } else { 0 };
+            } else {
+                timer.frames_since_tap = timer.frames_since_tap.saturating_add(1);
+            }
+        }
+        (long, double)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
 pub struct Inputs {
     pub press_x: bool,
     pub press_z: bool,
@@ -15,6 +187,119 @@ pub struct Inputs {
     pub tap_right: bool,
     pub tap_up: bool,
     pub tap_down: bool,
+
+    /// True on the frame a button has been held for its user's
+    /// `GestureTiming::long_press_frames`.
+    pub long_x: bool,
+    pub long_z: bool,
+    pub long_left: bool,
+    pub long_right: bool,
+    pub long_up: bool,
+    pub long_down: bool,
+
+    /// True on the second of two quick taps. The first tap still comes
+    /// through as `tap_*`.
+    pub double_x: bool,
+    pub double_z: bool,
+    pub double_left: bool,
+    pub double_right: bool,
+    pub double_up: bool,
+    pub double_down: bool,
+
+    /// The gamepad byte as the console reported it this frame and last,
+    /// before any control remapping.
+    pub gamepad: u8,
+    pub gamepad_prev: u8,
+
+    /// Set when the player prefers the d-pad to fire actions directly.
+    pub direct_actions: bool,
+
+    /// The mouse belongs to player one; the other users always see it idle
+    /// at the origin.
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub press_mouse_left: bool,
+    pub press_mouse_right: bool,
+    pub tap_mouse_left: bool,
+    pub tap_mouse_right: bool,
+    pub release_mouse_left: bool,
+    pub release_mouse_right: bool,
+}
+
+impl Inputs {
+    /// Combines every gamepad into one, for shared screens any player can
+    /// drive. The mouse stays with player one.
+    pub fn merge(inputs: &[Inputs; 4]) -> Inputs {
+        let mut merged = inputs[0];
+        for other in &inputs[1..] {
+            merged.press_x |= other.press_x;
+            merged.press_z |= other.press_z;
+            merged.press_left |= other.press_left;
+            merged.press_right |= other.press_right;
+            merged.press_up |= other.press_up;
+            merged.press_down |= other.press_down;
+            merged.tap_x |= other.tap_x;
+            merged.tap_z |= other.tap_z;
+            merged.tap_left |= other.tap_left;
+            merged.tap_right |= other.tap_right;
+            merged.tap_up |= other.tap_up;
+            merged.tap_down |= other.tap_down;
+            merged.long_x |= other.long_x;
+            merged.long_z |= other.long_z;
+            merged.long_left |= other.long_left;
+            merged.long_right |= other.long_right;
+            merged.long_up |= other.long_up;
+            merged.long_down |= other.long_down;
+            merged.double_x |= other.double_x;
+            merged.double_z |= other.double_z;
+            merged.double_left |= other.double_left;
+            merged.double_right |= other.double_right;
+            merged.double_up |= other.double_up;
+            merged.double_down |= other.double_down;
+            merged.gamepad |= other.gamepad;
+            merged.gamepad_prev |= other.gamepad_prev;
+        }
+        merged
+    }
+
+    /// Whether any gamepad button went down this frame.
+    pub fn any_tap(&self) -> bool {
+        self.gamepad & !self.gamepad_prev != 0
+    }
+
+    /// Whether any gamepad button is held.
+    pub fn any_press(&self) -> bool {
+        self.gamepad != 0
+    }
+
+    pub fn mouse(&self) -> (i32, i32) {
+        (self.mouse_x, self.mouse_y)
+    }
+
+    /// Whether any button is held or was just let go, mouse included.
+    pub fn active(&self) -> bool {
+        self.gamepad != 0
+            || self.gamepad_prev != 0
+            || self.press_mouse_left
+            || self.press_mouse_right
+            || self.release_mouse_left
+            || self.release_mouse_right
+    }
+
+    pub fn mouse_in(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+        point_in(self.mouse(), x, y, width, height)
+    }
+
+    /// Whether the left button was clicked this frame inside the rectangle.
+    pub fn clicked(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+        self.tap_mouse_left && self.mouse_in(x, y, width, height)
+    }
+}
+
+/// Hit test for mouse targets, used by `draw` with the mouse position
+/// remembered from the last update to highlight whatever is hovered.
+pub fn point_in((px, py): (i32, i32), x: i32, y: i32, width: i32, height: i32) -> bool {
+    (x..x + width).contains(&px) && (y..y + height).contains(&py)
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -37,21 +322,38 @@ impl User {
         }
     }
 
-    pub fn gamepad_prev(&self) -> u8 {
-        unsafe {
-            match self {
-                Self::One => GAMEPAD1_PREV,
-                Self::Two => GAMEPAD2_PREV,
-                Self::Three => GAMEPAD3_PREV,
-                Self::Four => GAMEPAD4_PREV,
-            }
+    fn index(&self) -> usize {
+        match self {
+            Self::One => 0,
+            Self::Two => 1,
+            Self::Three => 2,
+            Self::Four => 3,
+        }
+    }
+
+    /// Mouse position and buttons this frame and last, or all zeroes for
+    /// anyone but player one and during netplay.
+    pub fn mouse(&self, buttons_prev: u8) -> (i32, i32, u8, u8) {
+        match self {
+            Self::One if !netplay_active() => unsafe { (*MOUSE_X as i32, *MOUSE_Y as i32, *MOUSE_BUTTONS, buttons_prev) },
+            _ => (0, 0, 0, 0),
         }
     }
 
-    pub fn get_inputs(&self) -> Inputs {
-        let gamepad = self.gamepad();
-        let prev = self.gamepad_prev();
+    /// This frame's inputs, given the gamepad and mouse buttons as they
+    /// were last frame, player one's `controls` and this user's gesture
+    /// timers.
+    pub fn get_inputs(&self, gamepad_prev: u8, mouse_buttons_prev: u8, controls: &Controls, gestures: &mut Gestures) -> Inputs {
+        let controls = if netplay_active() { Controls::default() } else { *controls };
+        let raw_gamepad = self.gamepad();
+        let raw_prev = gamepad_prev;
+        let gamepad = controls.remap(raw_gamepad);
+        let prev = controls.remap(raw_prev);
         let pressed_this_frame = gamepad & (gamepad ^ prev);
+        let (long, double) = gestures.update(gamepad, pressed_this_frame, &controls.timings[self.index()]);
+        let (mouse_x, mouse_y, buttons, buttons_prev) = self.mouse(mouse_buttons_prev);
+        let clicked_this_frame = buttons & (buttons ^ buttons_prev);
+        let released_this_frame = buttons_prev & (buttons ^ buttons_prev);
         Inputs {
             press_x: gamepad & BUTTON_1 != 0,
             press_z: gamepad & BUTTON_2 != 0,
@@ -66,13 +368,174 @@ impl User {
             tap_right: pressed_this_frame & BUTTON_RIGHT != 0,
             tap_up: pressed_this_frame & BUTTON_UP != 0,
             tap_down: pressed_this_frame & BUTTON_DOWN != 0,
+
+            long_x: long[0],
+            long_z: long[1],
+            long_left: long[2],
+            long_right: long[3],
+            long_up: long[4],
+            long_down: long[5],
+
+            double_x: double[0],
+            double_z: double[1],
+            double_left: double[2],
+            double_right: double[3],
+            double_up: double[4],
+            double_down: double[5],
+
+            gamepad: raw_gamepad,
+            gamepad_prev: raw_prev,
+
+            direct_actions: controls.direct_actions,
+
+            mouse_x,
+            mouse_y,
+            press_mouse_left: buttons & MOUSE_LEFT != 0,
+            press_mouse_right: buttons & MOUSE_RIGHT != 0,
+            tap_mouse_left: clicked_this_frame & MOUSE_LEFT != 0,
+            tap_mouse_right: clicked_this_frame & MOUSE_RIGHT != 0,
+            release_mouse_left: released_this_frame & MOUSE_LEFT != 0,
+            release_mouse_right: released_this_frame & MOUSE_RIGHT != 0,
+        }
+    }
+}
+
+/// Something a game can't carry on from. It goes back to the menu with
+/// the bank as it stood, and any bet on the table returned.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameError {
+    /// A card was needed and none were left to deal.
+    OutOfCards,
+    /// The game reached a state its rules shouldn't allow.
+    BadState,
+}
+
+impl GameError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::OutOfCards => "Out of cards",
+            Self::BadState => "Game error",
         }
     }
 }
 
+/// What a game tells whoever runs it at the end of a frame, one thing a
+/// frame.
+pub enum GameEvent<State> {
+    /// Nothing to say, play goes on.
+    Continue,
+    /// The game is over and hands back the shared state.
+    Exit { state: State },
+    /// The game is over, and the game named `game` on the menu picks up in
+    /// its place.
+    SwitchTo { state: State, game: &'static str },
+    /// Play goes on, but `peek_state` is worth writing to disk now rather
+    /// than only once the game is left.
+    RequestSave,
+    /// A fanfare for a big win, played over the music.
+    PlaySfx(&'static Track),
+    /// The player deleted their save data: the game is over and the save
+    /// slot in use is to be wiped.
+    EraseSave,
+}
 
 pub trait Model<State> {
-    fn update(&mut self, inputs: [Inputs; 4]) -> Option<State>;
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<State>;
     fn draw(&self);
     fn share_state(&mut self, state: State);
-}
\ No newline at end of file
+    /// The game's current view of the shared state, for overlays drawn by
+    /// the menu while the game is running.
+    fn peek_state(&self) -> Option<State> {
+        None
+    }
+    /// Whether chips are on the table, which keeps the game from being
+    /// closed for inactivity.
+    fn bet_live(&self) -> bool {
+        false
+    }
+    /// Serialized progress through the current round, autosaved whenever it
+    /// changes so the round can be resumed after a power loss.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Picks up from a `snapshot`, returning false if it couldn't be read.
+    fn restore(&mut self, _snapshot: &[u8]) -> bool {
+        false
+    }
+    /// Whether the picture can change without any input, from animation or
+    /// the game moving along on its own. Games that only change on a key
+    /// press return false so the menu can keep the last frame on screen.
+    fn needs_redraw(&self) -> bool {
+        true
+    }
+    /// A big moment since the last call, celebrated with particles drawn
+    /// over the game.
+    fn take_burst(&mut self) -> Option<Burst> {
+        None
+    }
+    /// A shake or flash since the last call, applied to the whole screen.
+    fn take_screen_effect(&mut self) -> Option<ScreenEffect> {
+        None
+    }
+    /// Why the game gave up, if it just did. It exits from `update` as it
+    /// goes, so the menu only has to say what happened.
+    fn take_error(&mut self) -> Option<GameError> {
+        None
+    }
+    /// What the game is doing, by name, for the debug overlay.
+    fn state_name(&self) -> &'static str {
+        ""
+    }
+    /// Cards left to deal, for the debug overlay.
+    fn cards_left(&self) -> Option<usize> {
+        None
+    }
+    /// The menu has handed over to the game, after sharing its state and
+    /// restoring any snapshot, and before the first `update`.
+    fn on_enter(&mut self) {}
+    /// The menu is taking back control, after the game's last `update`
+    /// and before it's dropped.
+    fn on_exit(&mut self) {}
+    /// The pause menu has opened over the game, which gets no `update`
+    /// until `on_resume` or `on_exit`.
+    fn on_pause(&mut self) {}
+    /// The pause menu has closed and play carries on.
+    fn on_resume(&mut self) {}
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames until a button held from the first frame fires its long press.
+    fn frames_to_long_press(timings: &[GestureTiming; 6]) -> u32 {
+        let mut gestures = Gestures::new();
+        let mut pressed = BUTTON_2;
+        (1..).find(|_| {
+            let (long, _) = gestures.update(BUTTON_2, pressed, timings);
+            pressed = 0;
+            long[1]
+        }).unwrap()
+    }
+
+    #[test]
+    fn each_button_keeps_its_own_timing() {
+        let mut timings = [GestureTiming::Normal; 6];
+        assert_eq!(frames_to_long_press(&timings), timers::secs(0.5));
+        timings[1] = GestureTiming::Quick;
+        assert_eq!(frames_to_long_press(&timings), timers::secs(0.3));
+        timings[0] = GestureTiming::Relaxed;
+        assert_eq!(frames_to_long_press(&timings), timers::secs(0.3));
+
+        // a second tap 20 frames on is too slow for normal, in time for relaxed
+        let double_after = |timing| {
+            let mut gestures = Gestures::new();
+            gestures.update(BUTTON_1, BUTTON_1, &[timing; 6]);
+            for _ in 0..19 {
+                gestures.update(0, 0, &[timing; 6]);
+            }
+            gestures.update(BUTTON_1, BUTTON_1, &[timing; 6]).1[0]
+        };
+        assert!(!double_after(GestureTiming::Normal));
+        assert!(double_after(GestureTiming::Relaxed));
+    }
+}
@@ -0,0 +1,62 @@
+//! Chip amounts as text. Each comes back in a `FixedString`, so the HUDs
+//! that draw them every frame never go to the heap.
+
+use core::fmt::Write;
+use crate::fixed::FixedString;
+
+/// Long enough for any `u32` amount with its sign, dollar sign and
+/// separators.
+pub type Amount = FixedString<16>;
+
+/// Chip amounts with thousands separators, like `$12,500`.
+pub fn chips(amount: u32) -> Amount {
+    let mut digits = FixedString::<10>::new();
+    let _ = write!(digits, "{}", amount);
+    let mut out = Amount::new();
+    let _ = out.write_char('$');
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            let _ = out.write_char(',');
+        }
+        let _ = out.write_char(digit);
+    }
+    out
+}
+
+/// A win or loss with its sign, like `+$1,200` or `-$50`.
+pub fn signed(amount: i64) -> Amount {
+    let sign = if amount < 0 { "-" } else { "+" };
+    Amount::from_args(format_args!("{}{}", sign, chips(amount.unsigned_abs().min(u32::MAX as u64) as u32)))
+}
+
+/// At most six characters for tight spots: `$950`, `$12.5K`, `$3M`. The
+/// decimal is dropped once there are three digits in front of it.
+pub fn compact(amount: u32) -> Amount {
+    let (whole, tenths, suffix) = match amount {
+        0..=999 => return Amount::from_args(format_args!("${}", amount)),
+        1_000..=999_999 => (amount / 1_000, amount % 1_000 / 100, "K"),
+        _ => (amount / 1_000_000, amount % 1_000_000 / 100_000, "M"),
+    };
+    if tenths == 0 || whole >= 100 {
+        Amount::from_args(format_args!("${}{}", whole, suffix))
+    } else {
+        Amount::from_args(format_args!("${}.{}{}", whole, tenths, suffix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_fit_without_the_heap() {
+        assert_eq!(chips(0), "$0");
+        assert_eq!(chips(12_500), "$12,500");
+        assert_eq!(chips(u32::MAX), "$4,294,967,295");
+        assert_eq!(signed(-(u32::MAX as i64)), "-$4,294,967,295");
+        assert_eq!(signed(1_200), "+$1,200");
+        assert_eq!(compact(12_500), "$12.5K");
+        assert_eq!(compact(3_000_000), "$3M");
+        assert_eq!(fixed_format!("Total Bet: {}", chips(250)), "Total Bet: $250");
+    }
+}
@@ -0,0 +1,258 @@
+//! Background music, written as data so new tunes don't need new code.
+//!
+//! A `Track` is a set of patterns, each a short run of notes stored two
+//! bytes a note:
+//!
+//! - the MIDI pitch, 60 being middle C, or 0 for a rest
+//! - how many ticks the note lasts, `frames_per_tick` frames each
+//!
+//! Each `Voice` plays those patterns in the order it lists them, on the
+//! channel and with the instrument its `TONE_*` flags pick, and starts its
+//! order over when it runs out. A pattern can be listed as often as it's
+//! needed and shared between voices. Voices loop on their own, so they
+//! should add up to the same number of ticks.
+//!
+//! A sting is a track played once over whatever is on, for a big win. The
+//! background keeps time underneath but is ducked, and silenced outright on
+//! any channel the sting needs.
+
+use crate::{sfx::{Tone, Volume}, timers, wasm4::*};
+
+/// Frames a track takes to fade out before the next one starts.
+const FADE_FRAMES: u32 = timers::secs(1.0);
+/// Most voices a track can have, one per channel.
+const MAX_VOICES: usize = 4;
+/// Share of its volume the background keeps while a sting plays.
+const DUCK_PERCENT: u32 = 25;
+
+/// Frequencies of the octave from middle C, in Hz.
+const OCTAVE: [u16; 12] = [262, 277, 294, 311, 330, 349, 370, 392, 415, 440, 466, 494];
+
+/// Frequency of a MIDI pitch, in Hz.
+pub fn frequency(pitch: u8) -> u16 {
+    let (octave, step) = (pitch as i32 / 12 - 5, pitch as usize % 12);
+    if octave < 0 { OCTAVE[step] >> -octave } else { OCTAVE[step] << octave }
+}
+
+/// One part of a track and the sound it's played with.
+pub struct Voice {
+    /// Channel, duty cycle mode and pan, from the `TONE_*` constants.
+    /// Sound effects play on the first pulse channel, so music should keep
+    /// to the others.
+    pub flags: u32,
+    /// Volume at full level, from 0 to 100.
+    pub volume: u8,
+    /// Indexes into the track's patterns, played in turn.
+    pub order: &'static [u8],
+}
+
+pub struct Track {
+    pub frames_per_tick: u32,
+    pub patterns: &'static [&'static [u8]],
+    pub voices: &'static [Voice],
+}
+
+/// The title theme, a bright walk in C, played from slot select through
+/// the menu.
+pub const THEME: Track = Track {
+    frames_per_tick: 7,
+    patterns: &[
+        &[67, 2, 72, 2, 76, 2, 79, 4, 76, 2, 79, 2, 81, 2],
+        &[79, 3, 77, 1, 76, 2, 74, 2, 72, 4, 0, 4],
+        &[69, 2, 72, 2, 77, 2, 81, 4, 79, 2, 77, 2, 76, 2],
+        &[74, 2, 76, 2, 77, 2, 74, 2, 72, 6, 0, 2],
+        &[48, 2, 55, 2, 52, 2, 55, 2],
+        &[53, 2, 60, 2, 57, 2, 60, 2],
+        &[43, 2, 50, 2, 47, 2, 50, 2],
+    ],
+    voices: &[
+        Voice { flags: TONE_PULSE2 | TONE_MODE2, volume: 12, order: &[0, 1, 2, 3] },
+        Voice { flags: TONE_TRIANGLE, volume: 30, order: &[4, 4, 6, 4, 5, 5, 6, 4] },
+    ],
+};
+
+/// Something slower in A minor to play cards to.
+pub const TABLE: Track = Track {
+    frames_per_tick: 10,
+    patterns: &[
+        &[69, 4, 72, 2, 71, 2, 69, 2, 64, 4, 0, 2],
+        &[65, 4, 69, 2, 67, 2, 64, 6, 0, 2],
+        &[45, 2, 52, 2, 57, 2, 52, 2],
+        &[41, 2, 48, 2, 53, 2, 48, 2],
+        &[40, 2, 47, 2, 52, 2, 47, 2],
+    ],
+    voices: &[
+        Voice { flags: TONE_PULSE2 | TONE_MODE3, volume: 12, order: &[0, 1] },
+        Voice { flags: TONE_TRIANGLE, volume: 30, order: &[2, 2, 3, 4] },
+    ],
+};
+
+/// A short run up to a held chord, for a natural.
+pub const FANFARE: Track = Track {
+    frames_per_tick: 5,
+    patterns: &[
+        &[72, 2, 76, 2, 79, 2, 84, 6, 79, 2, 84, 8],
+        &[48, 6, 55, 6, 60, 10],
+    ],
+    voices: &[
+        Voice { flags: TONE_PULSE2 | TONE_MODE3, volume: 30, order: &[0] },
+        Voice { flags: TONE_TRIANGLE, volume: 40, order: &[1] },
+    ],
+};
+
+/// A longer flourish for the rarest wins.
+pub const JACKPOT: Track = Track {
+    frames_per_tick: 5,
+    patterns: &[
+        &[67, 1, 72, 1, 76, 1, 79, 1, 84, 3, 0, 1, 84, 1, 86, 1, 88, 2, 91, 8],
+        &[88, 2, 91, 2, 96, 12],
+        &[48, 4, 52, 4, 55, 4, 60, 8],
+        &[55, 4, 60, 12],
+    ],
+    voices: &[
+        Voice { flags: TONE_PULSE2 | TONE_MODE2, volume: 30, order: &[0, 1] },
+        Voice { flags: TONE_TRIANGLE, volume: 40, order: &[2, 3] },
+    ],
+};
+
+/// Where a voice is in its order, and frames until its next note is due.
+#[derive(Copy, Clone)]
+struct Cursor {
+    order: usize,
+    note: usize,
+    frames_left: u32,
+}
+
+impl Cursor {
+    const START: Cursor = Cursor { order: 0, note: 0, frames_left: 0 };
+}
+
+/// Background music, stepped once a frame. Asking for a different track
+/// fades out the one playing first.
+pub struct Music {
+    track: Option<&'static Track>,
+    /// What plays once the fade finishes, if anything.
+    next: Option<&'static Track>,
+    /// Frames of fade left, while fading.
+    fade: Option<u32>,
+    cursors: [Cursor; MAX_VOICES],
+    /// The last track switched away from and where it was, so going back to
+    /// it carries on instead of starting over.
+    left: Option<(&'static Track, [Cursor; MAX_VOICES])>,
+    /// A sting playing over the top, and where it is.
+    sting: Option<(&'static Track, [Cursor; MAX_VOICES])>,
+}
+
+impl Music {
+    pub const fn new() -> Self {
+        Self { track: None, next: None, fade: None, cursors: [Cursor::START; MAX_VOICES], left: None, sting: None }
+    }
+
+    fn is(track: Option<&'static Track>, other: Option<&'static Track>) -> bool {
+        match (track, other) {
+            (Some(track), Some(other)) => core::ptr::eq(track, other),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Switches to `track`, or to silence with `None`. Asking again for what
+    /// is already playing, or already coming up, changes nothing.
+    pub fn play(&mut self, track: Option<&'static Track>) {
+        let upcoming = if self.fade.is_some() { self.next } else { self.track };
+        if Self::is(upcoming, track) {
+            return
+        }
+        match self.track {
+            Some(_) => {
+                self.next = track;
+                self.fade.get_or_insert(FADE_FRAMES);
+            }
+            None => self.start(track),
+        }
+    }
+
+    fn start(&mut self, track: Option<&'static Track>) {
+        let left = self.left.take();
+        if let Some(playing) = self.track {
+            self.left = Some((playing, self.cursors));
+        }
+        self.cursors = match left {
+            Some((track_left, cursors)) if Self::is(Some(track_left), track) => cursors,
+            _ => [Cursor::START; MAX_VOICES],
+        };
+        self.track = track;
+        self.next = None;
+        self.fade = None;
+    }
+
+    /// Plays `track` once over the music, starting it over if it's already
+    /// playing.
+    pub fn sting(&mut self, track: &'static Track) {
+        self.sting = Some((track, [Cursor::START; MAX_VOICES]));
+    }
+
+    /// Steps the music a frame, played at `volume` from settings.
+    pub fn update(&mut self, volume: Volume) {
+        match self.fade {
+            Some(0 | 1) => self.start(self.next),
+            Some(fade) => self.fade = Some(fade - 1),
+            None => {}
+        }
+        // channels taken by the sting, as a bit per channel
+        let mut taken = 0;
+        if let Some((sting, cursors)) = &mut self.sting {
+            Self::step(sting, cursors, 100, 0, false, volume);
+            taken = sting.voices.iter().fold(0, |taken, voice| taken | 1 << (voice.flags & 3));
+            let done = sting.voices.iter().zip(cursors.iter()).all(|(voice, cursor)| cursor.order >= voice.order.len() && cursor.frames_left == 0);
+            if done {
+                self.sting = None;
+            }
+        }
+        let Some(track) = self.track else { return };
+        let mut level = 100 * self.fade.map_or(FADE_FRAMES, |fade| fade) / FADE_FRAMES;
+        if taken != 0 {
+            level = level * DUCK_PERCENT / 100;
+        }
+        Self::step(track, &mut self.cursors, level, taken, true, volume);
+    }
+
+    /// Moves each voice along a frame, sounding any note that comes due at
+    /// `level` percent of its volume unless its channel is in `muted`.
+    fn step(track: &Track, cursors: &mut [Cursor; MAX_VOICES], level: u32, muted: u32, looping: bool, volume: Volume) {
+        for (voice, cursor) in track.voices.iter().zip(cursors) {
+            if cursor.frames_left > 0 {
+                cursor.frames_left -= 1;
+                continue
+            }
+            let Some(&[pitch, ticks]) = Self::note(track, voice, cursor, looping) else { continue };
+            let frames = (ticks as u32 * track.frames_per_tick).max(1);
+            if pitch != 0 && muted & 1 << (voice.flags & 3) == 0 {
+                let loudness = (voice.volume as u32 * level / 100) as u8;
+                // a frame short so repeated notes are heard apart
+                let sustain = (frames - 1).min(255) as u8;
+                Tone { frequency: frequency(pitch), sustain, volume: loudness, flags: voice.flags, ..Tone::SILENT }.at(volume).play();
+            }
+            cursor.frames_left = frames - 1;
+        }
+    }
+
+    /// Takes the voice's next note, moving on to its next pattern at the end
+    /// of this one. `None` when the voice has no notes at all, or has played
+    /// them all when not `looping`.
+    fn note(track: &Track, voice: &Voice, cursor: &mut Cursor, looping: bool) -> Option<&'static [u8; 2]> {
+        for _ in 0..=voice.order.len() {
+            let pattern = voice.order.get(cursor.order).and_then(|index| track.patterns.get(*index as usize))?;
+            if let Some(note) = pattern.get(cursor.note * 2..cursor.note * 2 + 2) {
+                cursor.note += 1;
+                return note.try_into().ok()
+            }
+            cursor.order += 1;
+            if looping {
+                cursor.order %= voice.order.len();
+            }
+            cursor.note = 0;
+        }
+        None
+    }
+}
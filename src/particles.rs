@@ -0,0 +1,156 @@
+use core::f32::consts::TAU;
+
+use crate::{ui::Display, wasm4::*};
+
+/// Most particles alive at once. A burst that doesn't fit is cut short
+/// rather than pushing out particles already on screen.
+const CAPACITY: usize = 64;
+/// Pulls falling particles down a little more every frame.
+const GRAVITY: f32 = 0.08;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Effect {
+    /// Red, black and yellow flecks thrown up and fluttering down.
+    Confetti,
+    /// Chips flying out in every direction.
+    Chips,
+    /// Twinkling stars that hang where they appear.
+    Sparkles,
+}
+
+impl Effect {
+    fn count(&self) -> usize {
+        match self {
+            Self::Confetti => 24,
+            Self::Chips => 12,
+            Self::Sparkles => 10,
+        }
+    }
+
+    fn frames(&self) -> u8 {
+        match self {
+            Self::Confetti => 90,
+            Self::Chips => 60,
+            Self::Sparkles => 40,
+        }
+    }
+}
+
+/// A celebration a game asks for, centred on a point of the screen.
+#[derive(Copy, Clone)]
+pub struct Burst {
+    pub effect: Effect,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Copy, Clone)]
+struct Particle {
+    effect: Effect,
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+    color: u16,
+    /// Zero marks a free slot in the pool.
+    frames_left: u8,
+}
+
+const FREE: Particle = Particle {
+    effect: Effect::Sparkles,
+    x: 0.0,
+    y: 0.0,
+    dx: 0.0,
+    dy: 0.0,
+    color: 0,
+    frames_left: 0,
+};
+
+/// Every particle on screen, drawn over whatever else is showing.
+pub struct Particles {
+    pool: [Particle; CAPACITY],
+    seed: u32,
+}
+
+impl Particles {
+    pub const fn new() -> Self {
+        Self {
+            pool: [FREE; CAPACITY],
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    /// A number in `0..1`. Only where confetti lands depends on it, so a
+    /// xorshift of our own keeps game rngs out of it.
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+
+    pub fn spawn(&mut self, burst: Burst) {
+        let (x, y) = (burst.x as f32, burst.y as f32);
+        for index in 0..burst.effect.count() {
+            let Some(slot) = self.pool.iter().position(|particle| particle.frames_left == 0) else { return };
+            let angle = self.random() * TAU;
+            let speed = 0.5 + self.random() * 1.5;
+            let (x, y, dx, dy, color) = match burst.effect {
+                Effect::Confetti => (x, y, (self.random() - 0.5) * 3.0, -1.5 - 2.0 * self.random(), [0x2, 0x3, 0x4][index % 3]),
+                Effect::Chips => (x, y, libm::cosf(angle) * speed, libm::sinf(angle) * speed - 1.0, 0x34),
+                Effect::Sparkles => {
+                    let (x, y) = (x + (self.random() - 0.5) * 60.0, y + (self.random() - 0.5) * 16.0);
+                    (x, y, libm::cosf(angle) * 0.2, libm::sinf(angle) * 0.2, 0x4)
+                }
+            };
+            // stagger the ends so a burst fizzles out instead of vanishing
+            let frames = burst.effect.frames();
+            let frames_left = frames - (self.random() * (frames / 4) as f32) as u8;
+            self.pool[slot] = Particle { effect: burst.effect, x, y, dx, dy, color, frames_left };
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.pool.iter().any(|particle| particle.frames_left > 0)
+    }
+
+    pub fn update(&mut self) {
+        for particle in self.pool.iter_mut().filter(|particle| particle.frames_left > 0) {
+            particle.x += particle.dx;
+            particle.y += particle.dy;
+            match particle.effect {
+                Effect::Confetti => {
+                    particle.dy += GRAVITY / 2.0;
+                    particle.dx *= 0.97;
+                }
+                Effect::Chips => particle.dy += GRAVITY,
+                Effect::Sparkles => {}
+            }
+            particle.frames_left -= 1;
+            if particle.y > SCREEN_SIZE as f32 {
+                particle.frames_left = 0;
+            }
+        }
+    }
+
+    pub fn draw(&self, display: Display) {
+        for particle in self.pool.iter().filter(|particle| particle.frames_left > 0) {
+            let (x, y) = (particle.x as i32, particle.y as i32);
+            unsafe { *DRAW_COLORS = particle.color; }
+            match particle.effect {
+                // turning over as it falls, edge on every few frames, and
+                // more slowly in high contrast mode
+                Effect::Confetti => {
+                    let period = if display.high_contrast { 12 } else { 4 };
+                    rect(x, y, if particle.frames_left / period % 2 == 0 { 2 } else { 1 }, 2)
+                }
+                Effect::Chips => oval(x - 2, y - 2, 5, 5),
+                Effect::Sparkles => {
+                    let size = 1 + particle.frames_left as i32 / 16;
+                    hline(x - size, y, 2 * size as u32 + 1);
+                    vline(x, y - size, 2 * size as u32 + 1);
+                }
+            }
+        }
+    }
+}
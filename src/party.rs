@@ -0,0 +1,367 @@
+use crate::{
+    blackjack,
+    emotes::Emotes,
+    help::Page,
+    model::{local_player, GameEvent, Inputs, Model},
+    money,
+    rng::{GameRng, Rng},
+    timers,
+    turns::Turns,
+    wasm4::*,
+    BlackJack,
+    PlayerState,
+};
+
+const PARTY_ROUNDS: u32 = 5;
+const STARTING_STACK: u32 = 500;
+/// Stake for a spectator's side bet on the turn player's hand, paid 1:1.
+const SIDE_BET: u32 = 10;
+/// A player who doesn't touch their gamepad for this long is skipped from
+/// their next turn until they press something.
+const SIT_OUT_FRAMES: u32 = timers::secs(20.0);
+
+/// A game a turn can land on: its name, its constructor, which takes a
+/// random seed, and its rules. Turns hold the table by value, so every
+/// entry is a blackjack table for now.
+type PartyGame = (&'static str, fn(u64) -> BlackJack, &'static [Page]);
+
+const PARTY_GAMES: [PartyGame; 1] = [
+    ("Blackjack", BlackJack::new, &blackjack::RULES),
+];
+
+pub const RULES: [Page; 2] = [
+    Page {
+        title: "Casino Night",
+        diagram: None,
+        text: b"Players take turns playing single rounds of games picked at random, each starting with 500. After five rounds the richest player wins. Your own bank is never touched.",
+    },
+    Page {
+        title: "Side bets",
+        diagram: None,
+        text: b"While someone else plays, bet 10 on how their hand goes: \x80 if they'll win and \x81 if they'll lose. A push gives the bet back.",
+    },
+];
+
+// the turn's table is kept inline rather than boxed on every turn
+#[allow(clippy::large_enum_variant)]
+enum PartyPhase {
+    Setup,
+    Announce(usize),
+    Turn(BlackJack),
+    Results,
+}
+
+/// Casino Night: players take turns playing single rounds of randomly chosen
+/// games with a fixed starting stack. The richest player after the last round
+/// wins. The player's real bank is left untouched.
+pub struct PartyMode {
+    rng: Rng,
+    phase: PartyPhase,
+    turns: Turns,
+    player_state: PlayerState,
+    /// What each spectator bet on this turn: `Some(true)` for the turn
+    /// player winning, `Some(false)` for them losing.
+    side_bets: [Option<bool>; 4],
+    /// Side bets close once the turn player's cards are out.
+    side_bets_closed: bool,
+    idle_frames: [u32; 4],
+    sitting_out: [bool; 4],
+    /// Gamepads past `num_players` that pressed X, dealt in from the next
+    /// round.
+    joining: [bool; 4],
+    emotes: Emotes,
+}
+
+impl PartyMode {
+    pub fn new(random_seed: u64) -> Self {
+        Self {
+            rng: Rng::with_seed(random_seed),
+            phase: PartyPhase::Setup,
+            turns: Turns::new(STARTING_STACK),
+            player_state: PlayerState::default(),
+            side_bets: [None; 4],
+            side_bets_closed: false,
+            idle_frames: [0; 4],
+            sitting_out: [false; 4],
+            joining: [false; 4],
+            emotes: Emotes::new(),
+        }
+    }
+
+    fn advance_turn(&mut self) {
+        if self.turns.pass() {
+            // newcomers are only dealt in between rounds so every seat has
+            // played the same number of turns
+            for player in self.turns.num_players..4 {
+                if self.joining.iter().skip(player).any(|joining| *joining) {
+                    self.turns.num_players = player + 1;
+                    self.turns.banks[player] = STARTING_STACK;
+                    self.sitting_out[player] = !self.joining[player];
+                }
+            }
+            self.joining = [false; 4];
+        }
+    }
+
+    fn next_turn(&mut self) {
+        // skip anyone sitting out, unless everyone is
+        for _ in 0..self.turns.num_players {
+            self.advance_turn();
+            if !self.sitting_out[self.turns.turn] {
+                break
+            }
+        }
+        log!(Debug, "party", "seat {} up in round {}, banks {:?}", self.turns.turn, self.turns.round, self.turns.banks);
+        if self.turns.round >= PARTY_ROUNDS {
+            self.phase = PartyPhase::Results;
+        } else {
+            self.phase = PartyPhase::Announce(self.rng.u32(..PARTY_GAMES.len() as u32) as usize);
+        }
+    }
+
+    /// Tracks who has gone quiet and who wants in. Runs every frame once the
+    /// party has started.
+    fn track_attendance(&mut self, inputs: &[Inputs; 4]) {
+        for (player, player_inputs) in inputs.iter().enumerate() {
+            if player_inputs.any_press() {
+                self.idle_frames[player] = 0;
+                self.sitting_out[player] = false;
+            } else {
+                self.idle_frames[player] = self.idle_frames[player].saturating_add(1);
+            }
+            if player < self.turns.num_players {
+                if self.idle_frames[player] >= SIT_OUT_FRAMES {
+                    self.sitting_out[player] = true;
+                }
+            } else if player_inputs.tap_x {
+                self.joining[player] = true;
+            }
+        }
+    }
+
+    /// Pays out or collects every side bet once the turn player's bank has
+    /// gone from `before` to `after`. Pushes return the stake.
+    fn settle_side_bets(&mut self, before: u32, after: u32) {
+        for (player, bet) in self.side_bets.iter_mut().enumerate() {
+            match bet.take() {
+                Some(on_win) if after != before && on_win == (after > before) => self.turns.banks[player] += 2 * SIDE_BET,
+                Some(_) if after == before => self.turns.banks[player] += SIDE_BET,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Model<PlayerState> for PartyMode {
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<PlayerState> {
+        // setup and results are shared screens any gamepad can drive
+        let shared_inputs = Inputs::merge(&inputs);
+        if !matches!(self.phase, PartyPhase::Setup | PartyPhase::Results) {
+            self.track_attendance(&inputs);
+        }
+        if !matches!(self.phase, PartyPhase::Setup) {
+            // the turn player's d-pad belongs to the game while they play
+            let playing = matches!(self.phase, PartyPhase::Turn(_));
+            let free = [0, 1, 2, 3].map(|player| player < self.turns.num_players && !(playing && player == self.turns.turn));
+            self.emotes.update(&inputs, free);
+        }
+        match &mut self.phase {
+            PartyPhase::Setup => {
+                if shared_inputs.tap_up && self.turns.num_players < 4 {
+                    self.turns.num_players += 1;
+                }
+                if shared_inputs.tap_down && self.turns.num_players > 2 {
+                    self.turns.num_players -= 1;
+                }
+                if shared_inputs.tap_x {
+                    self.phase = PartyPhase::Announce(self.rng.u32(..PARTY_GAMES.len() as u32) as usize);
+                }
+                if shared_inputs.tap_z {
+                    return GameEvent::Exit { state: self.player_state }
+                }
+            }
+            PartyPhase::Announce(_) if self.sitting_out[self.turns.turn] && self.sitting_out.iter().take(self.turns.num_players).any(|out| !out) => {
+                self.next_turn();
+            }
+            PartyPhase::Announce(game_index) => {
+                if inputs[self.turns.turn].tap_x {
+                    let (_, func, _) = PARTY_GAMES[*game_index];
+                    let mut game = func(self.rng.new_seed());
+                    game.share_state(PlayerState {
+                        banks: self.turns.banks,
+                        seat: self.turns.turn,
+                        single_round: true,
+                        ..self.player_state
+                    });
+                    game.on_enter();
+                    self.side_bets_closed = false;
+                    self.phase = PartyPhase::Turn(game);
+                }
+            }
+            PartyPhase::Turn(game) => {
+                // the game only listens to the first gamepad, so hand it the
+                // inputs of whoever's turn it is
+                let mut turn_inputs = inputs;
+                turn_inputs.swap(0, self.turns.turn);
+                if game.bet_live() {
+                    self.side_bets_closed = true;
+                }
+                // everyone else can back or lay the turn player with X or Z
+                // until the cards come out
+                for (player, player_inputs) in inputs.iter().enumerate().take(self.turns.num_players) {
+                    let can_bet = player != self.turns.turn
+                        && !self.side_bets_closed
+                        && self.side_bets[player].is_none()
+                        && self.turns.banks[player] >= SIDE_BET;
+                    if can_bet && (player_inputs.tap_x || player_inputs.tap_z) {
+                        self.turns.banks[player] -= SIDE_BET;
+                        self.side_bets[player] = Some(player_inputs.tap_x);
+                    }
+                }
+                match game.update(turn_inputs) {
+                    GameEvent::Exit { state } | GameEvent::SwitchTo { state, .. } => {
+                        game.on_exit();
+                        let before = self.turns.banks[self.turns.turn];
+                        self.turns.banks[self.turns.turn] = state.bank();
+                        self.settle_side_bets(before, state.bank());
+                        self.next_turn();
+                    }
+                    GameEvent::PlaySfx(sting) => return GameEvent::PlaySfx(sting),
+                    // the party's banks are only kept once it's over
+                    GameEvent::Continue | GameEvent::RequestSave | GameEvent::EraseSave => {}
+                }
+            }
+            PartyPhase::Results => {
+                if shared_inputs.tap_x || shared_inputs.tap_z {
+                    let results = Some(self.turns.results(STARTING_STACK));
+                    return GameEvent::Exit { state: PlayerState { results, ..self.player_state } }
+                }
+            }
+        }
+        GameEvent::Continue
+    }
+
+    fn draw(&self) {
+        match &self.phase {
+            PartyPhase::Setup => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text("Casino Night", 32, 20);
+                text(fixed_format!("Players: {}", self.turns.num_players), 32, 50);
+                text(fixed_format!("Rounds: {}", PARTY_ROUNDS), 32, 60);
+                text(fixed_format!("Stack: {}", money::chips(STARTING_STACK)), 32, 70);
+                let t = b"\x86\x87: players";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 142);
+                }
+                let t = b"\x80: start \x81: exit";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            PartyPhase::Announce(game_index) => {
+                let (name, _, _) = PARTY_GAMES[*game_index];
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(fixed_format!("Round {}/{}", self.turns.round + 1, PARTY_ROUNDS), 10, 20);
+                if local_player() == Some(self.turns.turn) {
+                    text("Your turn!", 10, 50);
+                } else {
+                    text(fixed_format!("Player {}'s turn", self.turns.turn + 1), 10, 50);
+                }
+                self.player_state.looks[self.turns.turn].draw(140, 50);
+                text(fixed_format!("Game: {}", name), 10, 60);
+                text(fixed_format!("Chips: {}", money::chips(self.turns.banks[self.turns.turn])), 10, 70);
+                text(fixed_format!("Others bet {}:", money::chips(SIDE_BET)), 10, 90);
+                let t = b"\x80: win \x81: lose";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 10, 100);
+                }
+                let t = b"\x80: play";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            PartyPhase::Turn(game) => {
+                game.draw();
+                unsafe { *DRAW_COLORS = 0x31; }
+                for (player, bet) in self.side_bets.iter().enumerate() {
+                    if let Some(on_win) = bet {
+                        let side = if *on_win { "W" } else { "L" };
+                        text(fixed_format!("P{}{}", player + 1, side), 4 + 32 * player as i32, 131);
+                        self.player_state.looks[player].draw(28 + 32 * player as i32, 131);
+                    }
+                }
+            }
+            PartyPhase::Results => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text("Final Standings", 20, 20);
+                for (place, player) in self.turns.standings().into_iter().enumerate() {
+                    if place == 0 {
+                        unsafe { *DRAW_COLORS = 0x32; }
+                    } else {
+                        unsafe { *DRAW_COLORS = 0x02; }
+                    }
+                    text(
+                        fixed_format!("{}. P{} {}", place + 1, player + 1, money::chips(self.turns.banks[player])),
+                        20,
+                        (45 + 10 * place) as _
+                    );
+                    self.player_state.looks[player].draw(8, (45 + 10 * place) as _);
+                    if local_player() == Some(player) {
+                        text("you", 120, (45 + 10 * place) as _);
+                    }
+                }
+                unsafe { *DRAW_COLORS = 0x02; }
+                let t = b"\x80: done";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+        }
+        if !matches!(self.phase, PartyPhase::Setup) {
+            self.emotes.draw();
+        }
+    }
+
+    fn share_state(&mut self, state: PlayerState) {
+        self.player_state = state;
+        if let PartyPhase::Setup = self.phase {
+            self.turns.num_players = state.num_players().max(2);
+        }
+    }
+
+    fn state_name(&self) -> &'static str {
+        match &self.phase {
+            PartyPhase::Setup => "Setup",
+            PartyPhase::Announce(_) => "Announce",
+            PartyPhase::Turn(game) => game.state_name(),
+            PartyPhase::Results => "Results",
+        }
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        match &self.phase {
+            PartyPhase::Turn(game) => game.cards_left(),
+            _ => None,
+        }
+    }
+
+    // a turn in play is left, paused or picked up along with the party
+
+    fn on_exit(&mut self) {
+        if let PartyPhase::Turn(game) = &mut self.phase {
+            game.on_exit();
+        }
+    }
+
+    fn on_pause(&mut self) {
+        if let PartyPhase::Turn(game) = &mut self.phase {
+            game.on_pause();
+        }
+    }
+
+    fn on_resume(&mut self) {
+        if let PartyPhase::Turn(game) = &mut self.phase {
+            game.on_resume();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Passcodes that carry a bank between cartridges without a shared disk.
+//!
+//! A code is 13 characters of Crockford base32 (65 bits):
+//! - `version: 2 bits`
+//! - `nonce: 15 bits`, picked at random for each code shown, never 0
+//! - `bank: 32 bits`, scrambled so codes for nearby banks look unrelated
+//! - `check: 16 bits`, Fletcher-16 of the version, nonce and scrambled bank
+//!
+//! A slot remembers the nonces of the codes it last took in and refuses
+//! them a second time, so one code can't be imported over and over.
+
+use crate::save::fletcher16;
+
+pub const CODE_LEN: usize = 13;
+pub const ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const VERSION: u128 = 2;
+const SCRAMBLE: u32 = 0x5a3c_96e1;
+/// Nonces run from 1 up to this.
+pub const MAX_NONCE: u16 = 0x7fff;
+
+/// What a code carries.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Passcode {
+    pub bank: u32,
+    pub nonce: u16,
+}
+
+fn check(version: u128, nonce: u16, scrambled: u32) -> u16 {
+    let mut data = [0u8; 7];
+    data[0] = version as u8;
+    data[1..3].copy_from_slice(&nonce.to_le_bytes());
+    data[3..].copy_from_slice(&scrambled.to_le_bytes());
+    fletcher16(&data)
+}
+
+pub fn encode(passcode: Passcode) -> [u8; CODE_LEN] {
+    let Passcode { bank, nonce } = passcode;
+    let scrambled = bank.rotate_left(7) ^ SCRAMBLE;
+    let bits = VERSION << 63
+        | (nonce as u128) << 48
+        | (scrambled as u128) << 16
+        | check(VERSION, nonce, scrambled) as u128;
+    let mut code = [0u8; CODE_LEN];
+    for (index, c) in code.iter_mut().enumerate() {
+        let shift = 5 * (CODE_LEN - 1 - index);
+        *c = ALPHABET[((bits >> shift) & 0x1f) as usize];
+    }
+    code
+}
+
+/// Returns what `code` carries, or `None` if it was mistyped.
+pub fn decode(code: &[u8; CODE_LEN]) -> Option<Passcode> {
+    let mut bits = 0u128;
+    for c in code {
+        let value = ALPHABET.iter().position(|a| a == c)? as u128;
+        bits = bits << 5 | value;
+    }
+    let version = bits >> 63;
+    let nonce = (bits >> 48) as u16 & MAX_NONCE;
+    let scrambled = (bits >> 16) as u32;
+    if version != VERSION || nonce == 0 || bits as u16 != check(version, nonce, scrambled) {
+        return None
+    }
+    Some(Passcode { bank: (scrambled ^ SCRAMBLE).rotate_right(7), nonce })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BANKS: [u32; 5] = [0, 1, 100, u32::MAX - 1, u32::MAX];
+    const NONCES: [u16; 3] = [1, 0x1234, MAX_NONCE];
+
+    #[test]
+    fn codes_carry_the_bank_they_were_made_for() {
+        for bank in BANKS {
+            for nonce in NONCES {
+                let passcode = Passcode { bank, nonce };
+                assert_eq!(decode(&encode(passcode)), Some(passcode));
+            }
+        }
+        assert_eq!(decode(&encode(Passcode { bank: 100, nonce: 0 })), None, "nonces start at 1");
+    }
+
+    #[test]
+    fn codes_with_a_character_changed_are_refused() {
+        for bank in BANKS {
+            let code = encode(Passcode { bank, nonce: 77 });
+            for index in 0..CODE_LEN {
+                for &c in ALPHABET.iter().filter(|c| **c != code[index]) {
+                    let mut mistyped = code;
+                    mistyped[index] = c;
+                    assert_eq!(decode(&mistyped), None, "{} read from {:?}", bank, core::str::from_utf8(&mistyped));
+                }
+            }
+        }
+    }
+}
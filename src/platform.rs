@@ -0,0 +1,119 @@
+//! What the cart needs from the console it runs on. The functions in
+//! `wasm4` go through `Current`, which is the real console when built for
+//! WASM-4 and `headless::Headless` everywhere else, so the games can run
+//! under `cargo test` on the host with nothing else changed.
+//!
+//! The memory-mapped registers aren't part of the trait: `wasm4` points
+//! them at the console's memory or at the headless copy of it instead.
+
+/// The console's imports, with pointers and lengths turned into slices.
+pub trait Platform {
+    fn blit(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32);
+    #[allow(clippy::too_many_arguments)]
+    fn blit_sub(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, src_x: u32, src_y: u32, stride: u32, flags: u32);
+    fn line(x1: i32, y1: i32, x2: i32, y2: i32);
+    fn oval(x: i32, y: i32, width: u32, height: u32);
+    fn rect(x: i32, y: i32, width: u32, height: u32);
+    /// Draws text in the console's font, which has glyphs past ASCII for
+    /// the buttons, so it takes bytes rather than a `str`.
+    fn text(text: &[u8], x: i32, y: i32);
+    fn vline(x: i32, y: i32, len: u32);
+    fn hline(x: i32, y: i32, len: u32);
+    fn tone(frequency: u32, duration: u32, volume: u32, flags: u32);
+    /// Fills `dest` from storage, returning how many bytes were read.
+    fn diskr(dest: &mut [u8]) -> u32;
+    /// Stores `src`, returning how many bytes were written.
+    fn diskw(src: &[u8]) -> u32;
+    fn trace(text: &str);
+}
+
+#[cfg(target_family = "wasm")]
+pub type Current = Wasm4;
+#[cfg(not(target_family = "wasm"))]
+pub type Current = crate::headless::Headless;
+
+/// The WASM-4 console itself.
+#[cfg(target_family = "wasm")]
+pub struct Wasm4;
+
+#[cfg(target_family = "wasm")]
+impl Platform for Wasm4 {
+    fn blit(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32) {
+        unsafe { imports::blit(sprite.as_ptr(), x, y, width, height, flags) }
+    }
+
+    fn blit_sub(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, src_x: u32, src_y: u32, stride: u32, flags: u32) {
+        unsafe { imports::blit_sub(sprite.as_ptr(), x, y, width, height, src_x, src_y, stride, flags) }
+    }
+
+    fn line(x1: i32, y1: i32, x2: i32, y2: i32) {
+        unsafe { imports::line(x1, y1, x2, y2) }
+    }
+
+    fn oval(x: i32, y: i32, width: u32, height: u32) {
+        unsafe { imports::oval(x, y, width, height) }
+    }
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) {
+        unsafe { imports::rect(x, y, width, height) }
+    }
+
+    fn text(text: &[u8], x: i32, y: i32) {
+        unsafe { imports::text(text.as_ptr(), text.len(), x, y) }
+    }
+
+    fn vline(x: i32, y: i32, len: u32) {
+        unsafe { imports::vline(x, y, len) }
+    }
+
+    fn hline(x: i32, y: i32, len: u32) {
+        unsafe { imports::hline(x, y, len) }
+    }
+
+    fn tone(frequency: u32, duration: u32, volume: u32, flags: u32) {
+        unsafe { imports::tone(frequency, duration, volume, flags) }
+    }
+
+    fn diskr(dest: &mut [u8]) -> u32 {
+        unsafe { imports::diskr(dest.as_mut_ptr(), dest.len() as u32) }
+    }
+
+    fn diskw(src: &[u8]) -> u32 {
+        unsafe { imports::diskw(src.as_ptr(), src.len() as u32) }
+    }
+
+    fn trace(text: &str) {
+        unsafe { imports::trace(text.as_ptr(), text.len()) }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod imports {
+    extern "C" {
+        pub fn blit(sprite: *const u8, x: i32, y: i32, width: u32, height: u32, flags: u32);
+        #[link_name = "blitSub"]
+        pub fn blit_sub(
+            sprite: *const u8,
+            x: i32,
+            y: i32,
+            width: u32,
+            height: u32,
+            src_x: u32,
+            src_y: u32,
+            stride: u32,
+            flags: u32,
+        );
+        pub fn line(x1: i32, y1: i32, x2: i32, y2: i32);
+        pub fn oval(x: i32, y: i32, width: u32, height: u32);
+        pub fn rect(x: i32, y: i32, width: u32, height: u32);
+        #[link_name = "textUtf8"]
+        pub fn text(text: *const u8, length: usize, x: i32, y: i32);
+        pub fn vline(x: i32, y: i32, len: u32);
+        pub fn hline(x: i32, y: i32, len: u32);
+        pub fn tone(frequency: u32, duration: u32, volume: u32, flags: u32);
+        pub fn diskr(dest: *mut u8, size: u32) -> u32;
+        pub fn diskw(src: *const u8, size: u32) -> u32;
+        #[link_name = "traceUtf8"]
+        pub fn trace(trace: *const u8, length: usize);
+    }
+}
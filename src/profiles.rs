@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+use crate::{model::Inputs, money, sfx, stats::GameStats, ui::{Display, Keyboard, LETTERS}, wasm4::*};
+
+pub const MAX_PROFILES: usize = 4;
+pub const MAX_GAMES: usize = 8;
+pub const STARTING_BANK: u32 = 100;
+
+#[derive(Copy, Clone)]
+pub struct Profile {
+    pub initials: [u8; 3],
+    pub bank: u32,
+    /// Lifetime stats per game, kept separately for each odds mode.
+    pub stats: [[GameStats; 2]; MAX_GAMES],
+    /// One bit per `Achievement`.
+    pub achievements: u16,
+}
+
+impl Profile {
+    pub fn new(initials: [u8; 3]) -> Self {
+        Self {
+            initials,
+            bank: STARTING_BANK,
+            stats: [[GameStats::default(); 2]; MAX_GAMES],
+            achievements: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.initials).unwrap_or("???")
+    }
+}
+
+/// Startup screen where each gamepad picks (or creates) the profile it
+/// plays as. Player one confirming their pick closes the screen.
+pub struct ProfileScreen {
+    cursors: [usize; 4],
+    seats: [Option<usize>; 4],
+    entry: Option<(usize, Keyboard<3>)>,
+}
+
+impl ProfileScreen {
+    pub fn new() -> Self {
+        Self {
+            cursors: [0; 4],
+            seats: [None; 4],
+            entry: None,
+        }
+    }
+
+    /// Returns the profile chosen by each seat once player one has picked.
+    pub fn update(&mut self, inputs: [Inputs; 4], profiles: &mut Vec<Profile>) -> Option<[Option<usize>; 4]> {
+        if let Some((user, entry)) = &mut self.entry {
+            let user = *user;
+            if let Some(initials) = entry.update(&inputs[user]) {
+                if let Some(initials) = initials {
+                    profiles.push(Profile::new(initials));
+                    self.cursors[user] = profiles.len() - 1;
+                }
+                self.entry = None;
+            }
+            return None
+        }
+        // the extra row creates a new profile while there is room for one
+        let num_rows = if profiles.len() < MAX_PROFILES { profiles.len() + 1 } else { profiles.len() };
+        for (user, user_inputs) in inputs.iter().enumerate() {
+            let cursor = &mut self.cursors[user];
+            if user_inputs.tap_down {
+                *cursor = (*cursor + 1) % num_rows;
+            }
+            if user_inputs.tap_up {
+                *cursor = (*cursor + num_rows - 1) % num_rows;
+            }
+            if user_inputs.tap_down || user_inputs.tap_up {
+                sfx::cursor_move();
+            }
+            if user_inputs.tap_z && self.seats[user].is_some() {
+                sfx::cancel();
+                self.seats[user] = None;
+            }
+            if user_inputs.tap_x {
+                if *cursor == profiles.len() {
+                    sfx::confirm();
+                    self.entry = Some((user, Keyboard::new("Enter initials", LETTERS)));
+                    return None
+                }
+                if self.seats.contains(&Some(*cursor)) {
+                    sfx::error();
+                } else {
+                    sfx::confirm();
+                    self.seats[user] = Some(*cursor);
+                    if user == 0 {
+                        return Some(self.seats)
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, profiles: &[Profile], display: Display) {
+        if let Some((_, entry)) = &self.entry {
+            entry.draw(display);
+            return
+        }
+        unsafe { *DRAW_COLORS = 0x02; }
+        text("Who's playing?", 24, 10);
+        for row in 0..=profiles.len().min(MAX_PROFILES - 1) {
+            let y = 30 + 12 * row as i32;
+            for user in 0..4 {
+                if self.cursors[user] == row {
+                    unsafe { *DRAW_COLORS = 0x02; }
+                    text(fixed_format!("{}", user + 1), 4 + 8 * user as i32, y);
+                }
+            }
+            match profiles.get(row) {
+                Some(profile) => {
+                    let claimed = self.seats.contains(&Some(row));
+                    unsafe { *DRAW_COLORS = if claimed { 0x32 } else { 0x02 }; }
+                    text(fixed_format!("{} {}", profile.name(), money::chips(profile.bank)), 40, y);
+                }
+                None => {
+                    unsafe { *DRAW_COLORS = 0x02; }
+                    text("New profile", 40, y);
+                }
+            }
+        }
+        unsafe { *DRAW_COLORS = 0x02; }
+        let t = b"\x80: pick \x81: leave";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Randomness as the games see it. Game code asks a `GameRng` for numbers,
+//! shuffles and picks rather than calling fastrand itself, so tests can hand
+//! it a scripted generator and the generator behind `Rng` can be swapped
+//! here alone.
+
+use core::ops::RangeBounds;
+
+use crate::passcode::ALPHABET;
+
+/// The generator games keep between frames. Snapshots store its seed.
+pub type Rng = fastrand::Rng;
+
+pub trait GameRng {
+    /// A number in `range`, which mustn't be empty.
+    fn u32(&mut self, range: impl RangeBounds<u32>) -> u32;
+
+    /// A seed for a generator of its own, like a shoe that has to be
+    /// rebuilt from its seed later.
+    fn new_seed(&mut self) -> u64 {
+        (self.u32(..) as u64) << 32 | self.u32(..) as u64
+    }
+
+    /// Puts `items` in a random order.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.u32(..=index as u32) as usize);
+        }
+    }
+
+    /// One of `items`, or `None` if there aren't any.
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None
+        }
+        items.get(self.u32(..items.len() as u32) as usize)
+    }
+}
+
+// fastrand's own shuffle is kept so a seed deals the same shoe it always has
+impl GameRng for fastrand::Rng {
+    fn u32(&mut self, range: impl RangeBounds<u32>) -> u32 {
+        fastrand::Rng::u32(self, range)
+    }
+
+    fn new_seed(&mut self) -> u64 {
+        self.u64(..)
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        fastrand::Rng::shuffle(self, items)
+    }
+}
+
+/// Randomness gathered from the gamepads to seed games with. Only the
+/// gamepads and the frame count are the same on every netplay client, so
+/// the mouse stays out of it.
+///
+/// Every change of buttons is stirred in with how many frames the last
+/// state was held, so two players pressing the same keys seconds apart, or
+/// a frame apart, still launch different shuffles.
+#[derive(Default)]
+pub struct Entropy {
+    pool: u64,
+    gamepads: u32,
+    changed_frame: u64,
+}
+
+impl Entropy {
+    pub fn stir(&mut self, frame: u64, gamepads: [u8; 4]) {
+        let gamepads = u32::from_le_bytes(gamepads);
+        if gamepads == self.gamepads {
+            return
+        }
+        let held = frame - self.changed_frame;
+        self.pool = mix(self.pool ^ (gamepads as u64) << 32 ^ held);
+        self.gamepads = gamepads;
+        self.changed_frame = frame;
+    }
+
+    /// A seed for a game launched on `frame`.
+    pub fn seed(&self, frame: u64) -> u64 {
+        mix(self.pool ^ frame)
+    }
+}
+
+/// Characters in a seed code.
+pub const SEED_CODE_LEN: usize = 8;
+
+/// The low 40 bits of `bits` as a seed code in Crockford base32, for
+/// offering a random seed that can still be shared.
+pub fn seed_code(bits: u64) -> [u8; SEED_CODE_LEN] {
+    let mut code = [0u8; SEED_CODE_LEN];
+    for (index, c) in code.iter_mut().enumerate() {
+        let shift = 5 * (SEED_CODE_LEN - 1 - index);
+        *c = ALPHABET[(bits >> shift & 0x1f) as usize];
+    }
+    code
+}
+
+/// The shuffle seed a code stands for. Every code is a seed, so players
+/// can agree on any word they can spell, and the same code always deals
+/// the same shoe on every console.
+pub fn seed_from_code(code: &[u8; SEED_CODE_LEN]) -> u64 {
+    let bits = code.iter().fold(0u64, |bits, c| {
+        bits << 5 | ALPHABET.iter().position(|a| a == c).unwrap_or(0) as u64
+    });
+    // spread "00000001" and "00000002" over seeds nowhere near each other
+    mix(bits)
+}
+
+/// SplitMix64's finalizer, which spreads every input bit over the output.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ x >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ x >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ x >> 31
+}
+
+/// Hands out the numbers it was given, in order, for tests that need a
+/// particular roll. Panics on a number outside the range asked for, or
+/// once it runs out.
+#[cfg(test)]
+pub struct Scripted(alloc::collections::VecDeque<u32>);
+
+#[cfg(test)]
+impl Scripted {
+    pub fn new(numbers: &[u32]) -> Self {
+        Self(numbers.iter().copied().collect())
+    }
+}
+
+#[cfg(test)]
+impl GameRng for Scripted {
+    fn u32(&mut self, range: impl RangeBounds<u32>) -> u32 {
+        let number = self.0.pop_front().expect("the script ran out");
+        assert!(range.contains(&number), "scripted {} is out of range", number);
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_numbers_drive_picks_and_shuffles() {
+        assert_eq!(Scripted::new(&[2]).pick(&["a", "b", "c"]), Some(&"c"));
+        assert_eq!(Scripted::new(&[]).pick::<u8>(&[]), None);
+        // each step swaps the last unshuffled item with the one scripted
+        let mut items = [1, 2, 3, 4];
+        Scripted::new(&[0, 2, 1]).shuffle(&mut items);
+        assert_eq!(items, [4, 2, 3, 1]);
+        assert_eq!(Scripted::new(&[1, 2]).new_seed(), 1 << 32 | 2);
+    }
+
+    /// Stirs in X pressed on each of `frames` for a frame, and returns the
+    /// seed for a launch on frame 600.
+    fn seed_after_presses(frames: &[u64]) -> u64 {
+        let mut entropy = Entropy::default();
+        for frame in 0..600 {
+            let held = frames.contains(&frame);
+            entropy.stir(frame, [held as u8, 0, 0, 0]);
+        }
+        entropy.seed(600)
+    }
+
+    #[test]
+    fn press_timing_changes_the_seed() {
+        let seed = seed_after_presses(&[100, 200]);
+        assert_eq!(seed, seed_after_presses(&[100, 200]));
+        assert_ne!(seed, seed_after_presses(&[100, 201]));
+        assert_ne!(seed, seed_after_presses(&[101, 200]));
+        assert_ne!(seed, seed_after_presses(&[100]));
+    }
+
+    #[test]
+    fn seed_codes_stand_for_the_seed_they_were_made_from() {
+        let code = seed_code(0xabcd_0123_4567_89ef);
+        assert_eq!(&code, b"4D2PF2FF");
+        assert_eq!(seed_from_code(&code), seed_from_code(&seed_code(0x0123_4567_89ef)));
+        assert_ne!(seed_from_code(&code), seed_from_code(b"4D2PF2FG"));
+    }
+}
@@ -0,0 +1,67 @@
+/// Global odds mode, chosen in settings and applied to every game through
+/// its `TableRules`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum Difficulty {
+    /// True casino rules and house edges.
+    #[default]
+    Realistic,
+    /// Generous paytables and forgiving rules.
+    Arcade,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Realistic => "Realistic",
+            Self::Arcade => "Arcade",
+        }
+    }
+
+    /// Reads a difficulty stored as its `u8` repr, falling back to the
+    /// default for unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            1 => Self::Arcade,
+            _ => Self::Realistic,
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::Realistic => Self::Arcade,
+            Self::Arcade => Self::Realistic,
+        }
+    }
+
+    pub fn rules(&self) -> TableRules {
+        match self {
+            Self::Realistic => TableRules {
+                blackjack_payout: (6, 5),
+                double_any_two: false,
+                dealer_hits_soft_17: true,
+            },
+            Self::Arcade => TableRules {
+                blackjack_payout: (3, 2),
+                double_any_two: true,
+                dealer_hits_soft_17: false,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TableRules {
+    /// Winnings paid on a natural as `(numerator, denominator)` of the bet.
+    pub blackjack_payout: (u32, u32),
+    /// Allow doubling down on any two cards rather than only 10 or 11.
+    pub double_any_two: bool,
+    pub dealer_hits_soft_17: bool,
+}
+
+impl TableRules {
+    pub fn blackjack_winnings(&self, bet: u32) -> u32 {
+        let (numerator, denominator) = self.blackjack_payout;
+        bet * numerator / denominator
+    }
+}
@@ -0,0 +1,491 @@
+use alloc::vec::Vec;
+use crate::{felt::Felt, profiles::{Profile, MAX_GAMES, MAX_PROFILES}, rules::Difficulty, settings::{Settings, REDEEMED_CODES}, sfx::Volume, shuffle::{Realism, ShuffleStrategy}, stats::GameStats, timers::AnimationSpeed, wasm4::*};
+
+pub const NUM_SLOTS: usize = 3;
+const MAGIC: [u8; 2] = *b"CG";
+/// Bump this whenever fields are added to the payload, and gate reading the
+/// new fields on the slot's version in `SaveData::decode`.
+const CURRENT_VERSION: u8 = 15;
+const HEADER_SIZE: usize = 2 + 1 + 2 + 2;
+/// Each slot gets a fixed share of the 1KB disk so slots never move when the
+/// payload grows.
+const SLOT_SIZE: usize = 340;
+const DISK_SIZE: usize = NUM_SLOTS * SLOT_SIZE;
+/// Lifetime stats kept per profile. With every profile full, stats take
+/// `MAX_PROFILES * (3 + 5 * MAX_STAT_ENTRIES)` = 112 bytes, leaving room for
+/// the largest blackjack snapshot, 153 bytes, as a blackjack test checks.
+const MAX_STAT_ENTRIES: usize = 5;
+
+/// Everything kept in one save slot on the cartridge disk. The disk holds
+/// `NUM_SLOTS` of these back to back.
+///
+/// Slot header (little endian):
+/// - `magic: [u8; 2]`, always `b"CG"`
+/// - `version: u8`, the `CURRENT_VERSION` the slot was written with
+/// - `length: u16`, payload bytes in use
+/// - `checksum: u16`, Fletcher-16 of the payload
+///
+/// Payload, version 1:
+/// - `favorites: u32`
+/// - `play_frames: u32`
+/// - `num_profiles: u8`
+/// - `num_profiles` x (`initials: [u8; 3]`, `bank: u32`)
+///
+/// Added in version 2:
+/// - `resume_game: u8`, menu index of the game left mid-round, `0xff` if none
+/// - `resume_profile: u8`
+/// - `resume_len: u8`, so a longer snapshot refuses to save
+/// - `resume_len` bytes of the game's snapshot
+///
+/// Added in version 3:
+/// - `loss_limit: u32`, `0` if off
+/// - `difficulty: u8`
+///
+/// Added in version 4, for each profile in order:
+/// - `achievements: u16`
+/// - `num_stats: u8`, at most `MAX_STAT_ENTRIES`
+/// - `num_stats` x (`key: u8`, game index << 1 | difficulty, `rounds: u16`,
+///   `net: i16`)
+///
+/// Counters saturate at their stored width. A profile with more than
+/// `MAX_STAT_ENTRIES` games played only keeps its most played entries.
+///
+/// Added in version 5:
+/// - `controls: u8`, bit 0 swaps X and Z, bit 1 picks direct actions, bit 2
+///   mirrors the d-pad
+///
+/// Added in version 6:
+/// - `idle_minutes: u8`, `0` if off
+///
+/// Added in version 7:
+/// - `felt: u8`
+///
+/// Added in version 8:
+/// - `display: u8`, bit 0 turns on colorblind mode and bit 1 high contrast
+///
+/// Added in version 9:
+/// - `audio: u8`, the music volume in bits 0-1 and sound effects in bits 2-3
+///
+/// Added in version 10:
+/// - the table ambience volume, in bits 4-5 of `audio`
+///
+/// Added in version 11:
+/// - the animation speed, in bits 2-3 of `display`
+///
+/// Added in version 12:
+/// - `table: u8`, bit 0 asking for a shuffle seed before each table
+///
+/// Added in version 13:
+/// - riffling the blackjack shoe in bit 1 of `table`, the hold'em deck in
+///   bit 2 and how neatly in bits 3-4
+///
+/// Added in version 14:
+/// - `redeemed: [u16; REDEEMED_CODES]`, nonces of the passcodes imported,
+///   newest first
+///
+/// Added in version 15:
+/// - `timings: [u16; 4]`, each user's gesture timings, two bits a button in
+///   `BUTTONS` order
+///
+/// Fields are only ever appended. Reading past the end of an older payload
+/// yields zeroes, so older saves load with defaults for newer fields.
+#[derive(Clone, Default)]
+pub struct SaveData {
+    pub favorites: u32,
+    pub play_frames: u32,
+    pub profiles: Vec<Profile>,
+    pub resume: Option<Resume>,
+    pub settings: Settings,
+}
+
+/// A game that was interrupted mid-round, to be picked up where it left off.
+#[derive(Clone, PartialEq)]
+pub struct Resume {
+    pub game: usize,
+    pub profile: usize,
+    pub snapshot: Vec<u8>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SaveError {
+    /// The slot has never been written.
+    Blank,
+    /// The slot was written but fails its magic or checksum test.
+    Corrupt,
+    /// The slot was written by a newer version of the cartridge.
+    Unsupported,
+    /// The payload is too big for its slot, or a snapshot for its length
+    /// byte, so nothing was written.
+    TooLarge,
+}
+
+pub fn fletcher16(data: &[u8]) -> u16 {
+    let (mut sum1, mut sum2) = (0u16, 0u16);
+    for byte in data {
+        sum1 = (sum1 + *byte as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+    }
+    (sum2 << 8) | sum1
+}
+
+fn read_disk() -> [u8; DISK_SIZE] {
+    let mut buf = [0u8; DISK_SIZE];
+    unsafe {
+        diskr(buf.as_mut_ptr(), DISK_SIZE as u32);
+    }
+    buf
+}
+
+fn write_disk(buf: &[u8; DISK_SIZE]) {
+    unsafe {
+        diskw(buf.as_ptr(), DISK_SIZE as u32);
+    }
+}
+
+/// Reads little endian values from a byte buffer, yielding zeroes past the
+/// end of the buffer.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, position: 0 }
+    }
+
+    pub fn u8(&mut self) -> u8 {
+        let byte = self.buf.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        [(); N].map(|_| self.u8())
+    }
+
+    pub fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.bytes())
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.bytes())
+    }
+
+    pub fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.bytes())
+    }
+}
+
+struct Writer {
+    buf: [u8; SLOT_SIZE - HEADER_SIZE],
+    position: usize,
+    /// Set once something didn't fit, after which nothing more is written.
+    overflowed: bool,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self {
+            buf: [0; SLOT_SIZE - HEADER_SIZE],
+            position: 0,
+            overflowed: false,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        match self.buf.get_mut(self.position..self.position + bytes.len()) {
+            Some(into) if !self.overflowed => into.copy_from_slice(bytes),
+            _ => self.overflowed = true,
+        }
+        self.position += bytes.len();
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+}
+
+impl SaveData {
+    pub fn load_all() -> [Result<Self, SaveError>; NUM_SLOTS] {
+        let disk = read_disk();
+        let mut slots = disk.chunks_exact(SLOT_SIZE).map(Self::from_bytes);
+        [(); NUM_SLOTS].map(|_| slots.next().unwrap_or(Err(SaveError::Blank)))
+    }
+
+    pub fn load(slot: usize) -> Result<Self, SaveError> {
+        let disk = read_disk();
+        Self::from_bytes(&disk[slot * SLOT_SIZE..(slot + 1) * SLOT_SIZE])
+    }
+
+    /// Writes the slot, leaving what was there if it doesn't fit.
+    pub fn store(&self, slot: usize) -> Result<(), SaveError> {
+        let bytes = self.to_bytes()?;
+        let mut disk = read_disk();
+        disk[slot * SLOT_SIZE..(slot + 1) * SLOT_SIZE].copy_from_slice(&bytes);
+        write_disk(&disk);
+        Ok(())
+    }
+
+    pub fn erase(slot: usize) {
+        let mut disk = read_disk();
+        disk[slot * SLOT_SIZE..(slot + 1) * SLOT_SIZE].fill(0);
+        write_disk(&disk);
+    }
+
+    fn from_bytes(buf: &[u8]) -> Result<Self, SaveError> {
+        if buf.iter().all(|byte| *byte == 0) {
+            return Err(SaveError::Blank)
+        }
+        if buf[0..2] != MAGIC {
+            return Err(SaveError::Corrupt)
+        }
+        let version = buf[2];
+        let length = u16::from_le_bytes([buf[3], buf[4]]) as usize;
+        let checksum = u16::from_le_bytes([buf[5], buf[6]]);
+        let payload = match buf.get(HEADER_SIZE..HEADER_SIZE + length) {
+            Some(payload) if fletcher16(payload) == checksum => payload,
+            _ => return Err(SaveError::Corrupt),
+        };
+        if version > CURRENT_VERSION {
+            return Err(SaveError::Unsupported)
+        }
+        Ok(Self::decode(version, &mut Reader::new(payload)))
+    }
+
+    fn decode(version: u8, reader: &mut Reader) -> Self {
+        let favorites = reader.u32();
+        let play_frames = reader.u32();
+        let num_profiles = (reader.u8() as usize).min(MAX_PROFILES);
+        let mut profiles: Vec<Profile> = (0..num_profiles)
+            .map(|_| {
+                let initials = reader.bytes();
+                Profile {
+                    bank: reader.u32(),
+                    ..Profile::new(initials)
+                }
+            })
+            .collect();
+        let mut resume = None;
+        if version >= 2 {
+            let game = reader.u8();
+            let profile = reader.u8() as usize;
+            let len = reader.u8() as usize;
+            let snapshot = (0..len).map(|_| reader.u8()).collect();
+            if game != 0xff {
+                resume = Some(Resume { game: game as usize, profile, snapshot });
+            }
+        }
+        let mut settings = Settings::default();
+        if version >= 3 {
+            let loss_limit = reader.u32();
+            settings.loss_limit = if loss_limit == 0 { None } else { Some(loss_limit) };
+            settings.difficulty = Difficulty::from_byte(reader.u8());
+        }
+        if version >= 4 {
+            for profile in profiles.iter_mut() {
+                profile.achievements = reader.u16();
+                let num_stats = reader.u8();
+                for _ in 0..num_stats {
+                    let key = reader.u8() as usize;
+                    let stats = GameStats {
+                        rounds_played: reader.u16() as u32,
+                        net_winnings: reader.u16() as i16 as i32,
+                    };
+                    if let Some(game) = profile.stats.get_mut(key >> 1) {
+                        game[key & 1] = stats;
+                    }
+                }
+            }
+        }
+        if version >= 5 {
+            let controls = reader.u8();
+            settings.controls.swap_buttons = controls & 1 != 0;
+            settings.controls.direct_actions = controls & 2 != 0;
+            settings.controls.mirrored = controls & 4 != 0;
+        }
+        if version >= 6 {
+            let idle_minutes = reader.u8() as u32;
+            settings.idle_minutes = if idle_minutes == 0 { None } else { Some(idle_minutes) };
+        }
+        if version >= 7 {
+            settings.felt = Felt::from_byte(reader.u8());
+        }
+        if version >= 8 {
+            let display = reader.u8();
+            settings.colorblind = display & 1 != 0;
+            settings.high_contrast = display & 2 != 0;
+            if version >= 11 {
+                settings.animation_speed = AnimationSpeed::from_byte(display >> 2 & 3);
+            }
+        }
+        if version >= 9 {
+            let audio = reader.u8();
+            settings.music_volume = Volume::from_byte(audio & 3);
+            settings.sfx_volume = Volume::from_byte(audio >> 2 & 3);
+            if version >= 10 {
+                settings.ambience_volume = Volume::from_byte(audio >> 4 & 3);
+            }
+        }
+        if version >= 12 {
+            let table = reader.u8();
+            settings.seed_entry = table & 1 != 0;
+            if version >= 13 {
+                let riffle = |bit: u8| if table & bit != 0 { ShuffleStrategy::Riffle } else { ShuffleStrategy::FisherYates };
+                settings.shoe_shuffle = riffle(2);
+                settings.holdem_shuffle = riffle(4);
+                settings.realism = Realism::from_byte(table >> 3 & 3);
+            }
+        }
+        if version >= 14 {
+            settings.redeemed = [(); REDEEMED_CODES].map(|_| reader.u16());
+        }
+        if version >= 15 {
+            for user in 0..4 {
+                settings.controls.unpack_timings(user, reader.u16());
+            }
+        }
+        Self { favorites, play_frames, profiles, resume, settings }
+    }
+
+    fn encode(&self, writer: &mut Writer) {
+        writer.u32(self.favorites);
+        writer.u32(self.play_frames);
+        writer.u8(self.profiles.len().min(MAX_PROFILES) as u8);
+        for profile in self.profiles.iter().take(MAX_PROFILES) {
+            writer.bytes(&profile.initials);
+            writer.u32(profile.bank);
+        }
+        match &self.resume {
+            Some(resume) => {
+                writer.u8(resume.game as u8);
+                writer.u8(resume.profile as u8);
+                match u8::try_from(resume.snapshot.len()) {
+                    Ok(len) => writer.u8(len),
+                    Err(_) => writer.overflowed = true,
+                }
+                writer.bytes(&resume.snapshot);
+            }
+            None => {
+                writer.u8(0xff);
+                writer.u8(0);
+                writer.u8(0);
+            }
+        }
+        writer.u32(self.settings.loss_limit.unwrap_or(0));
+        writer.u8(self.settings.difficulty as u8);
+        for profile in self.profiles.iter().take(MAX_PROFILES) {
+            writer.u16(profile.achievements);
+            let mut entries: Vec<(usize, GameStats)> = (0..MAX_GAMES * 2)
+                .map(|key| (key, profile.stats[key >> 1][key & 1]))
+                .filter(|(_, stats)| stats.rounds_played > 0)
+                .collect();
+            entries.sort_by_key(|(_, stats)| core::cmp::Reverse(stats.rounds_played));
+            entries.truncate(MAX_STAT_ENTRIES);
+            writer.u8(entries.len() as u8);
+            for (key, stats) in entries {
+                writer.u8(key as u8);
+                writer.u16(stats.rounds_played.min(u16::MAX as u32) as u16);
+                writer.u16(stats.net_winnings.clamp(i16::MIN as i32, i16::MAX as i32) as i16 as u16);
+            }
+        }
+        let controls = &self.settings.controls;
+        writer.u8(controls.swap_buttons as u8 | (controls.direct_actions as u8) << 1 | (controls.mirrored as u8) << 2);
+        writer.u8(self.settings.idle_minutes.unwrap_or(0) as u8);
+        writer.u8(self.settings.felt as u8);
+        writer.u8(self.settings.colorblind as u8 | (self.settings.high_contrast as u8) << 1 | (self.settings.animation_speed as u8) << 2);
+        let settings = &self.settings;
+        writer.u8(settings.music_volume as u8 | (settings.sfx_volume as u8) << 2 | (settings.ambience_volume as u8) << 4);
+        writer.u8(
+            settings.seed_entry as u8
+                | (settings.shoe_shuffle as u8) << 1
+                | (settings.holdem_shuffle as u8) << 2
+                | (settings.realism as u8) << 3,
+        );
+        for nonce in settings.redeemed {
+            writer.u16(nonce);
+        }
+        for user in 0..4 {
+            writer.u16(settings.controls.packed_timings(user));
+        }
+    }
+
+    fn to_bytes(&self) -> Result<[u8; SLOT_SIZE], SaveError> {
+        let mut writer = Writer::new();
+        self.encode(&mut writer);
+        if writer.overflowed {
+            return Err(SaveError::TooLarge)
+        }
+        let payload = &writer.buf[..writer.position];
+        let mut slot = [0u8; SLOT_SIZE];
+        slot[0..2].copy_from_slice(&MAGIC);
+        slot[2] = CURRENT_VERSION;
+        slot[3..5].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+        slot[5..7].copy_from_slice(&fletcher16(payload).to_le_bytes());
+        slot[HEADER_SIZE..HEADER_SIZE + payload.len()].copy_from_slice(payload);
+        Ok(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GestureTiming;
+    use alloc::vec;
+    use crate::headless;
+
+    #[test]
+    fn slots_round_trip_through_the_disk() {
+        let _console = headless::session();
+        SaveData { favorites: 0b101, play_frames: 77, ..SaveData::default() }.store(1).unwrap();
+        let loaded = SaveData::load(1).unwrap();
+        assert_eq!((loaded.favorites, loaded.play_frames), (0b101, 77));
+        let mut settings = Settings::default();
+        settings.redeem(12);
+        settings.redeem(345);
+        settings.controls.timings[2][5] = GestureTiming::Relaxed;
+        SaveData { settings, ..SaveData::default() }.store(1).unwrap();
+        let redeemed = SaveData::load(1).unwrap().settings;
+        assert!(redeemed.redeemed(12) && redeemed.redeemed(345) && !redeemed.redeemed(6));
+        assert_eq!(redeemed.controls.timings[2][5], GestureTiming::Relaxed);
+        assert_eq!(redeemed.controls.timings[2][4], GestureTiming::Normal);
+        assert_eq!(SaveData::load(0).err(), Some(SaveError::Blank));
+    }
+
+    #[test]
+    fn damaged_slots_load_as_corrupt() {
+        let _console = headless::session();
+        SaveData { play_frames: 77, ..SaveData::default() }.store(0).unwrap();
+        let mut disk = read_disk();
+        disk[HEADER_SIZE] ^= 1;
+        write_disk(&disk);
+        assert_eq!(SaveData::load(0).err(), Some(SaveError::Corrupt));
+    }
+
+    #[test]
+    fn oversize_snapshots_leave_the_slot_alone() {
+        let _console = headless::session();
+        SaveData { play_frames: 77, ..SaveData::default() }.store(2).unwrap();
+        let resume = Some(Resume { game: 0, profile: 0, snapshot: vec![7; 256] });
+        assert_eq!(SaveData { resume, ..SaveData::default() }.store(2), Err(SaveError::TooLarge));
+        assert_eq!(SaveData::load(2).unwrap().play_frames, 77);
+    }
+
+    #[test]
+    fn erasing_a_slot_keeps_the_others() {
+        let _console = headless::session();
+        SaveData { play_frames: 1, ..SaveData::default() }.store(0).unwrap();
+        SaveData { play_frames: 2, ..SaveData::default() }.store(1).unwrap();
+        SaveData::erase(1);
+        assert_eq!(SaveData::load(0).unwrap().play_frames, 1);
+        assert_eq!(SaveData::load(1).err(), Some(SaveError::Blank));
+    }
+}
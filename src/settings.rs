@@ -0,0 +1,392 @@
+use crate::{
+    felt::Felt,
+    model::{Controls, GameEvent, Inputs, Model, BUTTONS},
+    money,
+    passcode::{self, Passcode, CODE_LEN, MAX_NONCE},
+    rules::Difficulty,
+    sfx::{self, Volume},
+    shuffle::{Realism, Shuffle, ShuffleStrategy},
+    timers::{self, AnimationSpeed},
+    ui::{self, Keyboard, Modal},
+    wasm4::*,
+    PlayerState,
+};
+
+const LOSS_LIMITS: [Option<u32>; 5] = [None, Some(50), Some(100), Some(250), Some(500)];
+const IDLE_TIMEOUTS: [Option<u32>; 4] = [None, Some(1), Some(2), Some(5)];
+/// Passcodes a slot remembers taking in, so none is taken twice.
+pub const REDEEMED_CODES: usize = 4;
+
+#[derive(Copy, Clone, Default)]
+pub struct Settings {
+    /// Betting is locked once the session's losses reach this amount.
+    pub loss_limit: Option<u32>,
+    pub difficulty: Difficulty,
+    pub controls: Controls,
+    /// Minutes without input before a game goes back to the menu.
+    pub idle_minutes: Option<u32>,
+    pub felt: Felt,
+    /// Swaps red and yellow for blue and orange and draws red suits hollow.
+    pub colorblind: bool,
+    /// Darker text, boxed focus and slower flashing.
+    pub high_contrast: bool,
+    pub music_volume: Volume,
+    pub sfx_volume: Volume,
+    /// Murmur and blips under the tables.
+    pub ambience_volume: Volume,
+    pub animation_speed: AnimationSpeed,
+    /// Asks for a shuffle seed before sitting at a table.
+    pub seed_entry: bool,
+    pub shoe_shuffle: ShuffleStrategy,
+    pub holdem_shuffle: ShuffleStrategy,
+    /// How neatly the tables shuffled by hand riffle.
+    pub realism: Realism,
+    /// Nonces of the last passcodes imported, newest first, 0 for none.
+    pub redeemed: [u16; REDEEMED_CODES],
+}
+
+impl Settings {
+    /// What drawing needs to know of these settings.
+    pub fn display(&self) -> ui::Display {
+        ui::Display { colorblind: self.colorblind, high_contrast: self.high_contrast }
+    }
+
+    /// The shuffle for a table set to `strategy`.
+    pub fn shuffle(&self, strategy: ShuffleStrategy) -> Shuffle {
+        Shuffle { strategy, realism: self.realism }
+    }
+
+    /// Whether the passcode with `nonce` was already imported.
+    pub fn redeemed(&self, nonce: u16) -> bool {
+        self.redeemed.contains(&nonce)
+    }
+
+    /// Remembers an imported passcode, forgetting the oldest.
+    pub fn redeem(&mut self, nonce: u16) {
+        self.redeemed.copy_within(..REDEEMED_CODES - 1, 1);
+        self.redeemed[0] = nonce;
+    }
+}
+
+const NUM_ROWS: usize = 22;
+/// Rows that fit on screen at once; the list scrolls to keep the cursor
+/// in view.
+const VISIBLE_ROWS: usize = 14;
+const EXPORT_ROW: usize = 19;
+const IMPORT_ROW: usize = 20;
+const DELETE_ROW: usize = 21;
+/// Button names for the gesture timing rows, in `BUTTONS` order.
+const BUTTON_NAMES: [&str; BUTTONS.len()] = ["X", "Z", "Left", "Right", "Up", "Down"];
+/// How long X has to be held, after arming, to wipe the save slot.
+const DELETE_HOLD_FRAMES: u32 = timers::secs(1.5);
+
+/// What an open modal is asking about.
+enum Confirm {
+    Delete,
+    /// Replacing the bank with one from a passcode.
+    Import(Passcode),
+}
+
+pub struct SettingsScreen {
+    player_state: PlayerState,
+    cursor: usize,
+    /// Which user's button the gesture timing row sets, as
+    /// `user * BUTTONS.len() + button`.
+    timing_target: usize,
+    /// Passcode entry for importing a bank from another cartridge.
+    entry: Option<Keyboard<CODE_LEN>>,
+    /// The nonce of the code shown for exporting, picked as the screen opens.
+    export_nonce: u16,
+    message: Option<&'static str>,
+    /// Set once the modal for "Delete save data" says yes; X then has to be
+    /// held for `DELETE_HOLD_FRAMES`.
+    confirm_delete: bool,
+    delete_hold_frames: u32,
+    modal: Option<(Modal, Confirm)>,
+}
+
+impl SettingsScreen {
+    pub fn new(player_state: PlayerState, seed: u64) -> Self {
+        Self {
+            player_state,
+            cursor: 0,
+            timing_target: 0,
+            entry: None,
+            export_nonce: 1 + (seed % MAX_NONCE as u64) as u16,
+            message: None,
+            confirm_delete: false,
+            delete_hold_frames: 0,
+            modal: None,
+        }
+    }
+
+    fn cycle_loss_limit(&mut self, step: isize) {
+        let settings = &mut self.player_state.settings;
+        let index = LOSS_LIMITS.iter().position(|limit| *limit == settings.loss_limit).unwrap_or(0);
+        let len = LOSS_LIMITS.len() as isize;
+        settings.loss_limit = LOSS_LIMITS[(index as isize + step).rem_euclid(len) as usize];
+    }
+
+    fn cycle_idle_timeout(&mut self, step: isize) {
+        let settings = &mut self.player_state.settings;
+        let index = IDLE_TIMEOUTS.iter().position(|minutes| *minutes == settings.idle_minutes).unwrap_or(0);
+        let len = IDLE_TIMEOUTS.len() as isize;
+        settings.idle_minutes = IDLE_TIMEOUTS[(index as isize + step).rem_euclid(len) as usize];
+    }
+}
+
+impl Model<PlayerState> for SettingsScreen {
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<PlayerState> {
+        let player_one_inputs = inputs[0];
+        if let Some((modal, confirm)) = &mut self.modal {
+            match (modal.update(&player_one_inputs), confirm) {
+                (Some(true), Confirm::Delete) => self.confirm_delete = true,
+                (Some(true), Confirm::Import(passcode)) => {
+                    let Passcode { bank, nonce } = *passcode;
+                    self.player_state.set_bank(bank);
+                    self.player_state.session_start_bank = bank;
+                    self.player_state.settings.redeem(nonce);
+                    self.message = Some("Code accepted");
+                }
+                (Some(false), _) => {}
+                (None, _) => return GameEvent::Continue,
+            }
+            self.modal = None;
+            return GameEvent::Continue
+        }
+        if let Some(entry) = &mut self.entry {
+            if let Some(code) = entry.update(&player_one_inputs) {
+                if let Some(code) = code {
+                    match passcode::decode(&code) {
+                        Some(passcode) if self.player_state.settings.redeemed(passcode.nonce) => {
+                            sfx::error();
+                            self.message = Some("Code already used");
+                        }
+                        Some(passcode) => {
+                            self.modal = Some((Modal::new("Replace bank?"), Confirm::Import(passcode)));
+                        }
+                        None => {
+                            sfx::error();
+                            self.message = Some("Bad code");
+                        }
+                    }
+                }
+                self.entry = None;
+            }
+            return GameEvent::Continue
+        }
+        if player_one_inputs.tap_down {
+            self.cursor = (self.cursor + 1) % NUM_ROWS;
+            self.confirm_delete = false;
+        }
+        if player_one_inputs.tap_up {
+            self.cursor = (self.cursor + NUM_ROWS - 1) % NUM_ROWS;
+            self.confirm_delete = false;
+        }
+        if player_one_inputs.tap_down || player_one_inputs.tap_up {
+            sfx::cursor_move();
+        }
+        let step = if player_one_inputs.tap_right {
+            1
+        } else if player_one_inputs.tap_left {
+            -1
+        } else {
+            0
+        };
+        if step != 0 && self.cursor < EXPORT_ROW {
+            sfx::confirm();
+            match self.cursor {
+                0 => self.cycle_loss_limit(step),
+                1 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.difficulty = settings.difficulty.toggled();
+                }
+                2 => {
+                    let controls = &mut self.player_state.settings.controls;
+                    controls.swap_buttons = !controls.swap_buttons;
+                }
+                3 => {
+                    let controls = &mut self.player_state.settings.controls;
+                    controls.direct_actions = !controls.direct_actions;
+                }
+                4 => {
+                    let controls = &mut self.player_state.settings.controls;
+                    controls.mirrored = !controls.mirrored;
+                }
+                5 => self.cycle_idle_timeout(step),
+                6 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.felt = settings.felt.cycled(step);
+                }
+                7 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.colorblind = !settings.colorblind;
+                }
+                8 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.high_contrast = !settings.high_contrast;
+                }
+                9 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.music_volume = settings.music_volume.cycled(step);
+                }
+                10 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.sfx_volume = settings.sfx_volume.cycled(step);
+                }
+                11 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.ambience_volume = settings.ambience_volume.cycled(step);
+                }
+                12 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.animation_speed = settings.animation_speed.cycled(step);
+                }
+                13 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.seed_entry = !settings.seed_entry;
+                }
+                14 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.shoe_shuffle = settings.shoe_shuffle.toggled();
+                }
+                15 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.holdem_shuffle = settings.holdem_shuffle.toggled();
+                }
+                16 => {
+                    let settings = &mut self.player_state.settings;
+                    settings.realism = settings.realism.cycled(step);
+                }
+                17 => {
+                    let len = (4 * BUTTONS.len()) as isize;
+                    self.timing_target = (self.timing_target as isize + step).rem_euclid(len) as usize;
+                }
+                18 => {
+                    let (user, button) = (self.timing_target / BUTTONS.len(), self.timing_target % BUTTONS.len());
+                    let timing = &mut self.player_state.settings.controls.timings[user][button];
+                    *timing = timing.cycled(step);
+                }
+                _ => {}
+            }
+        }
+        if player_one_inputs.tap_x && self.cursor == IMPORT_ROW {
+            sfx::confirm();
+            self.entry = Some(Keyboard::new("Enter code", passcode::ALPHABET));
+            self.message = None;
+        }
+        if self.cursor == DELETE_ROW {
+            if self.confirm_delete && player_one_inputs.press_x && (player_one_inputs.tap_x || self.delete_hold_frames > 0) {
+                self.delete_hold_frames += 1;
+                if self.delete_hold_frames >= DELETE_HOLD_FRAMES {
+                    return GameEvent::EraseSave
+                }
+            } else {
+                self.delete_hold_frames = 0;
+            }
+            if player_one_inputs.tap_x && !self.confirm_delete {
+                self.modal = Some((Modal::new("Delete save data?"), Confirm::Delete));
+            }
+        }
+        if player_one_inputs.tap_z {
+            sfx::cancel();
+            return GameEvent::Exit { state: self.player_state }
+        }
+        GameEvent::Continue
+    }
+
+    fn draw(&self) {
+        let display = self.player_state.settings.display();
+        if let Some(entry) = &self.entry {
+            entry.draw(display);
+            return
+        }
+        ui::set_text_colors(0x02, display);
+        text("Settings", 48, 2);
+        let settings = &self.player_state.settings;
+        let top = self.cursor.saturating_sub(VISIBLE_ROWS - 1);
+        for row in top..top + VISIBLE_ROWS {
+            ui::set_text_colors(if row == self.cursor { 0x32 } else { 0x02 }, display);
+            let y = (13 + 9 * (row - top)) as _;
+            match row {
+                0 => match settings.loss_limit {
+                    Some(limit) => text(fixed_format!("Loss limit: {}", money::chips(limit)), 10, y),
+                    None => text("Loss limit: Off", 10, y),
+                },
+                1 => text(fixed_format!("Odds: {}", settings.difficulty.name()), 10, y),
+                2 if settings.controls.swap_buttons => text("Confirm: Z", 10, y),
+                2 => text("Confirm: X", 10, y),
+                3 if settings.controls.direct_actions => text("Layout: Direct", 10, y),
+                3 => text("Layout: Cursor", 10, y),
+                4 if settings.controls.mirrored => text("Left-handed: On", 10, y),
+                4 => text("Left-handed: Off", 10, y),
+                5 => match settings.idle_minutes {
+                    Some(minutes) => text(fixed_format!("Idle exit: {}m", minutes), 10, y),
+                    None => text("Idle exit: Off", 10, y),
+                },
+                6 => text(fixed_format!("Felt: {}", settings.felt.name()), 10, y),
+                7 if settings.colorblind => text("Colorblind: On", 10, y),
+                7 => text("Colorblind: Off", 10, y),
+                8 if settings.high_contrast => text("High contrast: On", 10, y),
+                8 => text("High contrast: Off", 10, y),
+                9 => text(fixed_format!("Music: {}", settings.music_volume.name()), 10, y),
+                10 => text(fixed_format!("Sound: {}", settings.sfx_volume.name()), 10, y),
+                11 => text(fixed_format!("Ambience: {}", settings.ambience_volume.name()), 10, y),
+                12 => text(fixed_format!("Animations: {}", settings.animation_speed.name()), 10, y),
+                13 if settings.seed_entry => text("Pick seed: On", 10, y),
+                13 => text("Pick seed: Off", 10, y),
+                14 => text(fixed_format!("Shoe: {}", settings.shoe_shuffle.name()), 10, y),
+                15 => text(fixed_format!("Hold'em: {}", settings.holdem_shuffle.name()), 10, y),
+                16 => text(fixed_format!("Riffles: {}", settings.realism.name()), 10, y),
+                17 => {
+                    let (user, button) = (self.timing_target / BUTTONS.len(), self.timing_target % BUTTONS.len());
+                    text(fixed_format!("Gestures: P{} {}", user + 1, BUTTON_NAMES[button]), 10, y)
+                }
+                18 => {
+                    let (user, button) = (self.timing_target / BUTTONS.len(), self.timing_target % BUTTONS.len());
+                    text(fixed_format!("Timing: {}", settings.controls.timings[user][button].name()), 10, y)
+                }
+                EXPORT_ROW => text("Export code", 10, y),
+                IMPORT_ROW => text("Import code", 10, y),
+                _ => text("Delete save data", 10, y),
+            }
+            if row == self.cursor {
+                ui::draw_focus_box(10, y, 140, display);
+            }
+        }
+        ui::set_text_colors(0x02, display);
+        // one line under the rows for the code, a message or the delete
+        // prompt, with the controls below it
+        if self.cursor == EXPORT_ROW {
+            let code = passcode::encode(Passcode { bank: self.player_state.bank(), nonce: self.export_nonce });
+            unsafe {
+                extern_text(code.as_ptr(), code.len(), 28, 142);
+            }
+        }
+        if self.confirm_delete {
+            let t = b"Hold \x80 to delete";
+            unsafe {
+                extern_text(t.as_ptr(), t.len(), 10, 142);
+            }
+            rect(10, 140, 140 * self.delete_hold_frames / DELETE_HOLD_FRAMES, 1);
+        }
+        if let Some(message) = self.message {
+            text(message, 10, 142);
+        }
+        if let Some((modal, _)) = &self.modal {
+            modal.draw(display);
+        }
+        let t = b"\x84\x85:set \x80:ok \x81:back";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+
+    fn share_state(&mut self, state: PlayerState) {
+        self.player_state = state;
+    }
+
+    fn state_name(&self) -> &'static str {
+        "Settings"
+    }
+}
@@ -0,0 +1,263 @@
+//! Every sound effect in the cartridge, named for what it goes with so
+//! games don't pick tone parameters of their own. They all play on the
+//! first pulse channel or the noise channel, clear of the music.
+
+use core::sync::atomic::{AtomicU32, AtomicU8, AtomicUsize, Ordering::Relaxed};
+
+use crate::{music, wasm4::*};
+
+/// How loud music or sound effects play, picked in settings.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum Volume {
+    Off,
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl Volume {
+    const ALL: [Volume; 4] = [Volume::Off, Volume::Low, Volume::Medium, Volume::High];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Low => "Low",
+            Self::Medium => "Med",
+            Self::High => "High",
+        }
+    }
+
+    /// Reads a volume stored as its `u8` repr, falling back to the default
+    /// for unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        Self::ALL.get(byte as usize).copied().unwrap_or_default()
+    }
+
+    pub fn cycled(&self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(*self as isize + step).rem_euclid(len) as usize]
+    }
+
+    /// Turns down a volume from 0 to 100.
+    pub fn scale(&self, volume: u8) -> u8 {
+        (volume as u32 * *self as u32 / 3) as u8
+    }
+}
+
+/// One `tone()` call spelled out, so nobody has to pack its bit fields by
+/// hand. Durations are in frames and volumes run from 0 to 100. Build one
+/// from `Tone::SILENT` and set only what it needs:
+///
+/// ```ignore
+/// Tone { frequency: 880, sustain: 2, volume: 15, flags: TONE_PULSE1, ..Tone::SILENT }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tone {
+    /// Starting pitch in Hz.
+    pub frequency: u16,
+    /// Pitch to slide to over the whole tone, or 0 to hold `frequency`.
+    pub slide: u16,
+    pub attack: u8,
+    pub decay: u8,
+    pub sustain: u8,
+    pub release: u8,
+    /// Level held through the sustain.
+    pub volume: u8,
+    /// Level the attack climbs to before decaying to `volume`.
+    pub peak: u8,
+    /// Channel, duty cycle mode and pan, from the `TONE_*` constants.
+    pub flags: u32,
+}
+
+impl Tone {
+    pub const SILENT: Tone = Tone {
+        frequency: 0,
+        slide: 0,
+        attack: 0,
+        decay: 0,
+        sustain: 0,
+        release: 0,
+        volume: 0,
+        peak: 0,
+        flags: 0,
+    };
+
+    /// The same tone turned down to `volume`.
+    pub fn at(self, volume: Volume) -> Self {
+        Self { volume: volume.scale(self.volume), peak: volume.scale(self.peak), ..self }
+    }
+
+    /// Plays the tone as it is, ignoring the volume settings. A tone with
+    /// nothing left to hear isn't sent at all.
+    pub fn play(&self) {
+        if self.volume == 0 && self.peak == 0 {
+            return
+        }
+        tone(
+            self.frequency as u32 | (self.slide as u32) << 16,
+            (self.attack as u32) << 24 | (self.decay as u32) << 16 | self.sustain as u32 | (self.release as u32) << 8,
+            self.volume as u32 | (self.peak as u32) << 8,
+            self.flags,
+        );
+    }
+}
+
+// Sounds are started from anywhere in a frame, so what they share is kept
+// in atomics rather than threaded through every game. WASM-4 runs a cart on
+// one thread, so relaxed loads and stores are all they need.
+
+/// The volume picked in settings, as its `u8` repr.
+static VOLUME: AtomicU8 = AtomicU8::new(Volume::High as u8);
+/// State of a small LCG for `varied`, stepped on every use.
+static VARIATION: AtomicU32 = AtomicU32::new(0x9e37_79b9);
+/// Chip clinks still to come and frames until the next, played by `update`.
+static CLINKS_LEFT: AtomicU32 = AtomicU32::new(0);
+static CLINK_WAIT: AtomicU32 = AtomicU32::new(0);
+/// The win jingle's pitches, the next one to play and frames until it's
+/// due, played by `update`.
+static JINGLE: [AtomicU8; JINGLE_NOTES] = [const { AtomicU8::new(0) }; JINGLE_NOTES];
+static JINGLE_NEXT: AtomicUsize = AtomicUsize::new(JINGLE_NOTES);
+static JINGLE_WAIT: AtomicU32 = AtomicU32::new(0);
+
+/// Most clinks one stack of chips makes, however large.
+const MAX_CLINKS: u32 = 12;
+/// Frames between clinks, and before a payout's first one so it lands
+/// after the win sound.
+const CLINK_FRAMES: u32 = 4;
+const PAYOUT_DELAY: u32 = JINGLE_NOTES as u32 * JINGLE_FRAMES + 6;
+/// Notes in a win jingle and frames between them. The last note rings on.
+const JINGLE_NOTES: usize = 5;
+const JINGLE_FRAMES: u32 = 5;
+/// Steps of the major pentatonic scale, in semitones, which can't hit a
+/// sour note whichever way a jingle wanders.
+const PENTATONIC: [u8; 5] = [0, 2, 4, 7, 9];
+
+/// Sets the volume every effect after this plays at, from settings.
+pub fn set_volume(volume: Volume) {
+    VOLUME.store(volume as u8, Relaxed);
+}
+
+/// Plays a tone at the volume picked in settings, or not at all when
+/// effects are off.
+fn play(tone: Tone) {
+    tone.at(Volume::from_byte(VOLUME.load(Relaxed))).play();
+}
+
+/// The same tone pitched up or down by as much as 8%, differently every
+/// time, for sounds that repeat quickly and would otherwise sound canned.
+fn varied(tone: Tone) -> Tone {
+    let seed = VARIATION.load(Relaxed).wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    VARIATION.store(seed, Relaxed);
+    let roll = seed >> 16;
+    let percent = 92 + roll % 17;
+    let shift = |frequency: u16| (frequency as u32 * percent / 100) as u16;
+    Tone { frequency: shift(tone.frequency), slide: shift(tone.slide), ..tone }
+}
+
+/// A card snapped off the shoe onto the felt.
+pub fn card_deal() {
+    play(varied(Tone { frequency: 1200, slide: 600, release: 4, volume: 25, flags: TONE_NOISE, ..Tone::SILENT }));
+}
+
+/// A face-down card turned over.
+pub fn card_flip() {
+    play(varied(Tone { frequency: 700, slide: 1100, sustain: 1, release: 3, volume: 20, flags: TONE_NOISE, ..Tone::SILENT }));
+}
+
+/// Steps the win jingle and the clinks queued up by `chips` and `payout`,
+/// once a frame.
+pub fn update() {
+    let (next, frames) = (JINGLE_NEXT.load(Relaxed), JINGLE_WAIT.load(Relaxed));
+    if frames > 0 {
+        JINGLE_WAIT.store(frames - 1, Relaxed);
+    } else if let Some(pitch) = JINGLE.get(next) {
+        let last = next == JINGLE_NOTES - 1;
+        let sustain = if last { 3 * JINGLE_FRAMES } else { JINGLE_FRAMES - 2 } as u8;
+        let frequency = music::frequency(pitch.load(Relaxed));
+        play(Tone { frequency, sustain, release: 2, volume: 35, flags: TONE_PULSE1 | TONE_MODE3, ..Tone::SILENT });
+        JINGLE_NEXT.store(next + 1, Relaxed);
+        JINGLE_WAIT.store(JINGLE_FRAMES - 1, Relaxed);
+    }
+    let (left, frames) = (CLINKS_LEFT.load(Relaxed), CLINK_WAIT.load(Relaxed));
+    if left == 0 {
+        return
+    }
+    if frames > 0 {
+        CLINK_WAIT.store(frames - 1, Relaxed);
+        return
+    }
+    let left = left - 1;
+    CLINKS_LEFT.store(left, Relaxed);
+    CLINK_WAIT.store(CLINK_FRAMES, Relaxed);
+    // the last few chips of a big stack ring higher as it settles
+    let frequency = 1900 - 50 * left as u16;
+    play(varied(Tone { frequency, slide: frequency - 300, release: 6, volume: 20, flags: TONE_PULSE1 | TONE_MODE1, ..Tone::SILENT }));
+}
+
+/// One clink for a small amount, up to `MAX_CLINKS` for a big one.
+fn clinks(amount: u32) -> u32 {
+    (1 + amount.checked_ilog2().unwrap_or(0) / 2).min(MAX_CLINKS)
+}
+
+fn queue_clinks(amount: u32, delay: u32) {
+    let left = CLINKS_LEFT.load(Relaxed);
+    CLINKS_LEFT.store((left + clinks(amount)).min(MAX_CLINKS), Relaxed);
+    if left == 0 {
+        CLINK_WAIT.store(delay, Relaxed);
+    }
+}
+
+/// Chips set down on the felt, more of them for a bigger bet.
+pub fn chips(amount: u32) {
+    queue_clinks(amount, 0);
+}
+
+/// Chips pushed across to a winner, after the win sound has played.
+pub fn payout(amount: u32) {
+    queue_clinks(amount, PAYOUT_DELAY);
+}
+
+/// A hand or round that came out ahead: a short climb up the pentatonic
+/// scale, made up from `amount` so each payout has its own tune.
+pub fn win(amount: u32) {
+    let mut seed = amount.wrapping_mul(0x9e37_79b9) ^ 0x5bd1_e995;
+    let mut roll = |range: u32| {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (seed >> 16) % range
+    };
+    let root = 60 + [0, 2, 5, 7][roll(4) as usize];
+    let mut degree = roll(3) as usize;
+    for pitch in &JINGLE {
+        pitch.store(root + 12 * (degree / 5) as u8 + PENTATONIC[degree % 5], Relaxed);
+        degree += 1 + roll(2) as usize;
+    }
+    JINGLE_NEXT.store(0, Relaxed);
+    JINGLE_WAIT.store(0, Relaxed);
+}
+
+/// A hand or round that came out behind.
+pub fn lose() {
+    play(Tone { frequency: 330, slide: 165, sustain: 10, release: 20, volume: 35, flags: TONE_PULSE1 | TONE_MODE2, ..Tone::SILENT });
+}
+
+/// Something that can't be done right now.
+pub fn error() {
+    play(Tone { frequency: 140, sustain: 6, volume: 40, flags: TONE_PULSE1, ..Tone::SILENT });
+}
+
+/// A button pressed or a choice made.
+pub fn confirm() {
+    play(Tone { frequency: 660, slide: 990, sustain: 2, release: 3, volume: 18, flags: TONE_PULSE1 | TONE_MODE2, ..Tone::SILENT });
+}
+
+/// Backing out of a screen or answering no.
+pub fn cancel() {
+    play(Tone { frequency: 520, slide: 330, sustain: 2, release: 3, volume: 15, flags: TONE_PULSE1 | TONE_MODE2, ..Tone::SILENT });
+}
+
+/// Focus moving between rows or buttons.
+pub fn cursor_move() {
+    play(Tone { frequency: 880, sustain: 2, volume: 15, flags: TONE_PULSE1 | TONE_MODE3, ..Tone::SILENT });
+}
@@ -0,0 +1,248 @@
+//! How a table puts its cards in order. A shuffling machine's Fisher-Yates
+//! shuffle makes every order as likely as any other. A dealer shuffling by
+//! hand riffles, strips and cuts, which leaves runs of the old order behind,
+//! more of them the sloppier the dealer.
+//!
+//! A riffle cuts the cards near the middle and lets them fall from both
+//! halves, each card more likely to come from the half with more left, as
+//! in the Gilbert-Shannon-Reeds model. A sloppy hand also lets cards fall in
+//! clumps from one half. A strip pulls packets off the top into a new pile,
+//! turning their order over, and the cut moves the top part to the bottom.
+//!
+//! Riffles only mix the order the cards were gathered up in. Cards straight
+//! from the box are washed, spread and pushed around the table, before a
+//! dealer riffles them, or they'd keep the box's runs of suits.
+
+use alloc::vec::Vec;
+
+use crate::rng::GameRng;
+
+/// How a table shuffles, picked in settings for each table.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum ShuffleStrategy {
+    /// Every order equally likely, as from a shuffling machine.
+    #[default]
+    FisherYates,
+    /// Riffles, a strip and a cut, as dealt by hand.
+    Riffle,
+}
+
+impl ShuffleStrategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FisherYates => "Machine",
+            Self::Riffle => "Riffle",
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::FisherYates => Self::Riffle,
+            Self::Riffle => Self::FisherYates,
+        }
+    }
+}
+
+/// How carefully a dealer riffles, for tables shuffled by hand.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum Realism {
+    /// Even cuts and cards falling one at a time, riffled often.
+    Neat,
+    /// A casino dealer's riffle, riffle, strip, riffle and cut.
+    #[default]
+    Casino,
+    /// Once each side of the strip, with cards falling in clumps.
+    Sloppy,
+}
+
+impl Realism {
+    const ALL: [Realism; 3] = [Realism::Neat, Realism::Casino, Realism::Sloppy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Neat => "Neat",
+            Self::Casino => "Casino",
+            Self::Sloppy => "Sloppy",
+        }
+    }
+
+    /// Reads a realism stored as its `u8` repr, falling back to the default
+    /// for unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        Self::ALL.get(byte as usize).copied().unwrap_or_default()
+    }
+
+    pub fn cycled(&self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(*self as isize + step).rem_euclid(len) as usize]
+    }
+
+    /// Riffles before and after the strip.
+    fn riffles(&self) -> (u32, u32) {
+        match self {
+            Self::Neat => (4, 2),
+            Self::Casino => (2, 1),
+            Self::Sloppy => (1, 1),
+        }
+    }
+
+    /// Chance in 256 that a card falls from the same half as the last one,
+    /// whatever's left in each.
+    fn clumping(&self) -> u32 {
+        match self {
+            Self::Neat => 0,
+            Self::Casino => 64,
+            Self::Sloppy => 160,
+        }
+    }
+
+    /// How far from the middle a cut can land, as a fraction of the cards.
+    fn cut_spread(&self, len: usize) -> usize {
+        match self {
+            Self::Neat => len / 16,
+            Self::Casino => len / 8,
+            Self::Sloppy => len / 4,
+        }
+    }
+
+    /// Packets a strip pulls off, on average.
+    fn strips(&self) -> usize {
+        match self {
+            Self::Neat => 8,
+            Self::Casino => 5,
+            Self::Sloppy => 3,
+        }
+    }
+
+    /// Where to cut `len` cards, counted from the top.
+    fn cut(&self, len: usize, rng: &mut impl GameRng) -> usize {
+        let spread = self.cut_spread(len);
+        len / 2 - spread + rng.u32(..=2 * spread as u32) as usize
+    }
+}
+
+/// A table's shuffle: the strategy and, for riffles, how neatly they're
+/// done.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Shuffle {
+    pub strategy: ShuffleStrategy,
+    pub realism: Realism,
+}
+
+impl Shuffle {
+    /// Puts `cards`, gathered up in the order they're in, in a new one.
+    pub fn apply<T: Copy>(&self, rng: &mut impl GameRng, cards: &mut [T]) {
+        let realism = self.realism;
+        match self.strategy {
+            ShuffleStrategy::FisherYates => rng.shuffle(cards),
+            ShuffleStrategy::Riffle if cards.len() < 2 => {}
+            ShuffleStrategy::Riffle => {
+                let (before, after) = realism.riffles();
+                for _ in 0..before {
+                    riffle(realism, rng, cards);
+                }
+                strip(realism, rng, cards);
+                for _ in 0..after {
+                    riffle(realism, rng, cards);
+                }
+                let cut = realism.cut(cards.len(), rng);
+                cards.rotate_left(cut);
+            }
+        }
+    }
+
+    /// Shuffles cards that aren't in any order a table kept, like a new
+    /// deck, washing them first if they're to be riffled.
+    pub fn apply_fresh<T: Copy>(&self, rng: &mut impl GameRng, cards: &mut [T]) {
+        if self.strategy == ShuffleStrategy::Riffle {
+            rng.shuffle(cards);
+        }
+        self.apply(rng, cards);
+    }
+
+    /// Bit 0 for a riffle and the realism in bits 1-2, for snapshots and
+    /// saves.
+    pub fn to_byte(self) -> u8 {
+        self.strategy as u8 | (self.realism as u8) << 1
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        let strategy = if byte & 1 != 0 { ShuffleStrategy::Riffle } else { ShuffleStrategy::FisherYates };
+        Self { strategy, realism: Realism::from_byte(byte >> 1 & 3) }
+    }
+}
+
+fn riffle<T: Copy>(realism: Realism, rng: &mut impl GameRng, cards: &mut [T]) {
+    let len = cards.len();
+    let cut = realism.cut(len, rng);
+    let top: Vec<T> = cards[..cut].to_vec();
+    let (mut taken_top, mut next_bottom) = (0, cut);
+    let mut from_top = false;
+    // cards only ever land at or before the next one still to fall from the
+    // bottom half, so it can be riffled in place
+    for slot in 0..len {
+        let (top_left, bottom_left) = (cut - taken_top, len - next_bottom);
+        from_top = if top_left == 0 || bottom_left == 0 {
+            top_left > 0
+        } else if rng.u32(..256) < realism.clumping() {
+            from_top
+        } else {
+            rng.u32(..(top_left + bottom_left) as u32) < top_left as u32
+        };
+        if from_top {
+            cards[slot] = top[taken_top];
+            taken_top += 1;
+        } else {
+            cards[slot] = cards[next_bottom];
+            next_bottom += 1;
+        }
+    }
+}
+
+fn strip<T: Copy>(realism: Realism, rng: &mut impl GameRng, cards: &mut [T]) {
+    // pulling packets off the top turns their order over but keeps each
+    // packet's own
+    cards.reverse();
+    let mean = (cards.len() / realism.strips()).max(1) as u32;
+    let mut start = 0;
+    while start < cards.len() {
+        let size = (rng.u32(mean / 2..=mean + mean / 2).max(1) as usize).min(cards.len() - start);
+        cards[start..start + size].reverse();
+        start += size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    /// Neighbours in a fresh deck still next to each other after a shuffle.
+    fn runs_left(shuffle: Shuffle) -> usize {
+        let mut deck: Vec<u8> = (0..52).collect();
+        shuffle.apply(&mut Rng::with_seed(3), &mut deck);
+        let mut sorted = deck.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..52).collect::<Vec<u8>>());
+        deck.windows(2).filter(|pair| pair[1] == pair[0] + 1).count()
+    }
+
+    #[test]
+    fn sloppier_riffles_leave_more_of_the_old_order() {
+        let riffle = |realism| runs_left(Shuffle { strategy: ShuffleStrategy::Riffle, realism });
+        let (neat, sloppy) = (riffle(Realism::Neat), riffle(Realism::Sloppy));
+        assert!(sloppy > neat, "sloppy kept {} runs and neat {}", sloppy, neat);
+        assert!(riffle(Realism::Casino) > runs_left(Shuffle::default()));
+    }
+
+    #[test]
+    fn machine_shuffles_deal_the_shoes_they_always_have() {
+        let mut expected: Vec<u16> = (0..364).collect();
+        Rng::with_seed(5).shuffle(&mut expected);
+        let mut shoe: Vec<u16> = (0..364).collect();
+        Shuffle::default().apply(&mut Rng::with_seed(5), &mut shoe);
+        assert_eq!(shoe, expected);
+    }
+}
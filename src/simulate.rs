@@ -0,0 +1,53 @@
+//! Automated play for checking a game's rules against published odds,
+//! run natively by `src/bin/simulate.rs` rather than on the console.
+
+use alloc::collections::BTreeMap;
+
+pub use crate::{
+    blackjack::simulate::blackjack,
+    model::GameError,
+    rules::Difficulty,
+    shuffle::{Realism, Shuffle, ShuffleStrategy},
+};
+
+/// Totals over every round played. Amounts are in chips, on a bet of
+/// `bet` a round.
+pub struct Report {
+    pub bet: u32,
+    pub rounds: u64,
+    /// Everything put on the table, splits, doubles and insurance included.
+    pub wagered: u64,
+    /// What came back minus what was put down.
+    pub net: i64,
+    /// How many rounds ended up or down by each amount.
+    pub outcomes: BTreeMap<i64, u64>,
+    sum_of_squares: f64,
+}
+
+impl Report {
+    pub fn new(bet: u32) -> Self {
+        Self { bet, rounds: 0, wagered: 0, net: 0, outcomes: BTreeMap::new(), sum_of_squares: 0.0 }
+    }
+
+    pub fn record(&mut self, wagered: u32, returned: u32) {
+        let net = returned as i64 - wagered as i64;
+        self.rounds += 1;
+        self.wagered += wagered as u64;
+        self.net += net;
+        *self.outcomes.entry(net).or_default() += 1;
+        self.sum_of_squares += (net as f64).powi(2);
+    }
+
+    /// The house's take as a share of the opening bets, the way published
+    /// house edges are given.
+    pub fn house_edge(&self) -> f64 {
+        -self.net as f64 / (self.rounds as f64 * self.bet as f64)
+    }
+
+    /// Spread of a round's result, in opening bets.
+    pub fn std_dev(&self) -> f64 {
+        let rounds = self.rounds as f64;
+        let mean = self.net as f64 / rounds;
+        (self.sum_of_squares / rounds - mean * mean).sqrt() / self.bet as f64
+    }
+}
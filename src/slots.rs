@@ -0,0 +1,153 @@
+use crate::{model::Inputs, money, save::{SaveData, SaveError, NUM_SLOTS}, sfx, wasm4::*};
+
+#[derive(Copy, Clone, PartialEq)]
+enum SlotAction {
+    Play,
+    Copy,
+    Delete,
+}
+
+impl SlotAction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Play => "Play",
+            Self::Copy => "Copy",
+            Self::Delete => "Delete",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Self::Play => Self::Copy,
+            Self::Copy => Self::Delete,
+            Self::Delete => Self::Play,
+        }
+    }
+
+    fn prev(&self) -> Self {
+        match self {
+            Self::Play => Self::Delete,
+            Self::Copy => Self::Play,
+            Self::Delete => Self::Copy,
+        }
+    }
+}
+
+/// Startup screen for picking, copying and deleting save slots.
+pub struct SlotScreen {
+    slots: [Result<SaveData, SaveError>; NUM_SLOTS],
+    cursor: usize,
+    action: SlotAction,
+    confirm_delete: bool,
+}
+
+impl SlotScreen {
+    pub fn new() -> Self {
+        Self {
+            slots: SaveData::load_all(),
+            cursor: 0,
+            action: SlotAction::Play,
+            confirm_delete: false,
+        }
+    }
+
+    /// Returns the slot to play once one has been picked.
+    pub fn update(&mut self, inputs: Inputs) -> Option<usize> {
+        if inputs.tap_down {
+            self.cursor = (self.cursor + 1) % NUM_SLOTS;
+            self.confirm_delete = false;
+        }
+        if inputs.tap_up {
+            self.cursor = (self.cursor + NUM_SLOTS - 1) % NUM_SLOTS;
+            self.confirm_delete = false;
+        }
+        if inputs.tap_right {
+            self.action = self.action.next();
+            self.confirm_delete = false;
+        }
+        if inputs.tap_left {
+            self.action = self.action.prev();
+            self.confirm_delete = false;
+        }
+        if inputs.tap_down || inputs.tap_up || inputs.tap_right || inputs.tap_left {
+            sfx::cursor_move();
+        }
+        if inputs.tap_z {
+            sfx::cancel();
+            self.confirm_delete = false;
+        }
+        if inputs.tap_x {
+            match self.action {
+                SlotAction::Play => {
+                    // never overwrite a save we don't know how to read
+                    if let Err(SaveError::Unsupported) = self.slots[self.cursor] {
+                        sfx::error();
+                    } else {
+                        sfx::confirm();
+                        return Some(self.cursor)
+                    }
+                }
+                SlotAction::Copy => {
+                    // copy into the first empty slot
+                    let source = self.cursor;
+                    match (&self.slots[source], self.slots.iter().position(|slot| matches!(slot, Err(SaveError::Blank)))) {
+                        (Ok(save_data), Some(target)) => match save_data.store(target) {
+                            Ok(()) => {
+                                sfx::confirm();
+                                self.slots[target] = Ok(save_data.clone());
+                            }
+                            Err(_) => sfx::error(),
+                        },
+                        _ => sfx::error(),
+                    }
+                }
+                SlotAction::Delete if self.confirm_delete => {
+                    sfx::confirm();
+                    SaveData::erase(self.cursor);
+                    self.slots[self.cursor] = Err(SaveError::Blank);
+                    self.confirm_delete = false;
+                }
+                SlotAction::Delete => {
+                    sfx::confirm();
+                    self.confirm_delete = true;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        unsafe { *DRAW_COLORS = 0x02; }
+        text("Select save", 36, 10);
+        for (index, slot) in self.slots.iter().enumerate() {
+            let y = 30 + 30 * index as i32;
+            unsafe { *DRAW_COLORS = if index == self.cursor { 0x32 } else { 0x02 }; }
+            text(fixed_format!("Slot {}", index + 1), 10, y);
+            unsafe { *DRAW_COLORS = 0x02; }
+            match slot {
+                Ok(save_data) => {
+                    match save_data.profiles.first() {
+                        Some(profile) => text(fixed_format!("{} {}", profile.name(), money::chips(profile.bank)), 20, y + 10),
+                        None => text("No profiles", 20, y + 10),
+                    }
+                    let minutes = save_data.play_frames / 60 / 60;
+                    text(fixed_format!("{}h {:02}m", minutes / 60, minutes % 60), 100, y + 10);
+                }
+                Err(SaveError::Blank) => text("Empty", 20, y + 10),
+                // only writing a slot finds it too large
+                Err(SaveError::Corrupt | SaveError::TooLarge) => text("Corrupt data!", 20, y + 10),
+                Err(SaveError::Unsupported) => text("Newer version", 20, y + 10),
+            }
+        }
+        unsafe { *DRAW_COLORS = 0x02; }
+        if self.confirm_delete {
+            text("Delete? \u{80} again", 0, 142);
+        } else {
+            text(fixed_format!("\u{84}\u{85}: {}", self.action.name()), 0, 142);
+        }
+        let t = b"\x80: ok";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+}
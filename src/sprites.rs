@@ -0,0 +1,67 @@
+//! 2BPP sprite data generated from `assets/*.png` by `build.rs`. Sprites
+//! that pack smaller are kept run-length encoded in the cart and expanded
+//! whenever they're drawn.
+
+use crate::wasm4::*;
+
+include!(concat!(env!("OUT_DIR"), "/sprites.rs"));
+
+#[derive(Copy, Clone)]
+pub struct Sprite {
+    pub width: u32,
+    pub height: u32,
+    /// Whether `data` is run-length encoded rather than plain 2BPP.
+    packed: bool,
+    data: &'static [u8],
+}
+
+impl Sprite {
+    /// Expands the sprite into `buffer`, returning the part it fills, or
+    /// the data itself if it was never packed. See `build.rs` for the
+    /// encoding. Data cut off mid-chunk, or a chunk that would run past the
+    /// buffer, ends the sprite there rather than panicking mid-draw.
+    pub fn decode<'a>(&self, buffer: &'a mut [u8]) -> &'a [u8] {
+        if !self.packed {
+            return self.data
+        }
+        let (mut read, mut written) = (0, 0);
+        while let Some(&header) = self.data.get(read) {
+            let header = header as usize;
+            if header & 0x80 != 0 {
+                let len = (header & 0x7f) + 3;
+                let (Some(&byte), Some(out)) = (self.data.get(read + 1), buffer.get_mut(written..written + len)) else {
+                    break
+                };
+                out.fill(byte);
+                read += 2;
+                written += len;
+            } else {
+                let len = header + 1;
+                let (Some(bytes), Some(out)) = (self.data.get(read + 1..read + 1 + len), buffer.get_mut(written..written + len)) else {
+                    break
+                };
+                out.copy_from_slice(bytes);
+                read += 1 + len;
+                written += len;
+            }
+        }
+        &buffer[..written]
+    }
+
+    /// The same sprite with its data cut off after `len` bytes, as a damaged
+    /// cart might hold it, or `None` past the end.
+    #[cfg(test)]
+    pub fn truncated(&self, len: usize) -> Option<Self> {
+        Some(Self { data: self.data.get(..len)?, ..*self })
+    }
+
+    /// Blits with `BLIT_2BPP` using the current `DRAW_COLORS`. A sprite
+    /// that decodes short is left undrawn, since `blit` would read past it.
+    pub fn blit(&self, x: i32, y: i32) {
+        let mut buffer = [0; LARGEST];
+        let data = self.decode(&mut buffer);
+        if data.len() == (self.width * self.height).div_ceil(4) as usize {
+            blit(data, x, y, self.width, self.height, BLIT_2BPP);
+        }
+    }
+}
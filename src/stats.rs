@@ -0,0 +1,46 @@
+use alloc::format;
+use crate::{profiles::Profile, rules::Difficulty, wasm4::*};
+
+#[derive(Copy, Clone, Default)]
+pub struct GameStats {
+    pub rounds_played: u32,
+    pub net_winnings: i32,
+}
+
+impl GameStats {
+    pub fn record(&mut self, rounds: u32, net_winnings: i32) {
+        self.rounds_played = self.rounds_played.saturating_add(rounds);
+        self.net_winnings = self.net_winnings.saturating_add(net_winnings);
+    }
+}
+
+/// Dumps every profile's lifetime counters to the console, one `key=value`
+/// line per profile and per game/odds pair with rounds played, so sessions
+/// can be collected from the WASM-4 log.
+pub fn trace_stats(profiles: &[Profile], game_names: &[&str], play_frames: u32) {
+    trace(format!("stats play_frames={} profiles={}", play_frames, profiles.len()));
+    for profile in profiles {
+        trace(format!(
+            "stats profile={} bank={} achievements={:#06x}",
+            profile.name(),
+            profile.bank,
+            profile.achievements,
+        ));
+        for (game, name) in game_names.iter().enumerate() {
+            for difficulty in [Difficulty::Realistic, Difficulty::Arcade] {
+                let stats = profile.stats[game][difficulty as usize];
+                if stats.rounds_played == 0 {
+                    continue
+                }
+                trace(format!(
+                    "stats profile={} game={} odds={} rounds={} net={}",
+                    profile.name(),
+                    name,
+                    difficulty.name(),
+                    stats.rounds_played,
+                    stats.net_winnings,
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Lengths of time given in seconds and counted out on the 60fps frame
+//! tick, so a timing reads as what it means rather than a frame count.
+//!
+//! Gameplay timings, like how long a press has to be held, always last the
+//! same. Animation timings go through `anim` with the animation speed, so
+//! one multiplier speeds up or slows down every deal, slide and wipe.
+
+/// Frames the console runs a second.
+pub const FPS: u32 = 60;
+
+/// Slowest and fastest animation speeds. A quarter speed deal still fits
+/// the frame count a dealing table keeps.
+pub const SPEEDS: (f32, f32) = (0.25, 4.0);
+
+/// How fast animations play, picked in settings.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum AnimationSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Slow,
+}
+
+impl AnimationSpeed {
+    const ALL: [AnimationSpeed; 3] = [AnimationSpeed::Normal, AnimationSpeed::Fast, AnimationSpeed::Slow];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Fast => "Fast",
+            Self::Slow => "Slow",
+        }
+    }
+
+    /// Reads a speed stored as its `u8` repr, falling back to the default
+    /// for unknown values.
+    pub fn from_byte(byte: u8) -> Self {
+        Self::ALL.get(byte as usize).copied().unwrap_or_default()
+    }
+
+    pub fn cycled(&self, step: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        Self::ALL[(*self as isize + step).rem_euclid(len) as usize]
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            Self::Normal => 1.0,
+            Self::Fast => 2.0,
+            Self::Slow => 0.5,
+        }
+    }
+}
+
+/// Frames in `seconds`, whatever the animation speed.
+pub const fn secs(seconds: f32) -> u32 {
+    (seconds * FPS as f32 + 0.5) as u32
+}
+
+/// Frames an animation `seconds` long takes at `speed`, 1.0 being normal
+/// and kept within `SPEEDS`. Never less than one, so anything cued on it
+/// still happens.
+pub fn anim(seconds: f32, speed: f32) -> u32 {
+    (libm::roundf(seconds * FPS as f32 / speed.clamp(SPEEDS.0, SPEEDS.1)) as u32).max(1)
+}
+
+/// Counts down the frames of something that lasts a while, like a message
+/// on screen.
+#[derive(Copy, Clone)]
+pub struct Countdown(u32);
+
+impl Countdown {
+    pub const fn new(frames: u32) -> Self {
+        Self(frames)
+    }
+
+    /// Counts a frame off, returning false once it has run out.
+    pub fn tick(&mut self) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 > 0
+    }
+}
+
+/// Something that comes round every so many frames, counted from a frame
+/// counter kept elsewhere.
+#[derive(Copy, Clone)]
+pub struct Interval(u32);
+
+impl Interval {
+    pub const fn new(frames: u32) -> Self {
+        Self(frames)
+    }
+
+    /// Whether it comes round on `frame`. It doesn't on frame 0, before
+    /// any time has passed.
+    pub fn due(&self, frame: u64) -> bool {
+        frame > 0 && frame.is_multiple_of(self.0 as u64)
+    }
+
+    /// Frames since it last came round.
+    pub fn since(&self, frame: u64) -> u32 {
+        (frame % self.0 as u64) as u32
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_follow_the_speed_but_gameplay_doesnt() {
+        assert_eq!(secs(0.5), 30);
+        assert_eq!(anim(0.5, 1.0), 30);
+        assert_eq!(anim(0.5, 2.0), 15);
+        assert_eq!(anim(0.001, 2.0), 1, "never rounded away to nothing");
+        assert_eq!(anim(0.5, 100.0), anim(0.5, SPEEDS.1));
+        assert_eq!(anim(0.5, 0.01), anim(0.5, SPEEDS.0));
+    }
+
+    #[test]
+    fn intervals_come_round_after_each_period() {
+        let every_half_second = Interval::new(secs(0.5));
+        let due: alloc::vec::Vec<u64> = (0..100).filter(|frame| every_half_second.due(*frame)).collect();
+        assert_eq!(due, [30, 60, 90]);
+        assert_eq!(every_half_second.since(65), 5);
+    }
+}
@@ -0,0 +1,54 @@
+use alloc::vec::Vec;
+
+/// Seats taking turns at single rounds, each playing a stack of its own,
+/// as party mode and versus do.
+pub struct Turns {
+    pub num_players: usize,
+    pub banks: [u32; 4],
+    pub round: u32,
+    pub turn: usize,
+}
+
+impl Turns {
+    pub const fn new(stack: u32) -> Self {
+        Self { num_players: 2, banks: [stack; 4], round: 0, turn: 0 }
+    }
+
+    /// Hands the turn to the next seat, returning true when that starts a
+    /// new round.
+    pub fn pass(&mut self) -> bool {
+        self.turn += 1;
+        if self.turn < self.num_players {
+            return false
+        }
+        self.turn = 0;
+        self.round += 1;
+        true
+    }
+
+    /// Each seat's chips won or lost against the `stack` it started with.
+    pub fn results(&self, stack: u32) -> [i64; 4] {
+        core::array::from_fn(|player| if player < self.num_players { self.banks[player] as i64 - stack as i64 } else { 0 })
+    }
+
+    /// Player indices sorted from richest to poorest.
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.num_players).collect();
+        order.sort_by_key(|player| u32::MAX - self.banks[*player]);
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_go_round_the_seats_and_standings_go_by_stack() {
+        let mut turns = Turns { num_players: 3, ..Turns::new(500) };
+        assert_eq!([turns.pass(), turns.pass(), turns.pass()], [false, false, true]);
+        assert_eq!((turns.turn, turns.round), (0, 1));
+        turns.banks = [400, 900, 650, 0];
+        assert_eq!(turns.standings(), [1, 2, 0]);
+    }
+}
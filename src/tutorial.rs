@@ -0,0 +1,99 @@
+use crate::{layout::{self, Align, LINE_HEIGHT}, model::Inputs, wasm4::*};
+
+const CAPTION_WIDTH: i32 = 140;
+
+/// The button a tutorial step waits for.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Prompt {
+    X,
+    Right,
+    Up,
+    Down,
+}
+
+impl Prompt {
+    fn met(&self, inputs: &Inputs) -> bool {
+        match self {
+            Self::X => inputs.tap_x,
+            Self::Right => inputs.tap_right,
+            Self::Up => inputs.tap_up,
+            Self::Down => inputs.tap_down,
+        }
+    }
+
+    /// A frame where only this button was tapped, and the cursor layout is
+    /// used whatever the player's settings so every prompt means the same.
+    fn inputs(&self) -> Inputs {
+        let mut inputs = Inputs::default();
+        match self {
+            Self::X => (inputs.tap_x, inputs.press_x) = (true, true),
+            Self::Right => (inputs.tap_right, inputs.press_right) = (true, true),
+            Self::Up => (inputs.tap_up, inputs.press_up) = (true, true),
+            Self::Down => (inputs.tap_down, inputs.press_down) = (true, true),
+        }
+        inputs
+    }
+}
+
+/// One line of a tutorial script for a game `G`.
+pub struct Step<G> {
+    /// May hold button glyphs like `\x80`.
+    pub caption: &'static [u8],
+    /// The part of the screen the step is about: x, y, width and height.
+    pub highlight: (i32, i32, u32, u32),
+    pub prompt: Prompt,
+    /// Whether the game has got to where the step applies. Until it has,
+    /// the game runs on its own with every input held back.
+    pub ready: fn(&G) -> bool,
+}
+
+/// A script of steps laid over a game. Each step points out part of the
+/// screen and lets through nothing but the button it asks for.
+pub struct Tutorial<G: 'static> {
+    steps: &'static [Step<G>],
+    step: usize,
+}
+
+impl<G> Tutorial<G> {
+    pub const fn new(steps: &'static [Step<G>]) -> Self {
+        Self { steps, step: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.step >= self.steps.len()
+    }
+
+    fn showing(&self, game: &G) -> Option<&Step<G>> {
+        self.steps.get(self.step).filter(|step| (step.ready)(game))
+    }
+
+    /// The inputs the game gets this frame: the prompted button once it's
+    /// pressed, which also moves the script on, and nothing otherwise.
+    pub fn filter(&mut self, game: &G, inputs: &Inputs) -> Inputs {
+        match self.showing(game) {
+            Some(step) if step.prompt.met(inputs) => {
+                let prompted = step.prompt.inputs();
+                self.step += 1;
+                prompted
+            }
+            _ => Inputs::default(),
+        }
+    }
+
+    /// Outlines the highlight and captions it, above or below to keep it
+    /// clear.
+    pub fn draw(&self, game: &G) {
+        let Some(step) = self.showing(game) else { return };
+        let (x, y, width, height) = step.highlight;
+        unsafe { *DRAW_COLORS = 0x20; }
+        rect(x - 1, y - 1, width + 2, height + 2);
+        rect(x - 2, y - 2, width + 4, height + 4);
+        let lines = layout::wrap(step.caption, CAPTION_WIDTH).len() as i32;
+        let box_height = lines * LINE_HEIGHT + 6;
+        let box_y = if y + height as i32 / 2 >= 80 { 20 } else { 130 - box_height };
+        unsafe { *DRAW_COLORS = 0x31; }
+        rect(6, box_y, 148, box_height as u32);
+        unsafe { *DRAW_COLORS = 0x03; }
+        layout::draw_wrapped(step.caption, 80, box_y + 4, CAPTION_WIDTH, Align::Center);
+    }
+}
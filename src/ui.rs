@@ -0,0 +1,420 @@
+use crate::{layout::{self, Align}, model::{point_in, Inputs}, sfx, wasm4::*};
+
+/// The display settings drawing goes by, handed to whatever draws the way
+/// the animation speed is handed to whatever animates.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Display {
+    /// Red suits are drawn hollow and the palette trades red and yellow for
+    /// blue and orange.
+    pub colorblind: bool,
+    /// Darkens text drawn through `set_text_colors`, boxes in focused
+    /// widgets and slows flashing.
+    pub high_contrast: bool,
+}
+
+/// Sets `DRAW_COLORS` for text. In high contrast mode text without a
+/// background of its own goes a shade darker, yellow to red and red to
+/// black, so it reads clearly on the white screen.
+pub fn set_text_colors(colors: u16, display: Display) {
+    let colors = match colors & 0xf {
+        0x2 if display.high_contrast && colors & 0xf0 == 0 => colors & !0xf | 0x3,
+        0x4 if display.high_contrast && colors & 0xf0 == 0 => colors & !0xf | 0x2,
+        _ => colors,
+    };
+    unsafe { *DRAW_COLORS = colors; }
+}
+
+/// Outlines a focused line of text in high contrast mode, so focus shows by
+/// more than a change of color.
+pub fn draw_focus_box(x: i32, y: i32, width: i32, display: Display) {
+    if display.high_contrast {
+        unsafe { *DRAW_COLORS = 0x30; }
+        rect(x - 2, y - 1, width as u32 + 4, 10);
+    }
+}
+
+/// Text colors for each widget state, as `DRAW_COLORS` values.
+#[derive(Copy, Clone)]
+pub struct Style {
+    pub focused: u16,
+    pub hovered: u16,
+    pub normal: u16,
+    pub disabled: u16,
+}
+
+impl Style {
+    /// Black text on the red input bar along the bottom of a table.
+    pub const BAR: Style = Style { focused: 0x43, hovered: 0x13, normal: 0x03, disabled: 0x01 };
+    /// Red text on the white background, like the main menu.
+    pub const MENU: Style = Style { focused: 0x32, hovered: 0x42, normal: 0x02, disabled: 0x04 };
+
+    fn pick(&self, focused: bool, hovered: bool, disabled: bool) -> u16 {
+        if focused {
+            self.focused
+        } else if hovered {
+            self.hovered
+        } else if disabled {
+            self.disabled
+        } else {
+            self.normal
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Button {
+    pub text: &'static str,
+    pub disabled: bool,
+}
+
+impl Button {
+    pub const fn new(text: &'static str) -> Self {
+        Self { text, disabled: false }
+    }
+}
+
+/// Fixed-size buttons laid out in rows of `columns`. The d-pad moves focus
+/// without wrapping and a click focuses and presses in one go.
+pub struct ButtonGrid<const N: usize> {
+    pub buttons: [Button; N],
+    pub focus: usize,
+    columns: usize,
+    origin: (i32, i32),
+    /// Distance between the top left corners of neighbouring cells.
+    spacing: (i32, i32),
+    width: i32,
+    style: Style,
+}
+
+impl<const N: usize> ButtonGrid<N> {
+    pub fn new(buttons: [Button; N], columns: usize, origin: (i32, i32), spacing: (i32, i32), width: i32, style: Style) -> Self {
+        Self { buttons, focus: 0, columns, origin, spacing, width, style }
+    }
+
+    /// Top left corner of a button.
+    pub fn position(&self, index: usize) -> (i32, i32) {
+        let (x, y) = self.origin;
+        let (dx, dy) = self.spacing;
+        (x + (index % self.columns) as i32 * dx, y + (index / self.columns) as i32 * dy)
+    }
+
+    pub fn navigate(&mut self, inputs: &Inputs) {
+        let focus = self.focus;
+        let column = self.focus % self.columns;
+        if inputs.tap_right && column + 1 < self.columns && self.focus + 1 < N {
+            self.focus += 1;
+        }
+        if inputs.tap_left && column > 0 {
+            self.focus -= 1;
+        }
+        if inputs.tap_down && self.focus + self.columns < N {
+            self.focus += self.columns;
+        }
+        if inputs.tap_up && self.focus >= self.columns {
+            self.focus -= self.columns;
+        }
+        if self.focus != focus {
+            sfx::cursor_move();
+        }
+    }
+
+    /// The button clicked this frame, which also takes focus.
+    pub fn clicked(&mut self, inputs: &Inputs) -> Option<usize> {
+        let clicked = (0..N).find(|index| {
+            let (x, y) = self.position(*index);
+            inputs.clicked(x, y, self.width, 9)
+        });
+        if let Some(index) = clicked {
+            self.focus = index;
+        }
+        clicked
+    }
+
+    pub fn draw(&self, mouse: (i32, i32), display: Display) {
+        for (index, button) in self.buttons.iter().enumerate() {
+            let (x, y) = self.position(index);
+            let hovered = point_in(mouse, x, y, self.width, 9);
+            set_text_colors(self.style.pick(index == self.focus, hovered, button.disabled), display);
+            text(button.text, x, y);
+            if index == self.focus {
+                draw_focus_box(x, y, self.width, display);
+            }
+        }
+    }
+}
+
+/// A vertical list of rows where the focus wraps around at either end.
+pub struct Menu {
+    pub focus: usize,
+    pub len: usize,
+    origin: (i32, i32),
+    row_height: i32,
+    width: i32,
+    style: Style,
+}
+
+impl Menu {
+    pub fn new(len: usize, origin: (i32, i32), row_height: i32, width: i32, style: Style) -> Self {
+        Self { focus: 0, len, origin, row_height, width, style }
+    }
+
+    pub fn row_y(&self, row: usize) -> i32 {
+        self.origin.1 + self.row_height * row as i32
+    }
+
+    pub fn navigate(&mut self, inputs: &Inputs) {
+        if inputs.tap_down {
+            self.focus = (self.focus + 1) % self.len;
+        }
+        if inputs.tap_up {
+            self.focus = (self.focus + self.len - 1) % self.len;
+        }
+        if inputs.tap_down || inputs.tap_up {
+            sfx::cursor_move();
+        }
+    }
+
+    /// The row clicked this frame, which also takes focus.
+    pub fn clicked(&mut self, inputs: &Inputs) -> Option<usize> {
+        let clicked = (0..self.len).find(|row| inputs.clicked(self.origin.0, self.row_y(*row), self.width, self.row_height));
+        if let Some(row) = clicked {
+            self.focus = row;
+        }
+        clicked
+    }
+
+    /// Draws one row with the colors for its state. Labels are passed in
+    /// since they often change from frame to frame.
+    pub fn draw_row<T: AsRef<str>>(&self, row: usize, label: T, disabled: bool, mouse: (i32, i32), display: Display) {
+        let (x, _) = self.origin;
+        let y = self.row_y(row);
+        let hovered = point_in(mouse, x, y, self.width, self.row_height);
+        set_text_colors(self.style.pick(row == self.focus, hovered, disabled), display);
+        text(label, x, y);
+        if row == self.focus {
+            draw_focus_box(x, y, self.width, display);
+        }
+    }
+}
+
+/// A line of text that may contain the button glyph bytes, like `\x80` for X.
+pub struct Label {
+    pub text: &'static [u8],
+    pub x: i32,
+    pub y: i32,
+    pub align: Align,
+}
+
+impl Label {
+    pub const fn new(text: &'static [u8], x: i32, y: i32) -> Self {
+        Self { text, x, y, align: Align::Left }
+    }
+
+    /// Anchors the label by its middle or right edge at `x` instead.
+    pub const fn aligned(self, align: Align) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn draw(&self) {
+        layout::draw(self.text, self.x, self.y, self.align);
+    }
+}
+
+/// A yes/no question drawn over everything else. Whoever owns it skips its
+/// own update while the modal is open, so nothing underneath reacts to the
+/// answer's button press. Focus starts on "No" so a stray X is harmless.
+pub struct Modal {
+    message: &'static str,
+    yes: bool,
+}
+
+impl Modal {
+    const YES: (i32, i32) = (30, 80);
+    const NO: (i32, i32) = (100, 80);
+
+    pub fn new(message: &'static str) -> Self {
+        Self { message, yes: false }
+    }
+
+    /// The answer once one is given. Z always answers no.
+    pub fn update(&mut self, inputs: &Inputs) -> Option<bool> {
+        if inputs.tap_left || inputs.tap_right {
+            self.yes = !self.yes;
+            sfx::cursor_move();
+        }
+        let answer = if inputs.clicked(Self::YES.0, Self::YES.1, 24, 9) {
+            Some(true)
+        } else if inputs.clicked(Self::NO.0, Self::NO.1, 24, 9) || inputs.tap_z {
+            Some(false)
+        } else if inputs.tap_x {
+            Some(self.yes)
+        } else {
+            None
+        };
+        match answer {
+            Some(true) => sfx::confirm(),
+            Some(false) => sfx::cancel(),
+            None => {}
+        }
+        answer
+    }
+
+    pub fn draw(&self, display: Display) {
+        unsafe { *DRAW_COLORS = 0x23; }
+        rect(10, 56, 140, 38);
+        unsafe { *DRAW_COLORS = 0x01; }
+        layout::draw(self.message, 80, 64, Align::Center);
+        for ((x, y), label, focused) in [(Self::YES, "Yes", self.yes), (Self::NO, "No", !self.yes)] {
+            set_text_colors(if focused { 0x43 } else { 0x01 }, display);
+            text(label, x, y);
+            if focused {
+                draw_focus_box(x, y, 24, display);
+            }
+        }
+    }
+}
+
+pub const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// A fixed-length string typed on an on-screen keyboard. The d-pad moves
+/// over a grid of `alphabet` with an OK key at the end, X types the focused
+/// key and Z deletes the last character, or cancels once nothing is left.
+pub struct Keyboard<const N: usize> {
+    title: &'static str,
+    alphabet: &'static [u8],
+    typed: [u8; N],
+    len: usize,
+    /// Index into `alphabet`, or its length for the OK key.
+    focus: usize,
+}
+
+impl<const N: usize> Keyboard<N> {
+    const COLUMNS: usize = 10;
+    const KEY_SIZE: i32 = 12;
+    const LEFT: i32 = 20;
+    const TOP: i32 = 76;
+
+    pub fn new(title: &'static str, alphabet: &'static [u8]) -> Self {
+        Self { title, alphabet, typed: [0; N], len: 0, focus: 0 }
+    }
+
+    /// A keyboard with `typed` already filled in and OK focused, for a
+    /// suggestion that can be taken as it is or deleted and retyped.
+    pub fn filled(title: &'static str, alphabet: &'static [u8], typed: [u8; N]) -> Self {
+        Self { title, alphabet, typed, len: N, focus: alphabet.len() }
+    }
+
+    /// Every key, the OK key included.
+    fn num_keys(&self) -> usize {
+        self.alphabet.len() + 1
+    }
+
+    fn position(&self, key: usize) -> (i32, i32) {
+        let (row, column) = (key / Self::COLUMNS, key % Self::COLUMNS);
+        (Self::LEFT + Self::KEY_SIZE * column as i32, Self::TOP + Self::KEY_SIZE * row as i32)
+    }
+
+    fn navigate(&mut self, inputs: &Inputs) {
+        let focus = self.focus;
+        let num_keys = self.num_keys();
+        if inputs.tap_right {
+            self.focus = (self.focus + 1) % num_keys;
+        }
+        if inputs.tap_left {
+            self.focus = (self.focus + num_keys - 1) % num_keys;
+        }
+        // going off the top or bottom comes back in the same column at the
+        // other end, or on OK when the short last row doesn't reach it
+        let column = self.focus % Self::COLUMNS;
+        if inputs.tap_down {
+            self.focus = if self.focus + Self::COLUMNS < num_keys { self.focus + Self::COLUMNS } else { column };
+        }
+        if inputs.tap_up {
+            self.focus = match self.focus.checked_sub(Self::COLUMNS) {
+                Some(key) => key,
+                None => (column + (num_keys - 1) / Self::COLUMNS * Self::COLUMNS).min(num_keys - 1),
+            };
+        }
+        if self.focus != focus {
+            sfx::cursor_move();
+        }
+    }
+
+    /// Returns `Some(Some(text))` when confirmed and `Some(None)` when
+    /// cancelled.
+    pub fn update(&mut self, inputs: &Inputs) -> Option<Option<[u8; N]>> {
+        self.navigate(inputs);
+        let clicked = (0..self.num_keys()).find(|key| {
+            let (x, y) = self.position(*key);
+            inputs.clicked(x - 2, y - 2, Self::KEY_SIZE, Self::KEY_SIZE)
+        });
+        if let Some(key) = clicked {
+            self.focus = key;
+        }
+        if clicked.is_some() || inputs.tap_x {
+            if self.focus == self.alphabet.len() {
+                if self.len == N {
+                    sfx::confirm();
+                    return Some(Some(self.typed))
+                }
+                sfx::error();
+            } else if self.len < N {
+                sfx::confirm();
+                self.typed[self.len] = self.alphabet[self.focus];
+                self.len += 1;
+                if self.len == N {
+                    self.focus = self.alphabet.len();
+                }
+            } else {
+                sfx::error();
+            }
+        }
+        if inputs.tap_z {
+            sfx::cancel();
+            if self.len == 0 {
+                return Some(None)
+            }
+            self.len -= 1;
+        }
+        None
+    }
+
+    pub fn draw(&self, display: Display) {
+        set_text_colors(0x02, display);
+        layout::draw(self.title, 80, 40, Align::Center);
+        // what's typed so far, with a blank for every character to come
+        let left = 80 - (9 * N as i32) / 2;
+        for index in 0..N {
+            let x = left + 9 * index as i32;
+            if index < self.len {
+                set_text_colors(0x03, display);
+                layout::draw([self.typed[index]], x, 56, Align::Left);
+            } else {
+                unsafe { *DRAW_COLORS = if index == self.len { 0x02 } else { 0x03 }; }
+                hline(x, 64, 8);
+            }
+        }
+        for key in 0..self.num_keys() {
+            let (x, y) = self.position(key);
+            let focused = key == self.focus;
+            set_text_colors(if focused { 0x32 } else { 0x02 }, display);
+            if key == self.alphabet.len() {
+                text("OK", x, y);
+            } else {
+                layout::draw([self.alphabet[key]], x, y, Align::Left);
+            }
+            if focused {
+                draw_focus_box(x, y, if key == self.alphabet.len() { 16 } else { 8 }, display);
+            }
+        }
+        set_text_colors(0x02, display);
+        let t = b"\x84\x85\x86\x87: move \x80: type";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 142);
+        }
+        let t = b"\x81: delete";
+        unsafe {
+            extern_text(t.as_ptr(), t.len(), 0, 151);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{help::Page, model::{local_player, GameEvent, Inputs, Model}, money, turns::Turns, wasm4::*, BlackJack, PlayerState};
+
+const VERSUS_ROUNDS: u32 = 5;
+const VERSUS_STACK: u32 = 1_000;
+
+pub const RULES: [Page; 1] = [
+    Page {
+        title: "Versus",
+        diagram: None,
+        text: b"Two to four players take turns at one blackjack table, so everyone draws from the same shoe. Each starts with 1,000 and after five rounds the biggest stack wins. Your own bank is never touched.",
+    },
+];
+
+enum VersusPhase {
+    Setup,
+    Announce,
+    Turn,
+    Podium,
+}
+
+/// Head-to-head blackjack: players take turns at one table, so everyone
+/// draws from the same shoe, and the biggest stack after the last round
+/// wins. The player's real bank is left untouched.
+pub struct Versus {
+    table: BlackJack,
+    phase: VersusPhase,
+    turns: Turns,
+    player_state: PlayerState,
+}
+
+impl Versus {
+    pub fn new(random_seed: u64) -> Self {
+        Self {
+            table: BlackJack::new(random_seed),
+            phase: VersusPhase::Setup,
+            turns: Turns::new(VERSUS_STACK),
+            player_state: PlayerState::default(),
+        }
+    }
+
+    fn next_turn(&mut self) {
+        self.turns.pass();
+        log!(Debug, "versus", "seat {} up in round {}, banks {:?}", self.turns.turn, self.turns.round, self.turns.banks);
+        if self.turns.round == VERSUS_ROUNDS {
+            self.phase = VersusPhase::Podium;
+        } else {
+            self.phase = VersusPhase::Announce;
+        }
+    }
+}
+
+impl Model<PlayerState> for Versus {
+    fn update(&mut self, inputs: [Inputs; 4]) -> GameEvent<PlayerState> {
+        let shared_inputs = Inputs::merge(&inputs);
+        match self.phase {
+            VersusPhase::Setup => {
+                if shared_inputs.tap_up && self.turns.num_players < 4 {
+                    self.turns.num_players += 1;
+                }
+                if shared_inputs.tap_down && self.turns.num_players > 2 {
+                    self.turns.num_players -= 1;
+                }
+                if shared_inputs.tap_x {
+                    self.phase = VersusPhase::Announce;
+                }
+                if shared_inputs.tap_z {
+                    return GameEvent::Exit { state: self.player_state }
+                }
+            }
+            VersusPhase::Announce => {
+                if inputs[self.turns.turn].tap_x {
+                    // the same table is handed from seat to seat
+                    self.table.share_state(PlayerState {
+                        banks: self.turns.banks,
+                        seat: self.turns.turn,
+                        single_round: true,
+                        ..self.player_state
+                    });
+                    self.table.on_enter();
+                    self.phase = VersusPhase::Turn;
+                }
+            }
+            VersusPhase::Turn => {
+                let mut turn_inputs = inputs;
+                turn_inputs.swap(0, self.turns.turn);
+                match self.table.update(turn_inputs) {
+                    GameEvent::Exit { state } | GameEvent::SwitchTo { state, .. } => {
+                        self.table.on_exit();
+                        self.turns.banks[self.turns.turn] = state.bank();
+                        self.next_turn();
+                    }
+                    GameEvent::PlaySfx(sting) => return GameEvent::PlaySfx(sting),
+                    // the match's banks are only kept once it's over
+                    GameEvent::Continue | GameEvent::RequestSave | GameEvent::EraseSave => {}
+                }
+            }
+            VersusPhase::Podium => {
+                if shared_inputs.tap_x || shared_inputs.tap_z {
+                    let results = Some(self.turns.results(VERSUS_STACK));
+                    return GameEvent::Exit { state: PlayerState { results, ..self.player_state } }
+                }
+            }
+        }
+        GameEvent::Continue
+    }
+
+    fn draw(&self) {
+        match self.phase {
+            VersusPhase::Setup => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text("Blackjack Versus", 16, 20);
+                text(fixed_format!("Players: {}", self.turns.num_players), 32, 50);
+                text(fixed_format!("Rounds: {}", VERSUS_ROUNDS), 32, 60);
+                text(fixed_format!("Stack: {}", money::chips(VERSUS_STACK)), 32, 70);
+                let t = b"\x86\x87: players";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 142);
+                }
+                let t = b"\x80: start \x81: exit";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            VersusPhase::Announce => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text(fixed_format!("Round {}/{}", self.turns.round + 1, VERSUS_ROUNDS), 10, 20);
+                if local_player() == Some(self.turns.turn) {
+                    text("Your hand!", 10, 50);
+                } else {
+                    text(fixed_format!("Player {}'s hand", self.turns.turn + 1), 10, 50);
+                }
+                self.player_state.looks[self.turns.turn].draw(140, 50);
+                text(fixed_format!("Chips: {}", money::chips(self.turns.banks[self.turns.turn])), 10, 60);
+                let t = b"\x80: play";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+            VersusPhase::Turn => {
+                self.table.draw();
+            }
+            VersusPhase::Podium => {
+                unsafe { *DRAW_COLORS = 0x02; }
+                text("Podium", 56, 10);
+                // winner in the middle, second on the left, third on the right
+                let standings = self.turns.standings();
+                for (place, (x, height)) in [(60, 50), (20, 35), (100, 20)].into_iter().enumerate() {
+                    let Some(&player) = standings.get(place) else { continue };
+                    let top = 120 - height;
+                    unsafe { *DRAW_COLORS = if place == 0 { 0x34 } else { 0x32 }; }
+                    rect(x, top, 40, height as u32);
+                    unsafe { *DRAW_COLORS = 0x02; }
+                    text(fixed_format!("P{}", player + 1), x + 12, top - 20);
+                    self.player_state.looks[player].draw(x + 16, top - 30);
+                    text(money::compact(self.turns.banks[player]), x, top - 10);
+                    unsafe { *DRAW_COLORS = 0x01; }
+                    text(fixed_format!("{}", place + 1), x + 16, top + 4);
+                }
+                if let Some(&fourth) = standings.get(3) {
+                    unsafe { *DRAW_COLORS = 0x02; }
+                    text(fixed_format!("4. P{} {}", fourth + 1, money::chips(self.turns.banks[fourth])), 20, 126);
+                    self.player_state.looks[fourth].draw(8, 126);
+                }
+                unsafe { *DRAW_COLORS = 0x02; }
+                let t = b"\x80: done";
+                unsafe {
+                    extern_text(t.as_ptr(), t.len(), 0, 151);
+                }
+            }
+        }
+    }
+
+    fn share_state(&mut self, state: PlayerState) {
+        self.player_state = state;
+        if let VersusPhase::Setup = self.phase {
+            self.turns.num_players = state.num_players().max(2);
+        }
+    }
+
+    fn state_name(&self) -> &'static str {
+        match self.phase {
+            VersusPhase::Setup => "Setup",
+            VersusPhase::Announce => "Announce",
+            VersusPhase::Turn => self.table.state_name(),
+            VersusPhase::Podium => "Podium",
+        }
+    }
+
+    fn cards_left(&self) -> Option<usize> {
+        self.table.cards_left()
+    }
+
+    // a turn in play is left, paused or picked up along with the match
+
+    fn on_exit(&mut self) {
+        if let VersusPhase::Turn = self.phase {
+            self.table.on_exit();
+        }
+    }
+
+    fn on_pause(&mut self) {
+        if let VersusPhase::Turn = self.phase {
+            self.table.on_pause();
+        }
+    }
+
+    fn on_resume(&mut self) {
+        if let VersusPhase::Turn = self.phase {
+            self.table.on_resume();
+        }
+    }
+}
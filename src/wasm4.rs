@@ -3,6 +3,8 @@
 
 #![allow(unused)]
 
+use crate::platform::{Current, Platform};
+
 // ┌───────────────────────────────────────────────────────────────────────────┐
 // │                                                                           │
 // │ Platform Constants                                                        │
@@ -17,18 +19,27 @@ pub const SCREEN_SIZE: u32 = 160;
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
-pub const PALETTE: *mut [u32; 4] = 0x04 as *mut [u32; 4];
-pub const DRAW_COLORS: *mut u16 = 0x14 as *mut u16;
-pub const GAMEPAD1: *const u8 = 0x16 as *const u8;
-pub const GAMEPAD2: *const u8 = 0x17 as *const u8;
-pub const GAMEPAD3: *const u8 = 0x18 as *const u8;
-pub const GAMEPAD4: *const u8 = 0x19 as *const u8;
-pub const MOUSE_X: *const i16 = 0x1a as *const i16;
-pub const MOUSE_Y: *const i16 = 0x1c as *const i16;
-pub const MOUSE_BUTTONS: *const u8 = 0x1e as *const u8;
-pub const SYSTEM_FLAGS: *mut u8 = 0x1f as *mut u8;
-pub const NETPLAY: *const u8 = 0x20 as *const u8;
-pub const FRAMEBUFFER: *mut [u8; 6400] = 0xa0 as *mut [u8; 6400];
+/// Where a register or the framebuffer sits in the console's memory, or in
+/// the headless copy of it off the console.
+const fn address<T>(offset: usize) -> *mut T {
+    #[cfg(target_family = "wasm")]
+    return offset as *mut T;
+    #[cfg(not(target_family = "wasm"))]
+    crate::headless::MEMORY.wrapping_add(offset).cast()
+}
+
+pub const PALETTE: *mut [u32; 4] = address(0x04);
+pub const DRAW_COLORS: *mut u16 = address(0x14);
+pub const GAMEPAD1: *const u8 = address(0x16);
+pub const GAMEPAD2: *const u8 = address(0x17);
+pub const GAMEPAD3: *const u8 = address(0x18);
+pub const GAMEPAD4: *const u8 = address(0x19);
+pub const MOUSE_X: *const i16 = address(0x1a);
+pub const MOUSE_Y: *const i16 = address(0x1c);
+pub const MOUSE_BUTTONS: *const u8 = address(0x1e);
+pub const SYSTEM_FLAGS: *mut u8 = address(0x1f);
+pub const NETPLAY: *const u8 = address(0x20);
+pub const FRAMEBUFFER: *mut [u8; 6400] = address(0xa0);
 
 pub const BUTTON_1: u8 = 1;
 pub const BUTTON_2: u8 = 2;
@@ -52,11 +63,7 @@ pub const SYSTEM_HIDE_GAMEPAD_OVERLAY: u8 = 2;
 
 /// Copies pixels to the framebuffer.
 pub fn blit(sprite: &[u8], x: i32, y: i32, width: u32, height: u32, flags: u32) {
-    unsafe { extern_blit(sprite.as_ptr(), x, y, width, height, flags) }
-}
-extern "C" {
-    #[link_name = "blit"]
-    fn extern_blit(sprite: *const u8, x: i32, y: i32, width: u32, height: u32, flags: u32);
+    Current::blit(sprite, x, y, width, height, flags)
 }
 
 /// Copies a subregion within a larger sprite atlas to the framebuffer.
@@ -72,33 +79,7 @@ pub fn blit_sub(
     stride: u32,
     flags: u32,
 ) {
-    unsafe {
-        extern_blit_sub(
-            sprite.as_ptr(),
-            x,
-            y,
-            width,
-            height,
-            src_x,
-            src_y,
-            stride,
-            flags,
-        )
-    }
-}
-extern "C" {
-    #[link_name = "blitSub"]
-    fn extern_blit_sub(
-        sprite: *const u8,
-        x: i32,
-        y: i32,
-        width: u32,
-        height: u32,
-        src_x: u32,
-        src_y: u32,
-        stride: u32,
-        flags: u32,
-    );
+    Current::blit_sub(sprite, x, y, width, height, src_x, src_y, stride, flags)
 }
 
 pub const BLIT_2BPP: u32 = 1;
@@ -109,63 +90,38 @@ pub const BLIT_ROTATE: u32 = 8;
 
 /// Draws a line between two points.
 pub fn line(x1: i32, y1: i32, x2: i32, y2: i32) {
-    unsafe { extern_line(x1, y1, x2, y2) }
-}
-extern "C" {
-    #[link_name = "line"]
-    fn extern_line(x1: i32, y1: i32, x2: i32, y2: i32);
+    Current::line(x1, y1, x2, y2)
 }
 
 /// Draws an oval (or circle).
 pub fn oval(x: i32, y: i32, width: u32, height: u32) {
-    unsafe { extern_oval(x, y, width, height) }
-}
-extern "C" {
-    #[link_name = "oval"]
-    fn extern_oval(x: i32, y: i32, width: u32, height: u32);
+    Current::oval(x, y, width, height)
 }
 
 /// Draws a rectangle.
 pub fn rect(x: i32, y: i32, width: u32, height: u32) {
-    unsafe { extern_rect(x, y, width, height) }
-}
-extern "C" {
-    #[link_name = "rect"]
-    fn extern_rect(x: i32, y: i32, width: u32, height: u32);
+    Current::rect(x, y, width, height)
 }
 
 /// Draws text using the built-in system font.
 pub fn text<T: AsRef<str>>(text: T, x: i32, y: i32) {
-    let text_ref = text.as_ref();
-    unsafe { extern_text(text_ref.as_ptr(), text_ref.len(), x, y) }
+    Current::text(text.as_ref().as_bytes(), x, y)
 }
-extern "C" {
-    #[link_name = "textUtf8"]
-    pub fn extern_text(text: *const u8, length: usize, x: i32, y: i32);
+
+/// Draws `length` bytes of text from `text`, for strings using the font's
+/// button glyphs, which aren't valid UTF-8.
+pub unsafe fn extern_text(text: *const u8, length: usize, x: i32, y: i32) {
+    Current::text(core::slice::from_raw_parts(text, length), x, y)
 }
 
 /// Draws a vertical line
 pub fn vline(x: i32, y: i32, len: u32) {
-    unsafe {
-        extern_vline(x, y, len);
-    }
-}
-
-extern "C" {
-    #[link_name = "vline"]
-    fn extern_vline(x: i32, y: i32, len: u32);
+    Current::vline(x, y, len)
 }
 
 /// Draws a horizontal line
 pub fn hline(x: i32, y: i32, len: u32) {
-    unsafe {
-        extern_hline(x, y, len);
-    }
-}
-
-extern "C" {
-    #[link_name = "hline"]
-    fn extern_hline(x: i32, y: i32, len: u32);
+    Current::hline(x, y, len)
 }
 
 // ┌───────────────────────────────────────────────────────────────────────────┐
@@ -176,11 +132,7 @@ extern "C" {
 
 /// Plays a sound tone.
 pub fn tone(frequency: u32, duration: u32, volume: u32, flags: u32) {
-    unsafe { extern_tone(frequency, duration, volume, flags) }
-}
-extern "C" {
-    #[link_name = "tone"]
-    fn extern_tone(frequency: u32, duration: u32, volume: u32, flags: u32);
+    Current::tone(frequency, duration, volume, flags)
 }
 
 pub const TONE_PULSE1: u32 = 0;
@@ -200,12 +152,14 @@ pub const TONE_PAN_RIGHT: u32 = 32;
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
-extern "C" {
-    /// Reads up to `size` bytes from persistent storage into the pointer `dest`.
-    pub fn diskr(dest: *mut u8, size: u32) -> u32;
+/// Reads up to `size` bytes from persistent storage into the pointer `dest`.
+pub unsafe fn diskr(dest: *mut u8, size: u32) -> u32 {
+    Current::diskr(core::slice::from_raw_parts_mut(dest, size as usize))
+}
 
-    /// Writes up to `size` bytes from the pointer `src` into persistent storage.
-    pub fn diskw(src: *const u8, size: u32) -> u32;
+/// Writes up to `size` bytes from the pointer `src` into persistent storage.
+pub unsafe fn diskw(src: *const u8, size: u32) -> u32 {
+    Current::diskw(core::slice::from_raw_parts(src, size as usize))
 }
 
 // ┌───────────────────────────────────────────────────────────────────────────┐
@@ -216,10 +170,5 @@ extern "C" {
 
 /// Prints a message to the debug console.
 pub fn trace<T: AsRef<str>>(text: T) {
-    let text_ref = text.as_ref();
-    unsafe { extern_trace(text_ref.as_ptr(), text_ref.len()) }
-}
-extern "C" {
-    #[link_name = "traceUtf8"]
-    fn extern_trace(trace: *const u8, length: usize);
+    Current::trace(text.as_ref())
 }